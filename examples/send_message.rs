@@ -50,6 +50,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let message_input = SendMessageInput {
             text: "Hello".to_string(),
             reply_to_id: None,
+            idempotency_key: None,
+            mentions: Vec::new(),
         };
 
         // Send the message
@@ -0,0 +1,50 @@
+use beeper_desktop_api::BeeperClient;
+use beeper_desktop_api::events::Event;
+use std::env;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Get authentication token from environment variable
+    let token = env::var("BEEPER_TOKEN")
+        .unwrap_or_else(|_| {
+            eprintln!("Error: BEEPER_TOKEN environment variable not set");
+            eprintln!("Usage: BEEPER_TOKEN=your_token cargo run --example watch_events");
+            std::process::exit(1);
+        });
+
+    // Get API base URL from environment variable or use default
+    let base_url = env::var("BEEPER_API_URL")
+        .unwrap_or_else(|_| "http://localhost:23373".to_string());
+
+    println!("Connecting to Beeper Desktop API at: {}", base_url);
+    println!();
+
+    // Create a client with the provided token
+    let client = BeeperClient::new(&token, &base_url);
+
+    // Poll for new activity every 3 seconds
+    let mut events = client.events().with_interval(Duration::from_secs(3));
+
+    println!("👀 Watching for new messages (Ctrl+C to stop)...");
+    println!();
+
+    loop {
+        match events.next_event().await? {
+            Event::Message(message) => {
+                println!(
+                    "📨 [{}] {}: {}",
+                    message.timestamp,
+                    message.sender_name.as_deref().unwrap_or(&message.sender_id),
+                    message.text.as_deref().unwrap_or("[No text]")
+                );
+            }
+            Event::Reaction { message_id, reaction, .. } => {
+                println!("👍 {} reacted {} to {}", reaction.participant_id, reaction.reaction_key, message_id);
+            }
+            Event::ChatUpdated(chat) => {
+                println!("💬 Activity in '{}'", chat.display_name());
+            }
+        }
+    }
+}
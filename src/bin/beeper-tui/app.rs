@@ -0,0 +1,614 @@
+//! Application state and key handling for the terminal UI
+
+use std::cmp::Ordering;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use beeper_desktop_api::events::Event;
+use beeper_desktop_api::{compare_sort_keys, Chat, ListChatsOutput, ListMessagesOutput, Message, SendMessageInput};
+
+/// Which part of the screen receives key presses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Chats,
+    Messages,
+    Compose,
+    Search,
+}
+
+/// Work the main loop has to perform against the API
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Load the inbox, or the page of chats before `cursor`
+    LoadChats { cursor: Option<String> },
+    /// Load the latest messages of a chat
+    OpenChat(String),
+    /// Reload the latest messages of the open chat
+    RefreshMessages(String),
+    /// Load messages older than the oldest loaded one
+    LoadOlderMessages { chat_id: String, cursor: String },
+    /// Send the composed message
+    Send { chat_id: String, input: SendMessageInput },
+    /// Search chats
+    Search(String),
+    /// Jump to a chat or message in Beeper Desktop
+    FocusDesktop { chat_id: String, message_id: Option<String> },
+}
+
+/// Result of an [`Action`] or of a poll, sent back to the main loop
+#[derive(Debug)]
+pub enum Outcome {
+    Chats { output: ListChatsOutput, more: bool },
+    SearchResults { query: String, chats: Vec<Chat> },
+    Messages { chat_id: String, output: ListMessagesOutput },
+    OlderMessages { chat_id: String, output: ListMessagesOutput },
+    NewMessages { chat_id: String, messages: Vec<Message> },
+    Sent { chat_id: String, messages: Vec<Message> },
+    Focused,
+    Events(Vec<Event>),
+    Failed(String),
+}
+
+pub struct App {
+    pub chats: Vec<Chat>,
+    pub chats_cursor: Option<String>,
+    pub chats_has_more: bool,
+    pub selected_chat: usize,
+    /// Messages of the open chat, oldest first
+    pub messages: Vec<Message>,
+    pub messages_has_more: bool,
+    pub selected_message: Option<usize>,
+    pub open_chat_id: Option<String>,
+    pub compose: String,
+    pub reply_to: Option<Message>,
+    pub search: String,
+    pub showing_search_results: bool,
+    pub focus: Focus,
+    pub status: String,
+    pub should_quit: bool,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            chats: Vec::new(),
+            chats_cursor: None,
+            chats_has_more: false,
+            selected_chat: 0,
+            messages: Vec::new(),
+            messages_has_more: false,
+            selected_message: None,
+            open_chat_id: None,
+            compose: String::new(),
+            reply_to: None,
+            search: String::new(),
+            showing_search_results: false,
+            focus: Focus::Chats,
+            status: "Loading chats…".to_string(),
+            should_quit: false,
+        }
+    }
+
+    pub fn selected_chat(&self) -> Option<&Chat> {
+        self.chats.get(self.selected_chat)
+    }
+
+    pub fn open_chat(&self) -> Option<&Chat> {
+        let id = self.open_chat_id.as_deref()?;
+        self.chats.iter().find(|c| c.id == id)
+    }
+
+    pub fn selected_message(&self) -> Option<&Message> {
+        self.selected_message.and_then(|i| self.messages.get(i))
+    }
+
+    /// Replaces or extends the chat list, keeping it sorted by last activity
+    pub fn set_chats(&mut self, chats: Vec<Chat>, append: bool) {
+        if append {
+            for chat in chats {
+                if !self.chats.iter().any(|c| c.id == chat.id) {
+                    self.chats.push(chat);
+                }
+            }
+        } else {
+            self.chats = chats;
+        }
+        sort_chats_by_activity(&mut self.chats);
+        if self.selected_chat >= self.chats.len() {
+            self.selected_chat = self.chats.len().saturating_sub(1);
+        }
+    }
+
+    /// Updates a single chat in place (e.g. after new activity)
+    pub fn upsert_chat(&mut self, chat: Chat) {
+        let selected_id = self.selected_chat().map(|c| c.id.clone());
+        match self.chats.iter_mut().find(|c| c.id == chat.id) {
+            Some(existing) => *existing = chat,
+            None => self.chats.push(chat),
+        }
+        sort_chats_by_activity(&mut self.chats);
+        if let Some(id) = selected_id {
+            if let Some(index) = self.chats.iter().position(|c| c.id == id) {
+                self.selected_chat = index;
+            }
+        }
+    }
+
+    /// Shows the latest page of a freshly opened chat
+    pub fn set_messages(&mut self, chat_id: String, messages: Vec<Message>, has_more: bool) {
+        self.open_chat_id = Some(chat_id);
+        self.messages = merge_messages(Vec::new(), messages);
+        self.messages_has_more = has_more;
+        self.selected_message = self.messages.len().checked_sub(1);
+        self.reply_to = None;
+    }
+
+    /// Prepends an older page, keeping the same message selected
+    pub fn prepend_messages(&mut self, older: Vec<Message>, has_more: bool) {
+        let selected_id = self.selected_message().map(|m| m.id.clone());
+        self.messages = merge_messages(std::mem::take(&mut self.messages), older);
+        self.messages_has_more = has_more;
+        self.selected_message = selected_id
+            .and_then(|id| self.messages.iter().position(|m| m.id == id))
+            .or(self.messages.len().checked_sub(1));
+    }
+
+    /// Appends messages that arrived while the chat is open
+    pub fn append_messages(&mut self, newer: Vec<Message>) {
+        let follow = self.selected_message.map(|i| i + 1 == self.messages.len()).unwrap_or(true);
+        self.messages = merge_messages(std::mem::take(&mut self.messages), newer);
+        if follow {
+            self.selected_message = self.messages.len().checked_sub(1);
+        }
+    }
+
+    /// Shows that `action` is in progress while it runs in the background
+    pub fn begin(&mut self, action: &Action) {
+        self.status = match action {
+            Action::LoadOlderMessages { .. } => "Loading older messages…",
+            Action::Send { .. } => "Sending…",
+            Action::RefreshMessages(_) => return,
+            _ => "Loading…",
+        }
+        .to_string();
+    }
+
+    /// Applies the result of an action or poll; returns a follow-up action if one is needed
+    pub fn apply(&mut self, outcome: Outcome) -> Option<Action> {
+        match outcome {
+            Outcome::Chats { output, more } => {
+                self.chats_cursor = output.oldest_cursor;
+                self.chats_has_more = output.has_more;
+                self.showing_search_results = false;
+                self.set_chats(output.items, more);
+                self.status = format!("{} chats", self.chats.len());
+            }
+            Outcome::SearchResults { query, chats } => {
+                self.status = format!("{} chats match \"{}\"", chats.len(), query);
+                self.showing_search_results = true;
+                self.selected_chat = 0;
+                self.set_chats(chats, false);
+            }
+            // Results for a chat that was closed in the meantime are dropped
+            Outcome::Messages { chat_id, output } => {
+                if self.open_chat_id.as_deref() == Some(chat_id.as_str()) {
+                    self.set_messages(chat_id, output.items, output.has_more);
+                    self.status = "Ready".to_string();
+                }
+            }
+            Outcome::OlderMessages { chat_id, output } => {
+                if self.open_chat_id.as_deref() == Some(chat_id.as_str()) {
+                    let count = output.items.len();
+                    self.prepend_messages(output.items, output.has_more);
+                    self.status = format!("Loaded {} older messages", count);
+                }
+            }
+            Outcome::NewMessages { chat_id, messages } => {
+                if self.open_chat_id.as_deref() == Some(chat_id.as_str()) {
+                    self.append_messages(messages);
+                }
+            }
+            Outcome::Sent { chat_id, messages } => {
+                self.status = "Message sent".to_string();
+                if self.open_chat_id.as_deref() == Some(chat_id.as_str()) {
+                    self.append_messages(messages);
+                }
+            }
+            Outcome::Focused => self.status = "Opened in Beeper Desktop".to_string(),
+            Outcome::Events(events) => {
+                let mut refresh = None;
+                for event in events {
+                    refresh = self.apply_event(event).or(refresh);
+                }
+                return refresh;
+            }
+            Outcome::Failed(error) => self.status = format!("Error: {}", error),
+        }
+        None
+    }
+
+    fn apply_event(&mut self, event: Event) -> Option<Action> {
+        match event {
+            Event::ChatUpdated(chat) => {
+                if !self.showing_search_results {
+                    self.upsert_chat(chat);
+                }
+            }
+            Event::Message(message) => {
+                if self.open_chat_id.as_deref() == Some(message.chat_id.as_str()) {
+                    self.append_messages(vec![message]);
+                }
+            }
+            Event::Reaction { chat_id, .. } => {
+                if self.open_chat_id.as_deref() == Some(chat_id.as_str()) {
+                    return Some(Action::RefreshMessages(chat_id));
+                }
+            }
+        }
+        None
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.should_quit = true;
+            return None;
+        }
+
+        match self.focus {
+            Focus::Chats => self.handle_chats_key(key),
+            Focus::Messages => self.handle_messages_key(key),
+            Focus::Compose => self.handle_compose_key(key),
+            Focus::Search => self.handle_search_key(key),
+        }
+    }
+
+    fn handle_chats_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('q') => {
+                self.should_quit = true;
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if self.selected_chat + 1 < self.chats.len() {
+                    self.selected_chat += 1;
+                    None
+                } else if self.chats_has_more && !self.showing_search_results {
+                    self.chats_cursor.clone().map(|cursor| Action::LoadChats { cursor: Some(cursor) })
+                } else {
+                    None
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected_chat = self.selected_chat.saturating_sub(1);
+                None
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Tab => {
+                let chat_id = self.selected_chat()?.id.clone();
+                self.focus = Focus::Messages;
+                self.set_messages(chat_id.clone(), Vec::new(), false);
+                Some(Action::OpenChat(chat_id))
+            }
+            KeyCode::Char('/') => {
+                self.focus = Focus::Search;
+                self.search.clear();
+                None
+            }
+            KeyCode::Esc if self.showing_search_results => Some(self.clear_search()),
+            KeyCode::Char('o') => self.selected_chat().map(|chat| Action::FocusDesktop {
+                chat_id: chat.id.clone(),
+                message_id: None,
+            }),
+            _ => None,
+        }
+    }
+
+    fn handle_messages_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('q') => {
+                self.should_quit = true;
+                None
+            }
+            KeyCode::Esc | KeyCode::Left | KeyCode::Tab => {
+                self.focus = Focus::Chats;
+                None
+            }
+            KeyCode::Up | KeyCode::Char('k') => match self.selected_message {
+                Some(0) | None => self.older_messages_action(),
+                Some(i) => {
+                    self.selected_message = Some(i - 1);
+                    None
+                }
+            },
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(i) = self.selected_message {
+                    if i + 1 < self.messages.len() {
+                        self.selected_message = Some(i + 1);
+                    }
+                }
+                None
+            }
+            KeyCode::Char('i') | KeyCode::Enter => {
+                self.focus = Focus::Compose;
+                None
+            }
+            KeyCode::Char('r') => {
+                self.reply_to = self.selected_message().cloned();
+                self.focus = Focus::Compose;
+                None
+            }
+            KeyCode::Char('o') => {
+                let chat_id = self.open_chat_id.clone()?;
+                Some(Action::FocusDesktop {
+                    chat_id,
+                    message_id: self.selected_message().map(|m| m.id.clone()),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_compose_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Esc => {
+                if self.reply_to.take().is_none() {
+                    self.focus = Focus::Messages;
+                }
+                None
+            }
+            KeyCode::Enter => {
+                let chat_id = self.open_chat_id.clone()?;
+                if self.compose.trim().is_empty() {
+                    return None;
+                }
+                let input = SendMessageInput {
+                    text: std::mem::take(&mut self.compose),
+                    reply_to_id: self.reply_to.take().map(|m| m.id),
+                    idempotency_key: None,
+                    mentions: Vec::new(),
+                };
+                Some(Action::Send { chat_id, input })
+            }
+            KeyCode::Backspace => {
+                self.compose.pop();
+                None
+            }
+            KeyCode::Char(c) => {
+                self.compose.push(c);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Esc => {
+                self.focus = Focus::Chats;
+                self.search.clear();
+                None
+            }
+            KeyCode::Enter => {
+                self.focus = Focus::Chats;
+                let query = self.search.trim().to_string();
+                if query.is_empty() {
+                    Some(self.clear_search())
+                } else {
+                    Some(Action::Search(query))
+                }
+            }
+            KeyCode::Backspace => {
+                self.search.pop();
+                None
+            }
+            KeyCode::Char(c) => {
+                self.search.push(c);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns from search results to the inbox
+    fn clear_search(&mut self) -> Action {
+        self.selected_chat = 0;
+        Action::LoadChats { cursor: None }
+    }
+
+    fn older_messages_action(&self) -> Option<Action> {
+        if !self.messages_has_more {
+            return None;
+        }
+        let chat_id = self.open_chat_id.clone()?;
+        let cursor = self.messages.first()?.sort_key.clone();
+        Some(Action::LoadOlderMessages { chat_id, cursor })
+    }
+}
+
+/// Sorts chats by last activity, most recent first; pinned chats stay on top
+pub fn sort_chats_by_activity(chats: &mut [Chat]) {
+    chats.sort_by(|a, b| {
+        b.is_pinned
+            .cmp(&a.is_pinned)
+            .then_with(|| match (&a.last_activity, &b.last_activity) {
+                (Some(a), Some(b)) => b.cmp(a),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+    });
+}
+
+/// Merges two message lists, dropping duplicates and ordering oldest first
+pub fn merge_messages(mut existing: Vec<Message>, incoming: Vec<Message>) -> Vec<Message> {
+    for message in incoming {
+        match existing.iter_mut().find(|m| m.id == message.id) {
+            Some(current) => *current = message,
+            None => existing.push(message),
+        }
+    }
+    existing.sort_by(|a, b| compare_sort_keys(&a.sort_key, &b.sort_key));
+    existing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beeper_desktop_api::Participants;
+
+    fn chat(id: &str, last_activity: Option<&str>, pinned: bool) -> Chat {
+        Chat {
+            id: id.to_string(),
+            local_chat_id: None,
+            account_id: "whatsapp".to_string(),
+            network: "WhatsApp".to_string(),
+            title: id.to_string(),
+            chat_type: "single".to_string(),
+            participants: Participants {
+                items: vec![],
+                has_more: false,
+                total: 0,
+            },
+            last_activity: last_activity.map(str::to_string),
+            unread_count: 0,
+            last_read_message_sort_key: None,
+            is_archived: false,
+            is_muted: false,
+            is_pinned: pinned,
+            is_low_priority: None,
+            preview: None,
+        }
+    }
+
+    fn message(id: &str, sort_key: &str) -> Message {
+        Message {
+            id: id.to_string(),
+            chat_id: "chat-1".to_string(),
+            account_id: None,
+            sender_id: "user-1".to_string(),
+            sender_name: None,
+            text: Some(id.to_string()),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            sort_key: sort_key.to_string(),
+            is_edited: None,
+            attachments: None,
+            is_unread: None,
+            reactions: None,
+            reply_to_id: None,
+            is_sender: None,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_sort_chats_by_activity() {
+        let mut chats = vec![
+            chat("old", Some("2025-01-01T00:00:00Z"), false),
+            chat("none", None, false),
+            chat("new", Some("2025-02-01T00:00:00Z"), false),
+            chat("pinned", Some("2024-01-01T00:00:00Z"), true),
+        ];
+        sort_chats_by_activity(&mut chats);
+        let ids: Vec<&str> = chats.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["pinned", "new", "old", "none"]);
+    }
+
+    #[test]
+    fn test_prepend_keeps_selection_and_order() {
+        let mut app = App::new();
+        app.set_messages("chat-1".to_string(), vec![message("m3", "30"), message("m2", "20")], true);
+        assert_eq!(app.selected_message().map(|m| m.id.as_str()), Some("m3"));
+
+        app.selected_message = Some(0);
+        app.prepend_messages(vec![message("m1", "10"), message("m2", "20")], false);
+
+        let ids: Vec<&str> = app.messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m2", "m3"]);
+        assert_eq!(app.selected_message().map(|m| m.id.as_str()), Some("m2"));
+        assert!(!app.messages_has_more);
+    }
+
+    #[test]
+    fn test_up_at_top_requests_older_page() {
+        let mut app = App::new();
+        app.focus = Focus::Messages;
+        app.set_messages("chat-1".to_string(), vec![message("m2", "20")], true);
+
+        let action = app.handle_key(key(KeyCode::Up));
+        assert!(matches!(
+            action,
+            Some(Action::LoadOlderMessages { chat_id, cursor }) if chat_id == "chat-1" && cursor == "20"
+        ));
+    }
+
+    #[test]
+    fn test_reply_and_send() {
+        let mut app = App::new();
+        app.focus = Focus::Messages;
+        app.set_messages("chat-1".to_string(), vec![message("m1", "10")], false);
+
+        app.handle_key(key(KeyCode::Char('r')));
+        assert_eq!(app.focus, Focus::Compose);
+        app.handle_key(key(KeyCode::Char('h')));
+        app.handle_key(key(KeyCode::Char('i')));
+
+        match app.handle_key(key(KeyCode::Enter)) {
+            Some(Action::Send { chat_id, input }) => {
+                assert_eq!(chat_id, "chat-1");
+                assert_eq!(input.text, "hi");
+                assert_eq!(input.reply_to_id.as_deref(), Some("m1"));
+            }
+            other => panic!("Expected send action, got {:?}", other),
+        }
+        assert!(app.compose.is_empty());
+        assert!(app.reply_to.is_none());
+    }
+
+    #[test]
+    fn test_search_flow() {
+        let mut app = App::new();
+        app.handle_key(key(KeyCode::Char('/')));
+        assert_eq!(app.focus, Focus::Search);
+        app.handle_key(key(KeyCode::Char('a')));
+        assert!(matches!(app.handle_key(key(KeyCode::Enter)), Some(Action::Search(q)) if q == "a"));
+        assert_eq!(app.focus, Focus::Chats);
+    }
+
+    #[test]
+    fn test_focus_desktop_from_messages() {
+        let mut app = App::new();
+        app.focus = Focus::Messages;
+        app.set_messages("chat-1".to_string(), vec![message("m1", "10")], false);
+        assert!(matches!(
+            app.handle_key(key(KeyCode::Char('o'))),
+            Some(Action::FocusDesktop { chat_id, message_id })
+                if chat_id == "chat-1" && message_id.as_deref() == Some("m1")
+        ));
+    }
+
+    #[test]
+    fn test_outcomes_for_other_chats_are_dropped() {
+        let mut app = App::new();
+        app.chats = vec![chat("chat-1", None, false), chat("chat-2", None, false)];
+        app.handle_key(key(KeyCode::Enter));
+        assert_eq!(app.open_chat_id.as_deref(), Some("chat-1"));
+
+        let output = ListMessagesOutput { items: vec![message("m1", "10")], has_more: false };
+        assert!(app.apply(Outcome::Messages { chat_id: "chat-2".to_string(), output }).is_none());
+        assert!(app.messages.is_empty());
+
+        let output = ListMessagesOutput { items: vec![message("m1", "10")], has_more: false };
+        app.apply(Outcome::Messages { chat_id: "chat-1".to_string(), output });
+        assert_eq!(app.messages.len(), 1);
+
+        let reaction = Event::Reaction {
+            chat_id: "chat-1".to_string(),
+            message_id: "m1".to_string(),
+            reaction: serde_json::from_value(serde_json::json!({"id": "r1", "reactionKey": "👍", "participantID": "u2"})).unwrap(),
+        };
+        assert!(matches!(
+            app.apply(Outcome::Events(vec![reaction])),
+            Some(Action::RefreshMessages(chat_id)) if chat_id == "chat-1"
+        ));
+    }
+}
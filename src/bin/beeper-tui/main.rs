@@ -0,0 +1,174 @@
+//! `beeper-tui` terminal chat client for Beeper Desktop API
+//!
+//! A unified inbox: chats on the left sorted by activity, the open conversation on the right,
+//! and a compose box underneath. New messages are picked up with the event poller.
+
+#[path = "../beeper/config.rs"]
+mod config;
+mod app;
+mod ui;
+
+use std::path::PathBuf;
+use std::time::Duration;
+use clap::Parser;
+use ratatui::crossterm::event::{self, Event as TermEvent, KeyEvent, KeyEventKind};
+use tokio::sync::mpsc;
+use beeper_desktop_api::{BeeperClient, FocusAppInput, Result};
+use app::{Action, App, Outcome};
+
+#[derive(Debug, Parser)]
+#[command(name = "beeper-tui", version, about = "Terminal chat client for Beeper Desktop API")]
+struct Cli {
+    /// Bearer token (defaults to BEEPER_TOKEN or the config file)
+    #[arg(long, env = "BEEPER_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Base URL of the Beeper Desktop API server
+    #[arg(long, env = "BEEPER_API_URL")]
+    url: Option<String>,
+
+    /// Path to a TOML config file with `token` and `url` keys
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Seconds between checks for new messages
+    #[arg(long, default_value_t = 3)]
+    interval: u64,
+}
+
+/// Performs an action against the API; runs on its own task so the UI keeps drawing
+async fn perform(client: &BeeperClient, action: Action) -> Result<Outcome> {
+    match action {
+        Action::LoadChats { cursor } => {
+            let direction = cursor.as_ref().map(|_| "before");
+            let output = client.list_chats(cursor.as_deref(), direction).await?;
+            Ok(Outcome::Chats { output, more: cursor.is_some() })
+        }
+        Action::Search(query) => {
+            let output = client.search_chats(&query, None, None).await?;
+            Ok(Outcome::SearchResults { query, chats: output.items })
+        }
+        Action::OpenChat(chat_id) => {
+            let output = client.list_messages(&chat_id, None, None).await?;
+            Ok(Outcome::Messages { chat_id, output })
+        }
+        Action::RefreshMessages(chat_id) => {
+            let output = client.list_messages(&chat_id, None, None).await?;
+            Ok(Outcome::NewMessages { chat_id, messages: output.items })
+        }
+        Action::LoadOlderMessages { chat_id, cursor } => {
+            let output = client.list_messages(&chat_id, Some(&cursor), Some("before")).await?;
+            Ok(Outcome::OlderMessages { chat_id, output })
+        }
+        Action::Send { chat_id, input } => {
+            client.send_message(&chat_id, input).await?;
+            let output = client.list_messages(&chat_id, None, None).await?;
+            Ok(Outcome::Sent { chat_id, messages: output.items })
+        }
+        Action::FocusDesktop { chat_id, message_id } => {
+            client
+                .focus_app(Some(FocusAppInput {
+                    chat_id: Some(chat_id),
+                    message_id,
+                    draft: None,
+                }))
+                .await?;
+            Ok(Outcome::Focused)
+        }
+    }
+}
+
+/// Starts `action` in the background; its outcome arrives on `outcomes`
+fn dispatch(client: &BeeperClient, app: &mut App, outcomes: &mpsc::UnboundedSender<Outcome>, action: Action) {
+    app.begin(&action);
+    let client = client.clone();
+    let outcomes = outcomes.clone();
+    tokio::spawn(async move {
+        let outcome = perform(&client, action).await.unwrap_or_else(|e| Outcome::Failed(e.to_string()));
+        let _ = outcomes.send(outcome);
+    });
+}
+
+/// Polls for new events every `interval` until the UI goes away
+fn spawn_poller(client: &BeeperClient, interval: Duration, outcomes: mpsc::UnboundedSender<Outcome>) {
+    let mut poller = client.events().with_interval(interval);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let outcome = match poller.poll().await {
+                Ok(events) => Outcome::Events(events),
+                Err(e) => Outcome::Failed(e.to_string()),
+            };
+            if outcomes.send(outcome).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn spawn_key_reader() -> mpsc::UnboundedReceiver<KeyEvent> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if let TermEvent::Key(key) = event {
+                if key.kind == KeyEventKind::Press && tx.send(key).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    rx
+}
+
+async fn run(client: BeeperClient, interval: Duration) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut app = App::new();
+    let mut keys = spawn_key_reader();
+    let (outcomes_tx, mut outcomes) = mpsc::unbounded_channel();
+
+    dispatch(&client, &mut app, &outcomes_tx, Action::LoadChats { cursor: None });
+    spawn_poller(&client, interval, outcomes_tx.clone());
+
+    while !app.should_quit {
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+
+        tokio::select! {
+            key = keys.recv() => {
+                let Some(key) = key else { break };
+                if let Some(action) = app.handle_key(key) {
+                    dispatch(&client, &mut app, &outcomes_tx, action);
+                }
+            }
+            outcome = outcomes.recv() => {
+                let Some(outcome) = outcome else { break };
+                if let Some(action) = app.apply(outcome) {
+                    dispatch(&client, &mut app, &outcomes_tx, action);
+                }
+            }
+        }
+    }
+
+    ratatui::restore();
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    let settings = match config::resolve(cli.token, cli.url, cli.config) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(78);
+        }
+    };
+    let client = BeeperClient::new(settings.token, settings.base_url);
+
+    if let Err(e) = run(client, Duration::from_secs(cli.interval.max(1))).await {
+        ratatui::restore();
+        eprintln!("error: {}", e);
+        std::process::exit(74);
+    }
+}
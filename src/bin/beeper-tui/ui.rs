@@ -0,0 +1,212 @@
+//! Rendering for the terminal UI
+
+use chrono::{DateTime, Local};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use beeper_desktop_api::Message;
+use crate::app::{App, Focus};
+
+fn border_style(active: bool) -> Style {
+    if active {
+        Style::default().fg(Color::Cyan)
+    } else {
+        Style::default()
+    }
+}
+
+fn format_timestamp(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|ts| ts.with_timezone(&Local).format("%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+fn sender(message: &Message) -> &str {
+    if message.is_sender.unwrap_or(false) {
+        "me"
+    } else {
+        message.sender_name.as_deref().unwrap_or(&message.sender_id)
+    }
+}
+
+fn one_line(text: &str, max: usize) -> String {
+    let text = text.replace('\n', " ");
+    if text.chars().count() <= max {
+        text
+    } else {
+        format!("{}…", text.chars().take(max.saturating_sub(1)).collect::<String>())
+    }
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(frame.area());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(32), Constraint::Percentage(68)])
+        .split(rows[0]);
+
+    draw_chats(frame, app, columns[0]);
+
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(if app.reply_to.is_some() { 4 } else { 3 })])
+        .split(columns[1]);
+    draw_messages(frame, app, right[0]);
+    draw_compose(frame, app, right[1]);
+    draw_status(frame, app, rows[1]);
+}
+
+fn draw_chats(frame: &mut Frame, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .chats
+        .iter()
+        .map(|chat| {
+            let mut spans = vec![Span::styled(
+                format!("[{}] ", chat.network),
+                Style::default().fg(Color::DarkGray),
+            )];
+            let mut name = Style::default();
+            if chat.unread_count > 0 {
+                name = name.add_modifier(Modifier::BOLD);
+            }
+            spans.push(Span::styled(chat.display_name(), name));
+            if chat.unread_count > 0 {
+                spans.push(Span::styled(
+                    format!(" ({})", chat.unread_count),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let title = match app.focus {
+        Focus::Search => format!(" Search: {}▏", app.search),
+        _ if app.showing_search_results => " Search results (Esc to clear) ".to_string(),
+        _ => " Chats ".to_string(),
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(border_style(matches!(app.focus, Focus::Chats | Focus::Search))),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = ListState::default();
+    if !app.chats.is_empty() {
+        state.select(Some(app.selected_chat));
+    }
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_messages(frame: &mut Frame, app: &App, area: Rect) {
+    let width = area.width.saturating_sub(4) as usize;
+    let mut items: Vec<ListItem> = Vec::new();
+
+    for message in &app.messages {
+        let mut lines = Vec::new();
+
+        if let Some(reply_id) = &message.reply_to_id {
+            let quoted = app
+                .messages
+                .iter()
+                .find(|m| &m.id == reply_id)
+                .map(|m| format!("{}: {}", sender(m), m.text.as_deref().unwrap_or("[attachment]")))
+                .unwrap_or_else(|| "earlier message".to_string());
+            lines.push(Line::from(Span::styled(
+                format!("  ↪ {}", one_line(&quoted, width.saturating_sub(4))),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+
+        let mut spans = vec![
+            Span::styled(
+                format!("{} ", format_timestamp(&message.timestamp)),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(
+                format!("{}: ", sender(message)),
+                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            ),
+        ];
+
+        let mut body = message.text.clone().unwrap_or_default();
+        for attachment in message.attachments.iter().flatten() {
+            let name = attachment.file_name.as_deref().unwrap_or(&attachment.typ);
+            body.push_str(&format!(" [{}]", name));
+        }
+        spans.push(Span::raw(body));
+
+        if message.is_edited.unwrap_or(false) {
+            spans.push(Span::styled(" (edited)", Style::default().fg(Color::DarkGray)));
+        }
+        if let Some(reactions) = &message.reactions {
+            let keys: Vec<&str> = reactions.iter().map(|r| r.reaction_key.as_str()).collect();
+            if !keys.is_empty() {
+                spans.push(Span::styled(format!("  {}", keys.join(" ")), Style::default().fg(Color::Yellow)));
+            }
+        }
+        lines.push(Line::from(spans));
+        items.push(ListItem::new(lines));
+    }
+
+    let title = match app.open_chat() {
+        Some(chat) if app.messages_has_more => format!(" {} (↑ for older) ", chat.display_name()),
+        Some(chat) => format!(" {} ", chat.display_name()),
+        None => " Messages ".to_string(),
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(border_style(app.focus == Focus::Messages)),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray));
+
+    let mut state = ListState::default();
+    state.select(app.selected_message);
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_compose(frame: &mut Frame, app: &App, area: Rect) {
+    let mut lines = Vec::new();
+    if let Some(reply) = &app.reply_to {
+        let quoted = format!("{}: {}", sender(reply), reply.text.as_deref().unwrap_or(""));
+        lines.push(Line::from(Span::styled(
+            format!("↪ {}", one_line(&quoted, area.width.saturating_sub(6) as usize)),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let cursor = if app.focus == Focus::Compose { "▏" } else { "" };
+    lines.push(Line::from(format!("{}{}", app.compose, cursor)));
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Compose ")
+            .border_style(border_style(app.focus == Focus::Compose)),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let help = match app.focus {
+        Focus::Chats => "↑↓ select  Enter open  / search  o open in Desktop  q quit",
+        Focus::Messages => "↑↓ scroll  i compose  r reply  o open in Desktop  Esc chats",
+        Focus::Compose => "Enter send  Esc cancel reply / leave",
+        Focus::Search => "Enter search  Esc cancel",
+    };
+    let line = Line::from(vec![
+        Span::styled(format!(" {} ", app.status), Style::default().fg(Color::Black).bg(Color::Cyan)),
+        Span::raw(format!(" {}", help)),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
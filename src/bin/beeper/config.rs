@@ -0,0 +1,58 @@
+//! Token and base URL resolution for the CLI
+
+use std::path::PathBuf;
+use serde::Deserialize;
+use beeper_desktop_api::{BeeperError, Result, DEFAULT_BASE_URL};
+
+/// Contents of `~/.config/beeper/config.toml`
+#[derive(Debug, Default, Deserialize)]
+pub struct ConfigFile {
+    /// Bearer token
+    pub token: Option<String>,
+    /// Base URL of the Beeper Desktop API server
+    pub url: Option<String>,
+}
+
+/// Resolved connection settings
+#[derive(Debug)]
+pub struct Settings {
+    pub token: String,
+    pub base_url: String,
+}
+
+/// Default config file location, honoring `XDG_CONFIG_HOME`
+pub fn default_config_path() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("beeper").join("config.toml"));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("beeper").join("config.toml"))
+}
+
+fn load_config_file(path: Option<PathBuf>, explicit: bool) -> Result<ConfigFile> {
+    let Some(path) = path else {
+        return Ok(ConfigFile::default());
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => toml::from_str(&contents).map_err(|e| {
+            BeeperError::InvalidConfig(format!("{}: {}", path.display(), e))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && !explicit => Ok(ConfigFile::default()),
+        Err(e) => Err(BeeperError::InvalidConfig(format!("{}: {}", path.display(), e))),
+    }
+}
+
+/// Resolves settings from flags/environment first, then the config file
+pub fn resolve(token: Option<String>, url: Option<String>, config: Option<PathBuf>) -> Result<Settings> {
+    let explicit = config.is_some();
+    let file = load_config_file(config.or_else(default_config_path), explicit)?;
+
+    let token = token.or(file.token).ok_or_else(|| {
+        BeeperError::InvalidConfig(
+            "No token found. Pass --token, set BEEPER_TOKEN, or add `token` to ~/.config/beeper/config.toml".to_string(),
+        )
+    })?;
+    let base_url = url.or(file.url).unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+
+    Ok(Settings { token, base_url })
+}
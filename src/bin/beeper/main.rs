@@ -0,0 +1,965 @@
+//! `beeper` command-line client for Beeper Desktop API
+//!
+//! Reads the token from `--token`, `BEEPER_TOKEN`, or `~/.config/beeper/config.toml` and
+//! maps [`BeeperError`] variants to sysexits-style exit codes.
+
+mod config;
+mod output;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
+use beeper_desktop_api::bot::ShutdownHandle;
+use beeper_desktop_api::broadcast::BroadcastOptions;
+use beeper_desktop_api::events::Event;
+use beeper_desktop_api::idempotency::FileIdempotencyStore;
+use beeper_desktop_api::runtime::TokioRuntime;
+use beeper_desktop_api::scheduler::Scheduler;
+use beeper_desktop_api::transcript;
+use beeper_desktop_api::{
+    compare_sort_keys, BeeperClient, BeeperError, ChatType, FocusAppInput, Inbox, ListChatsParams, MediaType,
+    Message, MessageSearchParams, Result, SendMessageInput, SenderFilter,
+};
+use output::{print_item, print_list, snippet, OutputFormat, Table};
+
+#[derive(Debug, Parser)]
+#[command(name = "beeper", version, about = "Command-line client for Beeper Desktop API")]
+struct Cli {
+    /// Bearer token (defaults to BEEPER_TOKEN or the config file)
+    #[arg(long, env = "BEEPER_TOKEN", hide_env_values = true, global = true)]
+    token: Option<String>,
+
+    /// Base URL of the Beeper Desktop API server
+    #[arg(long, env = "BEEPER_API_URL", global = true)]
+    url: Option<String>,
+
+    /// Path to a TOML config file with `token` and `url` keys
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(flatten)]
+    format: FormatArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Args)]
+#[group(multiple = false)]
+struct FormatArgs {
+    /// Print aligned columns (default)
+    #[arg(long, global = true)]
+    table: bool,
+
+    /// Print pretty JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Print one JSON object per line
+    #[arg(long, global = true)]
+    ndjson: bool,
+}
+
+impl FormatArgs {
+    fn format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else if self.ndjson {
+            OutputFormat::Ndjson
+        } else {
+            OutputFormat::Table
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List connected messaging accounts
+    Accounts,
+    /// Chat operations
+    ///
+    /// There are no mute or pin commands, since the Desktop API has no endpoints for them.
+    #[command(subcommand)]
+    Chats(ChatsCommand),
+    /// Message operations
+    #[command(subcommand)]
+    Messages(MessagesCommand),
+    /// Look up people on an account
+    #[command(subcommand)]
+    Contacts(ContactsCommand),
+    /// Chat reminders
+    #[command(subcommand)]
+    Reminder(ReminderCommand),
+    /// Messages queued for later delivery
+    Schedule {
+        /// Queue file (defaults to ~/.config/beeper/schedule.json)
+        #[arg(long)]
+        queue: Option<PathBuf>,
+        #[command(subcommand)]
+        command: ScheduleCommand,
+    },
+    /// Focus Beeper Desktop, optionally opening a chat or message
+    Focus {
+        /// Chat to open
+        #[arg(long)]
+        chat: Option<String>,
+        /// Message to jump to
+        #[arg(long)]
+        message: Option<String>,
+        /// Draft text to pre-fill
+        #[arg(long)]
+        draft: Option<String>,
+    },
+    /// Asset operations
+    #[command(subcommand)]
+    Asset(AssetCommand),
+    /// Render a chat transcript for people to read
+    Export {
+        chat_id: String,
+        /// Transcript format
+        #[arg(long, value_enum, default_value_t = TranscriptFormat::Html)]
+        format: TranscriptFormat,
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Maximum number of most recent messages to include
+        #[arg(long, default_value_t = 1000)]
+        limit: usize,
+        /// Time zone for timestamps, e.g. `Europe/Berlin` or `+02:00`
+        #[arg(long, default_value = "UTC")]
+        timezone: transcript::Timezone,
+        /// chrono format string for timestamps
+        #[arg(long, default_value = transcript::DEFAULT_TIMESTAMP_FORMAT)]
+        time_format: String,
+        /// Keep the original attachment and avatar URLs instead of downloading them
+        #[arg(long)]
+        no_assets: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum TranscriptFormat {
+    Html,
+    Markdown,
+    Plaintext,
+}
+
+#[derive(Debug, Args)]
+struct PageArgs {
+    /// Pagination cursor
+    #[arg(long)]
+    cursor: Option<String>,
+    /// Pagination direction (`before` or `after`)
+    #[arg(long)]
+    direction: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum ChatsCommand {
+    /// List chats sorted by last activity
+    List {
+        #[command(flatten)]
+        filters: ChatFilterArgs,
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Show a single chat
+    Get {
+        chat_id: String,
+    },
+    /// Search chats by title, network, or participants
+    Search {
+        query: String,
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Archive a chat
+    Archive {
+        chat_id: String,
+        /// Move the chat back to the inbox instead
+        #[arg(long)]
+        unarchive: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+struct ChatFilterArgs {
+    /// Only chats on these accounts (repeatable)
+    #[arg(long = "account")]
+    account_ids: Vec<String>,
+    /// primary, low-priority, or archive
+    #[arg(long)]
+    inbox: Option<Inbox>,
+    /// Only chats with unread messages
+    #[arg(long)]
+    unread: bool,
+    /// single or group
+    #[arg(long = "type")]
+    chat_type: Option<ChatType>,
+    /// Only pinned chats
+    #[arg(long)]
+    pinned: bool,
+    /// Only muted (`true`) or unmuted (`false`) chats
+    #[arg(long)]
+    muted: Option<bool>,
+    /// Number of chats to collect
+    #[arg(long)]
+    limit: Option<u32>,
+}
+
+impl ChatFilterArgs {
+    /// Builds list filters, or `None` when no filter was given
+    fn into_params(self) -> Option<ListChatsParams> {
+        let params = ListChatsParams {
+            account_ids: self.account_ids,
+            inbox: self.inbox,
+            unread_only: self.unread,
+            chat_type: self.chat_type,
+            pinned_only: self.pinned,
+            muted: self.muted,
+            limit: self.limit,
+        };
+        let unfiltered = params.account_ids.is_empty()
+            && params.inbox.is_none()
+            && !params.unread_only
+            && params.chat_type.is_none()
+            && !params.pinned_only
+            && params.muted.is_none()
+            && params.limit.is_none();
+        (!unfiltered).then_some(params)
+    }
+}
+
+#[derive(Debug, Args)]
+struct MessageFilterArgs {
+    /// Only search these chats (repeatable)
+    #[arg(long = "chat")]
+    chat_ids: Vec<String>,
+    /// Only search these accounts (repeatable)
+    #[arg(long = "account")]
+    account_ids: Vec<String>,
+    /// `me`, `others`, or a user ID
+    #[arg(long)]
+    sender: Option<SenderFilter>,
+    /// Only messages at or after this RFC 3339 time
+    #[arg(long)]
+    after: Option<DateTime<Utc>>,
+    /// Only messages before this RFC 3339 time
+    #[arg(long)]
+    before: Option<DateTime<Utc>>,
+    /// image, video, voice, file, or link (repeatable)
+    #[arg(long = "media")]
+    media_types: Vec<MediaType>,
+    /// Skip muted chats
+    #[arg(long)]
+    exclude_muted: bool,
+    /// Skip low-priority chats
+    #[arg(long)]
+    exclude_low_priority: bool,
+    /// Number of matches to collect
+    #[arg(long)]
+    limit: Option<u32>,
+    /// Number of messages to request per page
+    #[arg(long)]
+    page_size: Option<u32>,
+}
+
+impl MessageFilterArgs {
+    /// Builds search parameters, or `None` when no filter was given
+    fn into_params(self, query: String) -> Option<MessageSearchParams> {
+        let unfiltered = self.chat_ids.is_empty()
+            && self.account_ids.is_empty()
+            && self.sender.is_none()
+            && self.after.is_none()
+            && self.before.is_none()
+            && self.media_types.is_empty()
+            && !self.exclude_muted
+            && !self.exclude_low_priority
+            && self.limit.is_none()
+            && self.page_size.is_none();
+        if unfiltered {
+            return None;
+        }
+
+        let mut params = MessageSearchParams::new()
+            .query(query)
+            .chat_ids(self.chat_ids)
+            .account_ids(self.account_ids)
+            .media_types(self.media_types);
+        params.sender = self.sender;
+        params.date_after = self.after;
+        params.date_before = self.before;
+        params.include_muted = self.exclude_muted.then_some(false);
+        params.exclude_low_priority = self.exclude_low_priority.then_some(true);
+        params.limit = self.limit;
+        params.page_size = self.page_size;
+        Some(params)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum MessagesCommand {
+    /// List messages in a chat
+    List {
+        chat_id: String,
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Send a text message
+    Send {
+        chat_id: String,
+        text: String,
+        /// Message ID to reply to
+        #[arg(long)]
+        reply_to: Option<String>,
+        /// Wait until the message appears in the chat and print its delivery status
+        #[arg(long)]
+        confirm: bool,
+        /// Seconds to wait with --confirm
+        #[arg(long, default_value_t = 30, requires = "confirm")]
+        timeout: u64,
+        /// Send at most once per key and chat; keys are kept in idempotency.json next to the config file
+        #[arg(long)]
+        idempotency_key: Option<String>,
+        /// Turn @names of chat participants into mentions
+        #[arg(long)]
+        mentions: bool,
+    },
+    /// Send the same message to several chats; supports {first_name}, {full_name}, {chat}, {network}
+    Broadcast {
+        text: String,
+        #[arg(required = true)]
+        chat_ids: Vec<String>,
+        /// Chats sent to at once
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        /// Minimum seconds between sends on the same network
+        #[arg(long, default_value_t = 1.0)]
+        interval: f64,
+    },
+    /// Forward a message to another chat
+    Forward {
+        source_chat_id: String,
+        message_id: String,
+        target_chat_id: String,
+    },
+    /// Show a message, optionally with the messages around it
+    Get {
+        chat_id: String,
+        message_id: String,
+        /// Also show this many messages before and after it
+        #[arg(long, default_value_t = 0)]
+        context: usize,
+    },
+    /// Show the replies a message answers and all replies to it
+    Thread {
+        chat_id: String,
+        message_id: String,
+    },
+    /// Search messages across chats
+    Search {
+        query: String,
+        #[command(flatten)]
+        filters: MessageFilterArgs,
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Print new messages as they arrive
+    Tail {
+        /// Only follow these chats
+        chat_ids: Vec<String>,
+        /// Seconds between polls
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ContactsCommand {
+    /// Search an account's contacts
+    Search {
+        account_id: String,
+        query: String,
+    },
+    /// Show a user on an account
+    Get {
+        account_id: String,
+        user_id: String,
+    },
+    /// Find or create the direct message chat with a phone number, username, or email
+    Dm {
+        account_id: String,
+        handle: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ReminderCommand {
+    /// Set a reminder on a chat
+    Set {
+        chat_id: String,
+        /// ISO 8601 timestamp
+        timestamp: String,
+    },
+    /// Clear a chat's reminder
+    Clear {
+        chat_id: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ScheduleCommand {
+    /// Queue a message
+    Add {
+        chat_id: String,
+        text: String,
+        /// When to send, RFC 3339 with any UTC offset, e.g. 2025-12-24T09:00:00+09:00
+        #[arg(long)]
+        at: DateTime<Utc>,
+        /// Message ID to reply to
+        #[arg(long)]
+        reply_to: Option<String>,
+    },
+    /// List queued, sent, and failed messages
+    List,
+    /// Remove a pending message from the queue
+    Cancel {
+        id: String,
+    },
+    /// Send queued messages as they become due; keeps running
+    Run,
+}
+
+#[derive(Debug, Subcommand)]
+enum AssetCommand {
+    /// Download an mxc:// or localmxc:// asset and print its local URL
+    Download {
+        url: String,
+    },
+}
+
+/// Maps an error to a sysexits-style exit code
+fn exit_code(error: &BeeperError) -> u8 {
+    match error {
+        BeeperError::ApiNotReachable { .. } => 69,
+        BeeperError::Unauthorized => 77,
+        BeeperError::RequestError(_) => 75,
+        BeeperError::ApiError { .. } => 65,
+        BeeperError::NotFound(_) => 66,
+        BeeperError::SerializationError(_) | BeeperError::MissingField(_) => 76,
+        BeeperError::InvalidConfig(_) => 78,
+        BeeperError::InvalidInput(_) => 65,
+        BeeperError::Io(_) => 74,
+        #[cfg(feature = "sqlite")]
+        BeeperError::Database(_) => 74,
+        #[cfg(feature = "search-index")]
+        BeeperError::Index(_) => 74,
+        _ => 70,
+    }
+}
+
+fn users_table(users: &[beeper_desktop_api::User]) -> Table {
+    let mut table = Table::new(vec!["ID", "NAME", "USERNAME", "PHONE"]);
+    for user in users {
+        table.push(vec![
+            user.id.clone(),
+            user.full_name.clone().unwrap_or_default(),
+            user.username.clone().unwrap_or_default(),
+            user.phone_number.clone().unwrap_or_default(),
+        ]);
+    }
+    table
+}
+
+fn chats_table(chats: &[beeper_desktop_api::Chat]) -> Table {
+    let mut table = Table::new(vec!["ID", "NETWORK", "TYPE", "UNREAD", "TITLE"]);
+    for chat in chats {
+        table.push(vec![
+            chat.id.clone(),
+            chat.network.clone(),
+            chat.chat_type.clone(),
+            chat.unread_count.to_string(),
+            chat.display_name(),
+        ]);
+    }
+    table
+}
+
+fn messages_table(messages: &[Message]) -> Table {
+    let mut table = Table::new(vec!["TIMESTAMP", "SENDER", "ID", "TEXT"]);
+    for message in messages {
+        table.push(vec![
+            message.timestamp.clone(),
+            message.sender_name.clone().unwrap_or_else(|| message.sender_id.clone()),
+            message.id.clone(),
+            snippet(message.text.as_deref(), 60),
+        ]);
+    }
+    table
+}
+
+/// Pages back through a chat until `limit` messages are collected or history ends
+async fn fetch_history(client: &BeeperClient, chat_id: &str, limit: usize) -> Result<Vec<Message>> {
+    let mut messages: Vec<Message> = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let page = match &cursor {
+            Some(c) => client.list_messages(chat_id, Some(c), Some("before")).await?,
+            None => client.list_messages(chat_id, None, None).await?,
+        };
+        let oldest = page
+            .items
+            .iter()
+            .map(|m| m.sort_key.clone())
+            .min_by(|a, b| compare_sort_keys(a, b));
+        let fetched = page.items.len();
+        messages.extend(page.items);
+
+        if messages.len() >= limit || !page.has_more || fetched == 0 {
+            break;
+        }
+        cursor = oldest;
+    }
+
+    messages.sort_by(|a, b| compare_sort_keys(&b.sort_key, &a.sort_key));
+    messages.truncate(limit);
+    Ok(messages)
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let config_path = cli.config.clone().or_else(config::default_config_path);
+    let settings = config::resolve(cli.token, cli.url, cli.config)?;
+    let client = BeeperClient::new(settings.token, settings.base_url);
+    let format = cli.format.format();
+
+    match cli.command {
+        Command::Accounts => {
+            let accounts = client.get_accounts().await?;
+            print_list(format, &accounts, |accounts| {
+                let mut table = Table::new(vec!["ACCOUNT", "NETWORK", "USER"]);
+                for account in accounts {
+                    table.push(vec![
+                        account.account_id.clone(),
+                        account.network.clone(),
+                        account.user.full_name.clone().unwrap_or_else(|| account.user.id.clone()),
+                    ]);
+                }
+                table
+            })
+        }
+        Command::Contacts(command) => match command {
+            ContactsCommand::Search { account_id, query } => {
+                let output = client.search_contacts(&account_id, &query).await?;
+                print_list(format, &output.items, users_table)
+            }
+            ContactsCommand::Get { account_id, user_id } => {
+                let user = client.get_user(&account_id, &user_id).await?;
+                print_item(format, &user)
+            }
+            ContactsCommand::Dm { account_id, handle } => {
+                let resolved = client.resolve_dm(&account_id, &handle).await?;
+                if resolved.created {
+                    eprintln!("Created chat with {}", resolved.chat.display_name());
+                }
+                print_item(format, &resolved.chat)
+            }
+        },
+        Command::Chats(command) => match command {
+            ChatsCommand::List { filters, page } => {
+                let output = match filters.into_params() {
+                    Some(params) => {
+                        client
+                            .list_chats_with(&params, page.cursor.as_deref(), page.direction.as_deref())
+                            .await?
+                    }
+                    None => client.list_chats(page.cursor.as_deref(), page.direction.as_deref()).await?,
+                };
+                print_list(format, &output.items, chats_table)
+            }
+            ChatsCommand::Get { chat_id } => {
+                let chat = client.get_chat(&chat_id).await?;
+                print_item(format, &chat)
+            }
+            ChatsCommand::Search { query, page } => {
+                let output = client
+                    .search_chats(&query, page.cursor.as_deref(), page.direction.as_deref())
+                    .await?;
+                print_list(format, &output.items, chats_table)
+            }
+            ChatsCommand::Archive { chat_id, unarchive } => {
+                let chat = client.archive_chat(&chat_id, !unarchive).await?;
+                print_item(format, &chat)
+            }
+        },
+        Command::Messages(command) => match command {
+            MessagesCommand::List { chat_id, page } => {
+                let output = client
+                    .list_messages(&chat_id, page.cursor.as_deref(), page.direction.as_deref())
+                    .await?;
+                print_list(format, &output.items, messages_table)
+            }
+            MessagesCommand::Send { chat_id, text, reply_to, confirm, timeout, idempotency_key, mentions } => {
+                // Keys must outlive this process, so there is no in-memory fallback
+                let client = match &idempotency_key {
+                    Some(_) => {
+                        let path = config_path.as_ref().map(|p| p.with_file_name("idempotency.json")).ok_or_else(|| {
+                            BeeperError::InvalidConfig("Cannot locate the idempotency store; pass --config".to_string())
+                        })?;
+                        client.clone().with_idempotency_store(FileIdempotencyStore::open(path)?)
+                    }
+                    None => client.clone(),
+                };
+                let mut input = SendMessageInput {
+                    text,
+                    reply_to_id: reply_to,
+                    idempotency_key,
+                    ..Default::default()
+                };
+                if mentions {
+                    input = input.mention_participants(&client.get_chat(&chat_id).await?);
+                }
+                if confirm {
+                    let delivery = client.send_and_confirm(&chat_id, input, Duration::from_secs(timeout)).await?;
+                    print_item(format, &delivery)
+                } else {
+                    let output = client.send_message(&chat_id, input).await?;
+                    print_item(format, &output)
+                }
+            }
+            MessagesCommand::Broadcast { text, chat_ids, concurrency, interval } => {
+                let input = SendMessageInput {
+                    text,
+                    ..Default::default()
+                };
+                let options = BroadcastOptions::new()
+                    .with_concurrency(concurrency)
+                    .with_default_interval(Duration::from_secs_f64(interval.max(0.0)));
+                let report = client.broadcast(chat_ids, input, &options).await;
+                let rows: Vec<serde_json::Value> = report
+                    .results
+                    .iter()
+                    .map(|r| match &r.outcome {
+                        Ok(output) => serde_json::json!({ "chatID": r.chat_id, "pendingMessageID": output.pending_message_id }),
+                        Err(e) => serde_json::json!({ "chatID": r.chat_id, "error": e.to_string() }),
+                    })
+                    .collect();
+                print_list(format, &rows, |rows| {
+                    let mut table = Table::new(vec!["CHAT", "RESULT"]);
+                    for row in rows {
+                        let result = row.get("error").or(row.get("pendingMessageID"));
+                        table.push(vec![
+                            row["chatID"].as_str().unwrap_or_default().to_string(),
+                            result.and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                        ]);
+                    }
+                    table
+                })?;
+                let failed = report.failed().count();
+                if failed > 0 {
+                    return Err(BeeperError::ApiError {
+                        code: "BROADCAST_INCOMPLETE".to_string(),
+                        message: format!("{} of {} sends failed", failed, report.results.len()),
+                    });
+                }
+                Ok(())
+            }
+            MessagesCommand::Forward { source_chat_id, message_id, target_chat_id } => {
+                let output = client.forward_message(&source_chat_id, &message_id, &target_chat_id).await?;
+                print_item(format, &output)
+            }
+            MessagesCommand::Get { chat_id, message_id, context } => {
+                if context == 0 {
+                    let message = client.get_message(&chat_id, &message_id).await?;
+                    return print_item(format, &message);
+                }
+                let around = client.get_message_context(&chat_id, &message_id, context, context).await?;
+                let messages: Vec<Message> = around.messages().cloned().collect();
+                print_list(format, &messages, messages_table)
+            }
+            MessagesCommand::Thread { chat_id, message_id } => {
+                let tree = client.load_thread(&chat_id, &message_id).await?;
+                let branch: Vec<Message> = tree.branch(&message_id).into_iter().cloned().collect();
+                print_list(format, &branch, messages_table)
+            }
+            MessagesCommand::Search { query, filters, page } => {
+                let output = match filters.into_params(query.clone()) {
+                    Some(params) => {
+                        client
+                            .search_messages_with(&params, page.cursor.as_deref(), page.direction.as_deref())
+                            .await?
+                    }
+                    None => {
+                        client
+                            .search_messages(&query, page.cursor.as_deref(), page.direction.as_deref())
+                            .await?
+                    }
+                };
+                print_list(format, &output.items, messages_table)
+            }
+            MessagesCommand::Tail { chat_ids, interval } => {
+                let mut events = client.events().with_interval(Duration::from_secs(interval));
+                if !chat_ids.is_empty() {
+                    events = events.with_chat_ids(chat_ids);
+                }
+
+                loop {
+                    if let Event::Message(message) = events.next_event().await? {
+                        match format {
+                            OutputFormat::Table => println!(
+                                "[{}] {}: {}",
+                                message.timestamp,
+                                message.sender_name.as_deref().unwrap_or(&message.sender_id),
+                                message.text.as_deref().unwrap_or("[No text]")
+                            ),
+                            _ => println!("{}", serde_json::to_string(&message)?),
+                        }
+                    }
+                }
+            }
+        },
+        Command::Reminder(command) => match command {
+            ReminderCommand::Set { chat_id, timestamp } => {
+                let chat = client.set_chat_reminder(&chat_id, &timestamp).await?;
+                print_item(format, &chat)
+            }
+            ReminderCommand::Clear { chat_id } => {
+                let chat = client.clear_chat_reminder(&chat_id).await?;
+                print_item(format, &chat)
+            }
+        },
+        Command::Schedule { queue, command } => {
+            let path = queue
+                .or_else(|| config::default_config_path().map(|p| p.with_file_name("schedule.json")))
+                .ok_or_else(|| BeeperError::InvalidConfig("Cannot locate the schedule queue; pass --queue".to_string()))?;
+            let scheduler = Scheduler::open(path)?;
+            match command {
+                ScheduleCommand::Add { chat_id, text, at, reply_to } => {
+                    let input = SendMessageInput {
+                        text,
+                        reply_to_id: reply_to,
+                        ..Default::default()
+                    };
+                    let job = scheduler.schedule(&chat_id, input, at)?;
+                    print_item(format, &job)
+                }
+                ScheduleCommand::List => print_list(format, &scheduler.list(), |jobs| {
+                    let mut table = Table::new(vec!["ID", "SEND AT", "STATUS", "CHAT", "TEXT"]);
+                    for job in jobs {
+                        table.push(vec![
+                            job.id.clone(),
+                            job.send_at.to_rfc3339(),
+                            format!("{:?}", job.status).to_lowercase(),
+                            job.chat_id.clone(),
+                            snippet(Some(&job.input.text), 60),
+                        ]);
+                    }
+                    table
+                }),
+                ScheduleCommand::Cancel { id } => {
+                    let job = scheduler.cancel(&id)?;
+                    print_item(format, &job)
+                }
+                ScheduleCommand::Run => {
+                    let scheduler = scheduler.on_error(|e| eprintln!("error: {}", e));
+                    scheduler.run(&client, &TokioRuntime, &ShutdownHandle::default()).await
+                }
+            }
+        }
+        Command::Focus { chat, message, draft } => {
+            let input = if chat.is_none() && message.is_none() && draft.is_none() {
+                None
+            } else {
+                Some(FocusAppInput {
+                    chat_id: chat,
+                    message_id: message,
+                    draft,
+                })
+            };
+            let output = client.focus_app(input).await?;
+            print_item(format, &output)
+        }
+        Command::Asset(AssetCommand::Download { url }) => {
+            let output = client.download_asset(&url).await?;
+            print_item(format, &output)
+        }
+        Command::Export { chat_id, format: transcript_format, output, limit, timezone, time_format, no_assets } => {
+            let chat = client.get_chat(&chat_id).await?;
+            let messages = fetch_history(&client, &chat_id, limit).await?;
+            let mut options = transcript::TranscriptOptions::new()
+                .with_timezone(timezone)
+                .with_timestamp_format(time_format);
+            if !no_assets {
+                let downloads = transcript::download_assets(&client, &chat, &messages).await;
+                for (url, error) in &downloads.failed {
+                    eprintln!("warning: could not download {}: {}", url, error);
+                }
+                options = match transcript_format {
+                    TranscriptFormat::Html => {
+                        let (embedded, unreadable) = options.with_embedded_assets(downloads.files);
+                        for (url, error) in &unreadable {
+                            eprintln!("warning: could not read {}: {}", url, error);
+                        }
+                        embedded
+                    }
+                    TranscriptFormat::Markdown | TranscriptFormat::Plaintext => options.with_asset_paths(
+                        downloads.files.into_iter().map(|(url, path)| (url, path.display().to_string())),
+                    ),
+                };
+            }
+            let rendered = match transcript_format {
+                TranscriptFormat::Html => transcript::render_html_with_options(&chat, &messages, &options),
+                TranscriptFormat::Markdown => transcript::render_markdown_with_options(&chat, &messages, &options),
+                TranscriptFormat::Plaintext => transcript::render_plaintext_with_options(&chat, &messages, &options),
+            };
+
+            match output {
+                Some(path) => std::fs::write(path, rendered)?,
+                None => print!("{}", rendered),
+            }
+            Ok(())
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_output_format_flags() {
+        let cli = Cli::parse_from(["beeper", "--ndjson", "accounts"]);
+        assert_eq!(cli.format.format(), OutputFormat::Ndjson);
+
+        let cli = Cli::parse_from(["beeper", "chats", "list", "--json"]);
+        assert_eq!(cli.format.format(), OutputFormat::Json);
+
+        let cli = Cli::parse_from(["beeper", "accounts"]);
+        assert_eq!(cli.format.format(), OutputFormat::Table);
+    }
+
+    #[test]
+    fn test_conflicting_output_flags_rejected() {
+        assert!(Cli::try_parse_from(["beeper", "--json", "--ndjson", "accounts"]).is_err());
+    }
+
+    #[test]
+    fn test_export_defaults_to_html() {
+        let cli = Cli::parse_from(["beeper", "export", "chat-1", "-o", "out.html"]);
+        match cli.command {
+            Command::Export { chat_id, format, output, limit, .. } => {
+                assert_eq!(chat_id, "chat-1");
+                assert_eq!(format, TranscriptFormat::Html);
+                assert_eq!(output, Some(PathBuf::from("out.html")));
+                assert_eq!(limit, 1000);
+            }
+            other => panic!("Expected export command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_export_markdown_with_timezone() {
+        let cli = Cli::parse_from(["beeper", "export", "chat-1", "--format", "markdown", "--timezone", "+03:00"]);
+        match cli.command {
+            Command::Export { format, timezone, .. } => {
+                assert_eq!(format, TranscriptFormat::Markdown);
+                assert_eq!(timezone, transcript::Timezone::Offset(chrono::FixedOffset::east_opt(3 * 3600).unwrap()));
+            }
+            other => panic!("Expected export command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_export_named_timezone() {
+        let cli = Cli::parse_from(["beeper", "export", "chat-1", "--timezone", "America/New_York"]);
+        let Command::Export { timezone, .. } = cli.command else {
+            panic!("Expected export command");
+        };
+        assert_eq!(timezone, transcript::Timezone::Named(transcript::Tz::America__New_York));
+        assert!(Cli::try_parse_from(["beeper", "export", "chat-1", "--timezone", "Nowhere/Else"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_list_filters() {
+        let cli = Cli::parse_from([
+            "beeper", "chats", "list", "--account", "slack", "--inbox", "low-priority", "--unread", "--type", "group",
+        ]);
+        let Command::Chats(ChatsCommand::List { filters, .. }) = cli.command else {
+            panic!("Expected chats list command");
+        };
+        let params = filters.into_params().unwrap();
+        assert_eq!(params.account_ids, vec!["slack"]);
+        assert_eq!(params.inbox, Some(Inbox::LowPriority));
+        assert_eq!(params.chat_type, Some(ChatType::Group));
+        assert!(params.unread_only && !params.pinned_only);
+
+        let cli = Cli::parse_from(["beeper", "chats", "list"]);
+        let Command::Chats(ChatsCommand::List { filters, .. }) = cli.command else {
+            panic!("Expected chats list command");
+        };
+        assert!(filters.into_params().is_none());
+    }
+
+    #[test]
+    fn test_message_search_filters() {
+        let cli = Cli::parse_from([
+            "beeper", "messages", "search", "invoice", "--sender", "others", "--media", "file", "--media", "voice",
+            "--after", "2025-01-01T00:00:00Z", "--exclude-muted",
+        ]);
+        let Command::Messages(MessagesCommand::Search { filters, .. }) = cli.command else {
+            panic!("Expected messages search command");
+        };
+        let params = filters.into_params("invoice".to_string()).unwrap();
+        assert_eq!(params.sender, Some(SenderFilter::Others));
+        assert_eq!(params.media_types, vec![MediaType::File, MediaType::Voice]);
+        assert_eq!(params.include_muted, Some(false));
+        assert!(params.date_after.is_some());
+
+        let cli = Cli::parse_from(["beeper", "messages", "search", "invoice"]);
+        let Command::Messages(MessagesCommand::Search { filters, .. }) = cli.command else {
+            panic!("Expected messages search command");
+        };
+        assert!(filters.into_params("invoice".to_string()).is_none());
+    }
+
+    #[test]
+    fn test_schedule_add_converts_offset_to_utc() {
+        let cli = Cli::parse_from([
+            "beeper", "schedule", "--queue", "q.json", "add", "chat-1", "Standup", "--at", "2025-12-24T09:00:00+09:00",
+        ]);
+        let Command::Schedule { queue, command: ScheduleCommand::Add { at, .. } } = cli.command else {
+            panic!("Expected schedule add command");
+        };
+        assert_eq!(queue, Some(PathBuf::from("q.json")));
+        assert_eq!(at.to_rfc3339(), "2025-12-24T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(exit_code(&BeeperError::Unauthorized), 77);
+        assert_eq!(exit_code(&BeeperError::ApiNotReachable { url: "x".to_string() }), 69);
+        assert_eq!(exit_code(&BeeperError::InvalidConfig("x".to_string())), 78);
+        assert_eq!(exit_code(&BeeperError::NotFound("x".to_string())), 66);
+    }
+}
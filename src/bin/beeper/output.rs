@@ -0,0 +1,117 @@
+//! Output formatting for the CLI
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use beeper_desktop_api::Result;
+
+/// How results are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for humans
+    Table,
+    /// A single pretty-printed JSON document
+    Json,
+    /// One compact JSON object per line
+    Ndjson,
+}
+
+/// A rendered table
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: Vec<&'static str>) -> Self {
+        Self { headers, rows: Vec::new() }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    fn print(&self) {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+
+        let line = |cells: Vec<&str>| {
+            let padded: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(i, c)| format!("{:<width$}", c, width = widths[i]))
+                .collect();
+            println!("{}", padded.join("  ").trim_end());
+        };
+
+        line(self.headers.clone());
+        for row in &self.rows {
+            line(row.iter().map(String::as_str).collect());
+        }
+    }
+}
+
+/// Truncates text to a single line of at most `max` characters
+pub fn snippet(text: Option<&str>, max: usize) -> String {
+    let text = text.unwrap_or("").replace('\n', " ");
+    if text.chars().count() <= max {
+        text
+    } else {
+        let truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+        format!("{}…", truncated)
+    }
+}
+
+/// Prints a list of items, using `table` to build rows for table output
+pub fn print_list<T: Serialize>(format: OutputFormat, items: &[T], table: impl FnOnce(&[T]) -> Table) -> Result<()> {
+    match format {
+        OutputFormat::Table => table(items).print(),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(items)?),
+        OutputFormat::Ndjson => {
+            for item in items {
+                println!("{}", serde_json::to_string(item)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints a single item; table output lists its top-level scalar fields
+pub fn print_item<T: Serialize>(format: OutputFormat, item: &T) -> Result<()> {
+    match format {
+        OutputFormat::Table => {
+            let mut table = Table::new(vec!["FIELD", "VALUE"]);
+            if let Value::Object(map) = serde_json::to_value(item)? {
+                for (key, value) in map {
+                    let value = match value {
+                        Value::String(s) => s,
+                        Value::Array(items) => format!("[{} items]", items.len()),
+                        Value::Object(_) => "{…}".to_string(),
+                        other => other.to_string(),
+                    };
+                    table.push(vec![key, value]);
+                }
+            }
+            table.print();
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormat::Ndjson => println!("{}", serde_json::to_string(item)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::snippet;
+
+    #[test]
+    fn test_snippet_truncates_and_flattens() {
+        assert_eq!(snippet(Some("hello\nworld"), 20), "hello world");
+        assert_eq!(snippet(Some("abcdefghij"), 5), "abcd…");
+        assert_eq!(snippet(None, 5), "");
+    }
+}
@@ -0,0 +1,47 @@
+//! Blocking app control and asset operations
+
+use crate::models::{DownloadAssetInput, DownloadAssetOutput, FocusAppInput, FocusAppOutput};
+use crate::error::Result;
+use super::{BeeperClient, handle_response};
+
+impl BeeperClient {
+    /// Focuses Beeper Desktop and optionally navigates to a specific location
+    pub fn focus_app(&self, input: Option<FocusAppInput>) -> Result<FocusAppOutput> {
+        let url = format!("{}/v1/focus", self.get_base_url());
+        let body = match input {
+            Some(inp) => serde_json::to_value(inp)?,
+            None => serde_json::json!({}),
+        };
+
+        let response = self
+            .get_http_client()
+            .post(&url)
+            .header("Authorization", self.get_auth_header())
+            .json(&body)
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Downloads an asset from a URL
+    ///
+    /// Downloads a Matrix asset using its mxc:// or localmxc:// URL to the device
+    /// running Beeper Desktop and returns the local file URL.
+    pub fn download_asset(&self, url: &str) -> Result<DownloadAssetOutput> {
+        let endpoint_url = format!("{}/v1/assets/download", self.get_base_url());
+        let input = DownloadAssetInput {
+            url: url.to_string(),
+        };
+
+        let response = self
+            .get_http_client()
+            .post(&endpoint_url)
+            .header("Authorization", self.get_auth_header())
+            .json(&input)
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+}
@@ -0,0 +1,145 @@
+//! Blocking chat-related API operations
+
+use crate::models::search::Collector;
+use crate::models::{Chat, CreateChatInput, CreateChatOutput, ListChatsOutput, ListChatsParams};
+use crate::error::Result;
+use super::{BeeperClient, handle_response};
+
+impl BeeperClient {
+    /// Lists all chats sorted by last activity
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::list_chats`].
+    pub fn list_chats(
+        &self,
+        cursor: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<ListChatsOutput> {
+        let mut url = format!("{}/v1/chats", self.get_base_url());
+        crate::client::utils::append_pagination(&mut url, cursor, direction);
+
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Lists chats matching filters
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::list_chats_with`].
+    pub fn list_chats_with(
+        &self,
+        params: &ListChatsParams,
+        cursor: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<ListChatsOutput> {
+        let query = params.to_query_string();
+        let mut collector = Collector::new(params, direction);
+        let mut cursor = cursor.map(str::to_string);
+
+        loop {
+            let mut url = format!("{}/v1/chats", self.get_base_url());
+            if !query.is_empty() {
+                url.push('?');
+                url.push_str(&query);
+            }
+            crate::client::utils::append_pagination(&mut url, cursor.as_deref(), direction);
+
+            let response = self
+                .get_http_client()
+                .get(&url)
+                .header("Authorization", self.get_auth_header())
+                .send()
+                .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+            match collector.push(handle_response(response)?) {
+                Some(next) => cursor = Some(next),
+                None => return Ok(collector.finish()),
+            }
+        }
+    }
+
+    /// Retrieves details for a specific chat
+    pub fn get_chat(&self, chat_id: &str) -> Result<Chat> {
+        let url = format!("{}/v1/chats/{}", self.get_base_url(), urlencoding::encode(chat_id));
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Creates a new chat
+    pub fn create_chat(&self, input: CreateChatInput) -> Result<CreateChatOutput> {
+        let url = format!("{}/v1/chats", self.get_base_url());
+        let response = self
+            .get_http_client()
+            .post(&url)
+            .header("Authorization", self.get_auth_header())
+            .json(&input)
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Archives or unarchives a chat
+    pub fn archive_chat(&self, chat_id: &str, archived: bool) -> Result<Chat> {
+        let url = format!("{}/v1/chats/{}/archive", self.get_base_url(), urlencoding::encode(chat_id));
+        let body = serde_json::json!({ "archived": archived });
+
+        let response = self
+            .get_http_client()
+            .post(&url)
+            .header("Authorization", self.get_auth_header())
+            .json(&body)
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Sets a reminder for a chat
+    pub fn set_chat_reminder(&self, chat_id: &str, timestamp: &str) -> Result<Chat> {
+        let url = format!(
+            "{}/v1/chats/{}/reminders",
+            self.get_base_url(),
+            urlencoding::encode(chat_id)
+        );
+        let body = serde_json::json!({ "timestamp": timestamp });
+
+        let response = self
+            .get_http_client()
+            .post(&url)
+            .header("Authorization", self.get_auth_header())
+            .json(&body)
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Clears a reminder from a chat
+    pub fn clear_chat_reminder(&self, chat_id: &str) -> Result<Chat> {
+        let url = format!(
+            "{}/v1/chats/{}/reminders",
+            self.get_base_url(),
+            urlencoding::encode(chat_id)
+        );
+
+        let response = self
+            .get_http_client()
+            .delete(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+}
@@ -0,0 +1,82 @@
+//! Blocking contact lookup API operations
+
+use reqwest::StatusCode;
+use crate::client::contacts::{find_dm, pick_contact, user_not_found};
+use crate::models::{CreateChatInput, ResolvedDm, SearchContactsOutput, User};
+use crate::error::Result;
+use super::{BeeperClient, handle_response};
+
+impl BeeperClient {
+    /// Searches an account's contacts by name, username, phone number, or email
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::search_contacts`].
+    pub fn search_contacts(&self, account_id: &str, query: &str) -> Result<SearchContactsOutput> {
+        let url = format!(
+            "{}/v1/accounts/{}/contacts/search?q={}",
+            self.get_base_url(),
+            urlencoding::encode(account_id),
+            urlencoding::encode(query)
+        );
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Retrieves a user on an account
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::get_user`].
+    pub fn get_user(&self, account_id: &str, user_id: &str) -> Result<User> {
+        let url = format!(
+            "{}/v1/accounts/{}/users/{}",
+            self.get_base_url(),
+            urlencoding::encode(account_id),
+            urlencoding::encode(user_id)
+        );
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(user_not_found(account_id, user_id));
+        }
+        handle_response(response)
+    }
+
+    /// Finds or creates the direct message chat with a person
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::resolve_dm`].
+    pub fn resolve_dm(&self, account_id: &str, handle: &str) -> Result<ResolvedDm> {
+        let contacts = self.search_contacts(account_id, handle)?;
+        let user = pick_contact(contacts.items, handle)?;
+
+        let query = user.full_name.as_deref().unwrap_or(handle);
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = self.search_chats(query, cursor.as_deref(), Some("before"))?;
+            let next = page.oldest_cursor.filter(|_| page.has_more && !page.items.is_empty());
+            if let Some(chat) = find_dm(page.items, account_id, &user.id) {
+                return Ok(ResolvedDm { user, chat, created: false });
+            }
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let output = self.create_chat(CreateChatInput {
+            account_id: account_id.to_string(),
+            participant_ids: vec![user.id.clone()],
+            title: None,
+        })?;
+        let chat = self.get_chat(&output.chat_id)?;
+        Ok(ResolvedDm { user, chat, created: true })
+    }
+}
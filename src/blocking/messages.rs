@@ -0,0 +1,326 @@
+//! Blocking message-related API operations
+
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use crate::broadcast::{render_for, BroadcastOptions, BroadcastReport, BroadcastResult, NetworkThrottle};
+use crate::client::messages::{
+    confirm_delivery, context, failed_delivery, forward_text, newest_sort_key, pending_delivery, recovered_output,
+    replays_earlier_send, ContextSide, CONFIRM_POLL_INTERVAL,
+};
+use crate::idempotency::{never_sent, scoped_key, IdempotencyRecord};
+use crate::thread::{older_cursor, pages_exhausted, ThreadTree, DEFAULT_MAX_PAGES};
+use crate::models::{
+    ChatID, Delivery, DeliveryStatus, ListMessagesOutput, Message, MessageContext, SendMessageInput, SendMessageOutput,
+};
+use crate::error::{BeeperError, Result};
+use super::{BeeperClient, handle_response};
+
+impl BeeperClient {
+    /// Lists all messages in a chat
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::list_messages`].
+    pub fn list_messages(
+        &self,
+        chat_id: &str,
+        cursor: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<ListMessagesOutput> {
+        let mut url = format!(
+            "{}/v1/chats/{}/messages",
+            self.get_base_url(),
+            urlencoding::encode(chat_id)
+        );
+        crate::client::utils::append_pagination(&mut url, cursor, direction);
+
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Sends a message to a chat
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::send_message`].
+    pub fn send_message(&self, chat_id: &str, input: SendMessageInput) -> Result<SendMessageOutput> {
+        let input = input.resolve_mentions()?;
+        let Some(key) = input.idempotency_key.as_deref() else {
+            return self.post_message(chat_id, &input);
+        };
+        let key = scoped_key(chat_id, key);
+        let store = self.idempotency_store();
+
+        // Claims the key atomically, so concurrent first sends cannot both go out
+        let attempt = IdempotencyRecord::Attempted { at: Utc::now() };
+        match store.insert_if_absent(&key, attempt.clone())? {
+            Some(IdempotencyRecord::Completed { output }) => return Ok(output),
+            Some(IdempotencyRecord::Attempted { at }) => {
+                let page = self.list_messages(chat_id, None, None)?;
+                if let Some(output) = recovered_output(chat_id, &page.items, &input, at) {
+                    store.put(&key, IdempotencyRecord::Completed { output: output.clone() })?;
+                    return Ok(output);
+                }
+                store.put(&key, attempt)?;
+            }
+            None => {}
+        }
+
+        match self.post_message(chat_id, &input) {
+            Ok(output) => {
+                store.put(&key, IdempotencyRecord::Completed { output: output.clone() })?;
+                Ok(output)
+            }
+            Err(e) => {
+                if never_sent(&e) {
+                    store.remove(&key)?;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn post_message(&self, chat_id: &str, input: &SendMessageInput) -> Result<SendMessageOutput> {
+        let url = format!(
+            "{}/v1/chats/{}/messages",
+            self.get_base_url(),
+            urlencoding::encode(chat_id)
+        );
+
+        let response = self
+            .get_http_client()
+            .post(&url)
+            .header("Authorization", self.get_auth_header())
+            .json(input)
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Sends a message and waits until it shows up in the chat
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::send_and_confirm_with`].
+    pub fn send_and_confirm(&self, chat_id: &str, input: SendMessageInput, timeout: Duration) -> Result<Delivery> {
+        let input = input.resolve_mentions()?;
+        let started = Instant::now();
+        let before = self.list_messages(chat_id, None, None)?;
+        let (sent_at, after) = match replays_earlier_send(self.idempotency_store(), chat_id, &input)? {
+            true => (DateTime::UNIX_EPOCH, None),
+            false => (Utc::now(), newest_sort_key(&before.items)),
+        };
+        let output = match self.send_message(chat_id, input.clone()) {
+            Ok(output) => output,
+            Err(e @ BeeperError::ApiError { .. }) => return Ok(failed_delivery(chat_id, &e)),
+            Err(e) => return Err(e),
+        };
+
+        let mut delivery = pending_delivery(chat_id, &output);
+        loop {
+            // The message is out; a failed poll only delays confirmation
+            if let Ok(page) = self.list_messages(chat_id, None, None) {
+                let found = confirm_delivery(&page.items, &output, &input, sent_at, after.as_deref());
+                if let Some((status, message)) = found {
+                    delivery.status = status;
+                    delivery.message = Some(message);
+                }
+            }
+            if delivery.status == DeliveryStatus::Delivered || started.elapsed() >= timeout {
+                return Ok(delivery);
+            }
+            std::thread::sleep(CONFIRM_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())));
+        }
+    }
+
+    /// Sends `input` to every chat in `targets`, one at a time
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::broadcast_with`]; the concurrency
+    /// option is ignored.
+    pub fn broadcast(&self, targets: Vec<ChatID>, input: SendMessageInput, options: &BroadcastOptions) -> BroadcastReport {
+        let throttle = NetworkThrottle::default();
+        let results = targets
+            .into_iter()
+            .map(|chat_id| {
+                let outcome = self.get_chat(&chat_id).and_then(|chat| {
+                    std::thread::sleep(throttle.reserve(&chat.network, options.interval(&chat.network), Instant::now()));
+                    let mut personal = input.clone().resolve_mentions()?;
+                    personal.text = render_for(&personal.text, &chat, options.variables(&chat_id));
+                    self.send_message(&chat_id, personal)
+                });
+                BroadcastResult { chat_id, outcome }
+            })
+            .collect();
+        BroadcastReport { results }
+    }
+
+    /// Forwards a message to another chat
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::forward_message`].
+    pub fn forward_message(
+        &self,
+        source_chat_id: &str,
+        message_id: &str,
+        target_chat_id: &str,
+    ) -> Result<SendMessageOutput> {
+        let chat = self.get_chat(source_chat_id)?;
+        let message = self.get_message(source_chat_id, message_id)?;
+
+        let input = SendMessageInput {
+            text: forward_text(&message, Some(&chat)),
+            ..Default::default()
+        };
+        self.send_message(target_chat_id, input)
+    }
+
+    /// Returns the message with `message_id`
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::get_message`].
+    pub fn get_message(&self, chat_id: &str, message_id: &str) -> Result<Message> {
+        self.get_message_with(chat_id, message_id, DEFAULT_MAX_PAGES)
+    }
+
+    /// Returns the message with `message_id`, reading at most `max_pages` pages
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::get_message_with`].
+    pub fn get_message_with(&self, chat_id: &str, message_id: &str, max_pages: usize) -> Result<Message> {
+        let mut cursor: Option<String> = None;
+        for _ in 0..max_pages {
+            let page = self.list_messages(chat_id, cursor.as_deref(), cursor.as_ref().map(|_| "before"))?;
+            if let Some(message) = page.items.iter().find(|m| m.id == message_id) {
+                return Ok(message.clone());
+            }
+            cursor = older_cursor(&page);
+            if cursor.is_none() {
+                return Err(BeeperError::NotFound(format!("Message {} in chat {}", message_id, chat_id)));
+            }
+        }
+        Err(pages_exhausted(&format!("Message {}", message_id), chat_id, max_pages))
+    }
+
+    /// Returns a message together with the messages around it
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::get_message_context`].
+    pub fn get_message_context(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        before: usize,
+        after: usize,
+    ) -> Result<MessageContext> {
+        let message = self.get_message(chat_id, message_id)?;
+        self.get_context_around(message, before, after)
+    }
+
+    /// Loads the messages around an already loaded message
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::get_context_around`].
+    pub fn get_context_around(&self, message: Message, before: usize, after: usize) -> Result<MessageContext> {
+        let mut older = ContextSide::new(&message, "before", before);
+        while let Some(cursor) = older.cursor() {
+            let page = self.list_messages(&message.chat_id, Some(&cursor), Some("before"))?;
+            older.push(page);
+        }
+        let mut newer = ContextSide::new(&message, "after", after);
+        while let Some(cursor) = newer.cursor() {
+            let page = self.list_messages(&message.chat_id, Some(&cursor), Some("after"))?;
+            newer.push(page);
+        }
+        Ok(context(message, older, newer))
+    }
+
+    fn page_into(
+        &self,
+        chat_id: &str,
+        tree: &mut ThreadTree,
+        max_pages: usize,
+        done: impl Fn(&ThreadTree) -> bool,
+    ) -> Result<bool> {
+        let mut cursor: Option<String> = None;
+        for _ in 0..max_pages {
+            let page = self.list_messages(chat_id, cursor.as_deref(), cursor.as_ref().map(|_| "before"))?;
+            cursor = older_cursor(&page);
+            for message in page.items {
+                tree.insert(message);
+            }
+            if done(tree) || cursor.is_none() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Builds the reply tree around a message
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::load_thread`].
+    pub fn load_thread(&self, chat_id: &str, message_id: &str) -> Result<ThreadTree> {
+        self.load_thread_with(chat_id, message_id, DEFAULT_MAX_PAGES)
+    }
+
+    /// Builds the reply tree around a message, reading at most `max_pages` pages
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::load_thread_with`].
+    pub fn load_thread_with(&self, chat_id: &str, message_id: &str, max_pages: usize) -> Result<ThreadTree> {
+        let mut tree = ThreadTree::new();
+        if !self.page_into(chat_id, &mut tree, max_pages, |tree| tree.has_ancestry(message_id))? {
+            return Err(pages_exhausted(&format!("Thread of message {}", message_id), chat_id, max_pages));
+        }
+        if !tree.contains(message_id) {
+            return Err(BeeperError::NotFound(format!("Message {} in chat {}", message_id, chat_id)));
+        }
+        Ok(tree)
+    }
+
+    /// Loads the parents missing from `tree` by paging back through the chat
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::fetch_missing_parents`].
+    pub fn fetch_missing_parents(&self, chat_id: &str, tree: &mut ThreadTree) -> Result<()> {
+        self.fetch_missing_parents_with(chat_id, tree, DEFAULT_MAX_PAGES)
+    }
+
+    /// Loads the parents missing from `tree`, reading at most `max_pages` pages
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::fetch_missing_parents_with`].
+    pub fn fetch_missing_parents_with(&self, chat_id: &str, tree: &mut ThreadTree, max_pages: usize) -> Result<()> {
+        if tree.missing_parents().is_empty() {
+            return Ok(());
+        }
+        if !self.page_into(chat_id, tree, max_pages, |tree| tree.missing_parents().is_empty())? {
+            return Err(pages_exhausted("Missing parents", chat_id, max_pages));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idempotency::{IdempotencyStore, MemoryIdempotencyStore};
+    use crate::test_support;
+
+    #[test]
+    fn test_confirm_recovered_send() {
+        let now = Utc::now();
+        let copy = Message {
+            is_sender: Some(true),
+            ..test_support::message("m9", "me", "9", &now.to_rfc3339(), "Paging on-call")
+        };
+        let page = ListMessagesOutput { items: vec![copy], has_more: false };
+        let base_url = test_support::serve_json(serde_json::to_string(&page).unwrap());
+
+        let store = MemoryIdempotencyStore::new();
+        let attempt = IdempotencyRecord::Attempted { at: now - chrono::Duration::minutes(1) };
+        store.put(&scoped_key("chat-1", "page-1"), attempt).unwrap();
+        let client = BeeperClient::new("token", base_url.as_str()).with_idempotency_store(store);
+        let input = SendMessageInput {
+            text: "Paging on-call".to_string(),
+            idempotency_key: Some("page-1".to_string()),
+            ..Default::default()
+        };
+
+        let delivery = client.send_and_confirm("chat-1", input, Duration::from_secs(5)).unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Delivered);
+        assert_eq!(delivery.message.unwrap().id, "m9");
+    }
+}
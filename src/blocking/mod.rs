@@ -0,0 +1,150 @@
+//! Blocking (synchronous) client for Beeper Desktop API
+//!
+//! Mirrors the async [`crate::BeeperClient`] method-for-method on top of reqwest's blocking
+//! client, so it can be used from build scripts and small CLIs without an async runtime.
+//! Enabled with the `blocking` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::blocking::BeeperClient;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = BeeperClient::new("your-token-here", "http://localhost:23373");
+//!     let accounts = client.get_accounts()?;
+//!     println!("{:?}", accounts);
+//!     Ok(())
+//! }
+//! ```
+
+pub mod chats;
+pub mod messages;
+pub mod accounts;
+pub mod contacts;
+pub mod search;
+pub mod app;
+mod utils;
+
+use std::sync::Arc;
+use reqwest::blocking::Client;
+use crate::idempotency::{IdempotencyStore, MemoryIdempotencyStore};
+use self::utils::handle_response;
+use crate::client::DEFAULT_BASE_URL;
+
+/// Blocking Beeper API client
+///
+/// Stores the bearer token and base URL for API requests.
+/// All requests to the API will include the token in the Authorization header.
+#[derive(Clone)]
+pub struct BeeperClient {
+    token: String,
+    base_url: String,
+    http_client: Client,
+    idempotency: Arc<dyn IdempotencyStore>,
+}
+
+impl BeeperClient {
+    /// Creates a new blocking Beeper API client
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Bearer token for authentication
+    /// * `base_url` - Base URL of the Beeper Desktop API server
+    pub fn new(token: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            base_url: base_url.into(),
+            http_client: Client::new(),
+            idempotency: Arc::new(MemoryIdempotencyStore::new()),
+        }
+    }
+
+    /// Creates a new blocking Beeper API client with default base URL
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Bearer token for authentication
+    pub fn with_token(token: impl Into<String>) -> Self {
+        Self::new(token, DEFAULT_BASE_URL)
+    }
+
+    /// Updates the bearer token
+    pub fn set_token(&mut self, token: impl Into<String>) {
+        self.token = token.into();
+    }
+
+    /// Updates the base URL
+    pub fn set_base_url(&mut self, base_url: impl Into<String>) {
+        self.base_url = base_url.into();
+    }
+
+    /// Gets the current base URL
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Keeps idempotency keys in `store` instead of in memory
+    ///
+    /// See [`crate::idempotency`].
+    pub fn with_idempotency_store(mut self, store: impl IdempotencyStore + 'static) -> Self {
+        self.idempotency = Arc::new(store);
+        self
+    }
+
+    pub(crate) fn get_auth_header(&self) -> String {
+        format!("Bearer {}", self.token)
+    }
+
+    pub(crate) fn get_base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub(crate) fn get_http_client(&self) -> &Client {
+        &self.http_client
+    }
+
+    pub(crate) fn idempotency_store(&self) -> &dyn IdempotencyStore {
+        self.idempotency.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_client_creation() {
+        let client = BeeperClient::new("test-token", "http://localhost:23373");
+        assert_eq!(client.base_url(), "http://localhost:23373");
+    }
+
+    #[test]
+    fn test_blocking_client_with_default_url() {
+        let client = BeeperClient::with_token("test-token");
+        assert_eq!(client.base_url(), DEFAULT_BASE_URL);
+    }
+
+    #[test]
+    fn test_blocking_client_set_base_url() {
+        let mut client = BeeperClient::new("test-token", "http://localhost:23373");
+        client.set_base_url("http://example.com:8080");
+        assert_eq!(client.base_url(), "http://example.com:8080");
+    }
+
+    #[test]
+    fn test_blocking_get_auth_header() {
+        let client = BeeperClient::new("my-secret-token", "http://localhost:23373");
+        assert_eq!(client.get_auth_header(), "Bearer my-secret-token");
+    }
+
+    #[test]
+    fn test_blocking_unreachable_api_maps_error() {
+        let client = BeeperClient::new("test-token", "http://127.0.0.1:1");
+        match client.get_accounts() {
+            Err(crate::BeeperError::ApiNotReachable { url }) => {
+                assert_eq!(url, "http://127.0.0.1:1");
+            }
+            other => panic!("Expected ApiNotReachable, got {:?}", other.map(|a| a.len())),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Blocking search-related API operations
+
+use crate::models::search::Collector;
+use crate::models::{MessageSearchParams, SearchChatsOutput, SearchMessagesOutput};
+use crate::error::Result;
+use super::{BeeperClient, handle_response};
+
+impl BeeperClient {
+    /// Searches messages across chats
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::search_messages`].
+    pub fn search_messages(
+        &self,
+        query: &str,
+        cursor: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<SearchMessagesOutput> {
+        let mut url = format!("{}/v1/messages/search?q={}", self.get_base_url(), urlencoding::encode(query));
+        crate::client::utils::append_pagination(&mut url, cursor, direction);
+
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+
+    /// Searches messages with structured filters
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::search_messages_with`].
+    pub fn search_messages_with(
+        &self,
+        params: &MessageSearchParams,
+        cursor: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<SearchMessagesOutput> {
+        let query = params.to_query_string();
+        let mut collector = Collector::new(params, direction);
+        let mut cursor = cursor.map(str::to_string);
+
+        loop {
+            let mut url = format!("{}/v1/messages/search?{}", self.get_base_url(), query);
+            crate::client::utils::append_pagination(&mut url, cursor.as_deref(), direction);
+
+            let response = self
+                .get_http_client()
+                .get(&url)
+                .header("Authorization", self.get_auth_header())
+                .send()
+                .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+            match collector.push(handle_response(response)?) {
+                Some(next) => cursor = Some(next),
+                None => return Ok(collector.finish()),
+            }
+        }
+    }
+
+    /// Searches chats by title, network, or participants
+    ///
+    /// Blocking counterpart of [`crate::BeeperClient::search_chats`].
+    pub fn search_chats(
+        &self,
+        query: &str,
+        cursor: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<SearchChatsOutput> {
+        let mut url = format!("{}/v1/chats/search?q={}", self.get_base_url(), urlencoding::encode(query));
+        crate::client::utils::append_pagination(&mut url, cursor, direction);
+
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response)
+    }
+}
@@ -0,0 +1,23 @@
+//! Utility functions for blocking API operations
+
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use crate::client::utils::status_error;
+use crate::error::Result;
+
+pub(super) use crate::client::utils::map_request_error;
+
+pub(super) fn handle_response<T: DeserializeOwned>(
+    response: reqwest::blocking::Response,
+) -> Result<T> {
+    match response.status() {
+        StatusCode::OK | StatusCode::CREATED => {
+            let data = response.json::<T>()?;
+            Ok(data)
+        }
+        status => {
+            let body = response.text().unwrap_or_default();
+            Err(status_error(status, &body))
+        }
+    }
+}
@@ -0,0 +1,791 @@
+//! Command routing on top of the event stream
+//!
+//! [`Bot`] drives an [`EventPoller`] and dispatches new messages and reactions to the
+//! handlers registered on it. It skips the current user's own messages, runs at most a
+//! configurable number of handlers per chat at a time, and finishes in-flight handlers
+//! before [`Bot::run`] returns after a [`ShutdownHandle::shutdown`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::BeeperClient;
+//! use beeper_desktop_api::bot::Filter;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = BeeperClient::new("your-token-here", "http://localhost:23373");
+//!
+//!     client
+//!         .bot()
+//!         .on_command("/deploy", |ctx| async move {
+//!             ctx.reply(format!("Deploying {}…", ctx.raw_args())).await?;
+//!             Ok(())
+//!         })
+//!         .on_message(Filter::new().networks(["Slack"]).mentioning(["deploybot"]), |ctx| async move {
+//!             ctx.reply_emoji("👀").await?;
+//!             Ok(())
+//!         })
+//!         .run()
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+use crate::client::BeeperClient;
+use crate::error::{BeeperError, Result};
+use crate::events::{Event, EventPoller};
+use crate::models::mention::mentions_name;
+use crate::models::{Chat, Message, Reaction, RichText, SendMessageInput, SendMessageOutput, User};
+use crate::runtime::{BoxFuture, Runtime};
+
+/// How often a shutting-down bot checks whether in-flight handlers have finished
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+type Handler = Arc<dyn Fn(Context) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+type ErrorHandler = Arc<dyn Fn(&BeeperError) + Send + Sync>;
+
+fn boxed<F, Fut>(handler: F) -> Handler
+where
+    F: Fn(Context) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    Arc::new(move |ctx| Box::pin(handler(ctx)))
+}
+
+/// Conditions an event must meet for a handler to run
+///
+/// Each list matches any of its values; an empty list matches everything. Network names
+/// are compared case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    chat_ids: Vec<String>,
+    account_ids: Vec<String>,
+    networks: Vec<String>,
+    sender_ids: Vec<String>,
+    mentions: Vec<String>,
+    mentioning_me: bool,
+}
+
+impl Filter {
+    /// Creates a filter that matches everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only events in these chats
+    pub fn chats<I: IntoIterator<Item = S>, S: Into<String>>(mut self, chat_ids: I) -> Self {
+        self.chat_ids = chat_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only events on these accounts
+    pub fn accounts<I: IntoIterator<Item = S>, S: Into<String>>(mut self, account_ids: I) -> Self {
+        self.account_ids = account_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only events on these networks, e.g. `WhatsApp`
+    pub fn networks<I: IntoIterator<Item = S>, S: Into<String>>(mut self, networks: I) -> Self {
+        self.networks = networks.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only messages (or reactions) from these user IDs
+    pub fn senders<I: IntoIterator<Item = S>, S: Into<String>>(mut self, sender_ids: I) -> Self {
+        self.sender_ids = sender_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only messages that mention one of these names, as `@name` or a mention link
+    pub fn mentioning<I: IntoIterator<Item = S>, S: Into<String>>(mut self, names: I) -> Self {
+        self.mentions = names
+            .into_iter()
+            .map(|n| n.into().trim_start_matches('@').to_lowercase())
+            .collect();
+        self
+    }
+
+    /// Only messages that mention the bot's own user, see [`Message::mentions_me`]
+    ///
+    /// Useful in large groups, where a bot should not react to every message.
+    pub fn mentioning_me(mut self) -> Self {
+        self.mentioning_me = true;
+        self
+    }
+
+    fn matches(&self, chat_id: &str, chat: Option<&Chat>, sender_id: &str, account_id: Option<&str>) -> bool {
+        if !self.chat_ids.is_empty() && !self.chat_ids.iter().any(|id| id == chat_id) {
+            return false;
+        }
+        if !self.sender_ids.is_empty() && !self.sender_ids.iter().any(|id| id == sender_id) {
+            return false;
+        }
+        if !self.account_ids.is_empty() {
+            let account = account_id.or(chat.map(|c| c.account_id.as_str()));
+            if !account.is_some_and(|a| self.account_ids.iter().any(|id| id == a)) {
+                return false;
+            }
+        }
+        if !self.networks.is_empty() {
+            let network = chat.map(|c| c.network.as_str());
+            if !network.is_some_and(|n| self.networks.iter().any(|want| want.eq_ignore_ascii_case(n))) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Matches a message; `me` are the bot's own users, used by [`Filter::mentioning_me`]
+    pub(crate) fn matches_message(&self, message: &Message, chat: Option<&Chat>, me: &[User]) -> bool {
+        if !self.matches(&message.chat_id, chat, &message.sender_id, message.account_id.as_deref()) {
+            return false;
+        }
+        if !self.mentions.is_empty() {
+            let text = message.text.as_deref().unwrap_or_default();
+            let linked = message.mentions();
+            let mentioned = self.mentions.iter().any(|name| {
+                mentions_name(text, name) || linked.iter().any(|m| m.name.to_lowercase() == *name)
+            });
+            if !mentioned {
+                return false;
+            }
+        }
+        !self.mentioning_me || me.iter().any(|user| message.mentions_me(user))
+    }
+
+    /// Matches a reaction; filters on mentions never match reactions
+    fn matches_reaction(&self, chat_id: &str, chat: Option<&Chat>, participant_id: &str) -> bool {
+        self.mentions.is_empty() && !self.mentioning_me && self.matches(chat_id, chat, participant_id, None)
+    }
+}
+
+/// Splits `/command args` into the command and its arguments
+fn parse_command(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if !text.starts_with('/') {
+        return None;
+    }
+    match text.split_once(char::is_whitespace) {
+        Some((command, args)) => Some((command, args.trim())),
+        None => Some((text.trim_end(), "")),
+    }
+}
+
+/// What a handler is called with
+#[derive(Clone)]
+pub struct Context {
+    client: BeeperClient,
+    /// Chat the event happened in
+    pub chat_id: String,
+    /// Message the event is about
+    pub message_id: String,
+    /// The new message, for message and command handlers
+    pub message: Option<Message>,
+    /// The chat, if the poller has reported it
+    pub chat: Option<Chat>,
+    /// The new reaction, for reaction handlers
+    pub reaction: Option<Reaction>,
+    args: String,
+}
+
+impl Context {
+    /// Client the bot is running with
+    pub fn client(&self) -> &BeeperClient {
+        &self.client
+    }
+
+    /// Text after the command name, trimmed
+    pub fn raw_args(&self) -> &str {
+        &self.args
+    }
+
+    /// Whitespace-separated command arguments
+    pub fn args(&self) -> Vec<&str> {
+        self.args.split_whitespace().collect()
+    }
+
+    /// Sends a message to the chat without quoting anything
+    pub async fn send(&self, text: impl Into<String>) -> Result<SendMessageOutput> {
+        self.client
+            .send_message(&self.chat_id, SendMessageInput {
+                text: text.into(),
+                ..Default::default()
+            })
+            .await
+    }
+
+    /// Replies to the message that triggered the handler
+    pub async fn reply(&self, text: impl Into<String>) -> Result<SendMessageOutput> {
+        self.client
+            .send_message(&self.chat_id, SendMessageInput {
+                text: text.into(),
+                reply_to_id: Some(self.message_id.clone()),
+                ..Default::default()
+            })
+            .await
+    }
+
+    /// Replies with formatted text, e.g. code snippets or mentions
+    pub async fn reply_rich(&self, text: &RichText) -> Result<SendMessageOutput> {
+        self.reply(text.to_markdown()).await
+    }
+
+    /// Replies with a message containing only an emoji
+    ///
+    /// This is a visible message, not a reaction; the Desktop API has no endpoint for
+    /// adding reactions.
+    pub async fn reply_emoji(&self, emoji: &str) -> Result<SendMessageOutput> {
+        self.reply(emoji).await
+    }
+
+    /// Replies with a link below an optional caption
+    ///
+    /// Nothing is uploaded; the Desktop API cannot send files, so `url` must already be
+    /// reachable by the chat's participants.
+    pub async fn reply_link(&self, url: &str, caption: Option<&str>) -> Result<SendMessageOutput> {
+        let text = match caption {
+            Some(caption) => format!("{}\n{}", caption, url),
+            None => url.to_string(),
+        };
+        self.reply(text).await
+    }
+}
+
+enum Route {
+    Command { name: String, filter: Filter, handler: Handler },
+    Message { filter: Filter, handler: Handler },
+    Reaction { filter: Filter, handler: Handler },
+}
+
+/// Stops a running [`Bot`]
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    /// Asks the bot to stop after the current polling round and in-flight handlers
+    pub fn shutdown(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    /// True once [`ShutdownHandle::shutdown`] was called
+    pub fn is_shutdown(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Default)]
+struct ChatQueue {
+    running: usize,
+    waiting: VecDeque<(Handler, Context)>,
+}
+
+/// Limits how many handlers run per chat and tracks the total in flight
+#[derive(Clone, Default)]
+struct Dispatcher {
+    per_chat: usize,
+    chats: Arc<Mutex<HashMap<String, ChatQueue>>>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Dispatcher {
+    fn dispatch<R: Runtime>(&self, runtime: &R, handler: Handler, ctx: Context, on_error: Option<ErrorHandler>) {
+        let chat_id = ctx.chat_id.clone();
+        {
+            let mut chats = self.chats.lock().expect("dispatcher lock poisoned");
+            let queue = chats.entry(chat_id.clone()).or_default();
+            if queue.running >= self.per_chat {
+                queue.waiting.push_back((handler, ctx));
+                return;
+            }
+            queue.running += 1;
+        }
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        let dispatcher = self.clone();
+        runtime.spawn(Box::pin(async move {
+            let mut next = Some((handler, ctx));
+            while let Some((handler, ctx)) = next.take() {
+                // A panicking handler is already reported by the panic hook; catching it
+                // keeps the chat's queue moving and lets `run()` finish draining
+                if let Ok(Err(e)) = CatchUnwind(handler(ctx)).await {
+                    if let Some(on_error) = &on_error {
+                        on_error(&e);
+                    }
+                }
+
+                let mut chats = dispatcher.chats.lock().expect("dispatcher lock poisoned");
+                let queue = chats.entry(chat_id.clone()).or_default();
+                next = queue.waiting.pop_front();
+                if next.is_none() {
+                    queue.running -= 1;
+                    if queue.running == 0 {
+                        chats.remove(&chat_id);
+                    }
+                }
+            }
+            dispatcher.in_flight.fetch_sub(1, Ordering::SeqCst);
+        }));
+    }
+
+    fn is_idle(&self) -> bool {
+        self.in_flight.load(Ordering::SeqCst) == 0
+    }
+}
+
+/// Resolves to `Err` with the panic payload if the wrapped future panics while polled
+struct CatchUnwind<F>(F);
+
+impl<F: Future + Unpin> Future for CatchUnwind<F> {
+    type Output = std::thread::Result<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let inner = &mut self.0;
+        match std::panic::catch_unwind(AssertUnwindSafe(|| Pin::new(inner).poll(cx))) {
+            Ok(poll) => poll.map(Ok),
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// Routes events from an [`EventPoller`] to registered handlers
+pub struct Bot<R: Runtime> {
+    poller: EventPoller<R>,
+    routes: Vec<Route>,
+    dispatcher: Dispatcher,
+    on_error: Option<ErrorHandler>,
+    shutdown: ShutdownHandle,
+    self_users: Vec<User>,
+    chats: HashMap<String, Chat>,
+}
+
+impl<R: Runtime> Bot<R> {
+    /// Creates a bot driven by the given poller
+    ///
+    /// Handlers for one chat run one at a time unless [`Bot::with_chat_concurrency`] says
+    /// otherwise.
+    pub fn new(poller: EventPoller<R>) -> Self {
+        Self {
+            poller,
+            routes: Vec::new(),
+            dispatcher: Dispatcher {
+                per_chat: 1,
+                ..Default::default()
+            },
+            on_error: None,
+            shutdown: ShutdownHandle::default(),
+            self_users: Vec::new(),
+            chats: HashMap::new(),
+        }
+    }
+
+    /// Sets how many handlers may run at once in a single chat
+    pub fn with_chat_concurrency(mut self, limit: usize) -> Self {
+        self.dispatcher.per_chat = limit.max(1);
+        self
+    }
+
+    /// Runs `handler` for messages starting with `command`, e.g. `/deploy`
+    ///
+    /// When a command matches, plain message handlers are not run for that message.
+    pub fn on_command<F, Fut>(self, command: &str, handler: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.on_command_where(command, Filter::new(), handler)
+    }
+
+    /// Runs `handler` for a command in events matching `filter`
+    pub fn on_command_where<F, Fut>(mut self, command: &str, filter: Filter, handler: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let name = format!("/{}", command.trim_start_matches('/'));
+        self.routes.push(Route::Command { name, filter, handler: boxed(handler) });
+        self
+    }
+
+    /// Runs `handler` for new messages matching `filter`
+    pub fn on_message<F, Fut>(mut self, filter: Filter, handler: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.routes.push(Route::Message { filter, handler: boxed(handler) });
+        self
+    }
+
+    /// Runs `handler` for new reactions matching `filter`; the sender is the reacting user
+    pub fn on_reaction<F, Fut>(mut self, filter: Filter, handler: F) -> Self
+    where
+        F: Fn(Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.routes.push(Route::Reaction { filter, handler: boxed(handler) });
+        self
+    }
+
+    /// Called with errors returned by handlers and failed polling rounds
+    pub fn on_error<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&BeeperError) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(handler));
+        self
+    }
+
+    /// Returns a handle that stops [`Bot::run`]
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Polls and dispatches events until shut down
+    ///
+    /// Polling errors are reported to [`Bot::on_error`] and retried on the next round, except
+    /// [`BeeperError::Unauthorized`], which stops the bot. On shutdown, and before returning
+    /// that error, waits for running and queued handlers to finish.
+    pub async fn run(mut self) -> Result<()> {
+        let client = self.poller.client().clone();
+        let runtime = self.poller.runtime().clone();
+        let interval = self.poller.interval();
+        let mut outcome = Ok(());
+
+        while !self.shutdown.is_shutdown() {
+            if self.self_users.is_empty() {
+                match client.get_accounts().await {
+                    Ok(accounts) => self.self_users = accounts.into_iter().map(|a| a.user).collect(),
+                    Err(BeeperError::Unauthorized) => {
+                        outcome = Err(BeeperError::Unauthorized);
+                        break;
+                    }
+                    Err(e) => self.report(&e),
+                }
+            }
+
+            match self.poller.poll().await {
+                Ok(events) => {
+                    for event in events {
+                        self.handle_event(&client, &runtime, event);
+                    }
+                }
+                Err(BeeperError::Unauthorized) => {
+                    outcome = Err(BeeperError::Unauthorized);
+                    break;
+                }
+                Err(e) => self.report(&e),
+            }
+
+            if !self.shutdown.is_shutdown() {
+                runtime.sleep(interval).await;
+            }
+        }
+
+        while !self.dispatcher.is_idle() {
+            runtime.sleep(DRAIN_CHECK_INTERVAL).await;
+        }
+        outcome
+    }
+
+    fn report(&self, error: &BeeperError) {
+        if let Some(on_error) = &self.on_error {
+            on_error(error);
+        }
+    }
+
+    fn is_self(&self, sender_id: &str, is_sender: Option<bool>) -> bool {
+        is_sender == Some(true) || self.self_users.iter().any(|u| u.id == sender_id)
+    }
+
+    fn handle_event(&mut self, client: &BeeperClient, runtime: &R, event: Event) {
+        match event {
+            Event::ChatUpdated(chat) => {
+                for participant in &chat.participants.items {
+                    if participant.is_self == Some(true) && !self.is_self(&participant.id, None) {
+                        self.self_users.push(participant.clone());
+                    }
+                }
+                self.chats.insert(chat.id.clone(), chat);
+            }
+            Event::Message(message) => {
+                if self.is_self(&message.sender_id, message.is_sender) {
+                    return;
+                }
+                let chat = self.chats.get(&message.chat_id);
+                let command = message.text.as_deref().and_then(parse_command);
+
+                let mut handlers = Vec::new();
+                let mut args = "";
+                if let Some((name, command_args)) = command {
+                    for route in &self.routes {
+                        if let Route::Command { name: route_name, filter, handler } = route {
+                            if route_name.eq_ignore_ascii_case(name) && filter.matches_message(&message, chat, &self.self_users) {
+                                handlers.push(handler.clone());
+                                args = command_args;
+                            }
+                        }
+                    }
+                }
+                if handlers.is_empty() {
+                    for route in &self.routes {
+                        if let Route::Message { filter, handler } = route {
+                            if filter.matches_message(&message, chat, &self.self_users) {
+                                handlers.push(handler.clone());
+                            }
+                        }
+                    }
+                }
+
+                let ctx = Context {
+                    client: client.clone(),
+                    chat_id: message.chat_id.clone(),
+                    message_id: message.id.clone(),
+                    chat: chat.cloned(),
+                    reaction: None,
+                    args: args.to_string(),
+                    message: Some(message.clone()),
+                };
+                for handler in handlers {
+                    self.dispatcher.dispatch(runtime, handler, ctx.clone(), self.on_error.clone());
+                }
+            }
+            Event::Reaction { chat_id, message_id, reaction } => {
+                if self.is_self(&reaction.participant_id, None) {
+                    return;
+                }
+                let chat = self.chats.get(&chat_id);
+                let ctx = Context {
+                    client: client.clone(),
+                    chat_id: chat_id.clone(),
+                    message_id,
+                    message: None,
+                    chat: chat.cloned(),
+                    reaction: Some(reaction.clone()),
+                    args: String::new(),
+                };
+                for route in &self.routes {
+                    if let Route::Reaction { filter, handler } = route {
+                        if filter.matches_reaction(&chat_id, chat, &reaction.participant_id) {
+                            self.dispatcher.dispatch(runtime, handler.clone(), ctx.clone(), self.on_error.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl BeeperClient {
+    /// Creates a [`Bot`] driven by tokio
+    pub fn bot(&self) -> Bot<crate::runtime::TokioRuntime> {
+        Bot::new(self.events())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn message(id: &str, chat_id: &str, sender: &str, text: &str) -> Message {
+        Message {
+            chat_id: chat_id.to_string(),
+            ..test_support::message(id, sender, id, "2025-01-01T10:00:00Z", text)
+        }
+    }
+
+    fn chat(id: &str, network: &str) -> Chat {
+        Chat {
+            id: id.to_string(),
+            account_id: network.to_lowercase(),
+            network: network.to_string(),
+            ..test_support::chat(vec![test_support::me()])
+        }
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("/deploy api prod"), Some(("/deploy", "api prod")));
+        assert_eq!(parse_command("  /status "), Some(("/status", "")));
+        assert_eq!(parse_command("deploy"), None);
+    }
+
+    #[test]
+    fn test_filter_matching() {
+        let slack = chat("c1", "Slack");
+        let msg = message("1", "c1", "u1", "hey @DeployBot, ship it");
+
+        assert!(Filter::new().matches_message(&msg, Some(&slack), &[]));
+        assert!(Filter::new().networks(["slack"]).matches_message(&msg, Some(&slack), &[]));
+        assert!(!Filter::new().networks(["WhatsApp"]).matches_message(&msg, Some(&slack), &[]));
+        assert!(!Filter::new().networks(["Slack"]).matches_message(&msg, None, &[]));
+        assert!(Filter::new().accounts(["slack"]).matches_message(&msg, Some(&slack), &[]));
+        assert!(Filter::new().mentioning(["@deploybot"]).matches_message(&msg, None, &[]));
+        assert!(!Filter::new().mentioning(["deploy"]).matches_message(&msg, None, &[]));
+        assert!(!Filter::new().senders(["u2"]).chats(["c1"]).matches_message(&msg, None, &[]));
+
+        let user = |id: &str, username: &str| User {
+            username: Some(username.to_string()),
+            ..test_support::user(id, username, None)
+        };
+        let me = user("@deploybot:beeper.com", "DeployBot");
+        let other = user("@ops:beeper.com", "ops");
+        assert!(Filter::new().mentioning_me().matches_message(&msg, None, &[me]));
+        assert!(!Filter::new().mentioning_me().matches_message(&msg, None, &[other]));
+        assert!(!Filter::new().mentioning_me().matches_reaction("c1", None, "u1"));
+    }
+
+    #[cfg(feature = "tokio")]
+    mod dispatch {
+        use super::*;
+        use crate::runtime::TokioRuntime;
+
+        fn bot() -> (BeeperClient, Bot<TokioRuntime>) {
+            let client = BeeperClient::new("token", "http://127.0.0.1:9");
+            let bot = Bot::new(client.events());
+            (client, bot)
+        }
+
+        async fn drain(bot: &Bot<TokioRuntime>) {
+            for _ in 0..200 {
+                if bot.dispatcher.is_idle() {
+                    return;
+                }
+                TokioRuntime.sleep(Duration::from_millis(5)).await;
+            }
+            panic!("handlers did not finish");
+        }
+
+        #[tokio::test]
+        async fn test_commands_take_precedence_and_self_messages_are_skipped() {
+            let commands = Arc::new(Mutex::new(Vec::new()));
+            let messages = Arc::new(AtomicUsize::new(0));
+            let (client, bot) = bot();
+            let seen = commands.clone();
+            let count = messages.clone();
+            let mut bot = bot
+                .on_command("deploy", move |ctx| {
+                    let seen = seen.clone();
+                    async move {
+                        seen.lock().unwrap().push(ctx.args().join(","));
+                        Ok(())
+                    }
+                })
+                .on_message(Filter::new(), move |_| {
+                    let count = count.clone();
+                    async move {
+                        count.fetch_add(1, Ordering::SeqCst);
+                        Ok(())
+                    }
+                });
+
+            bot.handle_event(&client, &TokioRuntime, Event::ChatUpdated(chat("c1", "Slack")));
+            bot.handle_event(&client, &TokioRuntime, Event::Message(message("1", "c1", "u1", "/deploy api prod")));
+            bot.handle_event(&client, &TokioRuntime, Event::Message(message("2", "c1", "u1", "hello")));
+            bot.handle_event(&client, &TokioRuntime, Event::Message(message("3", "c1", "me", "/deploy mine")));
+            let mut own = message("4", "c1", "other-device", "hi");
+            own.is_sender = Some(true);
+            bot.handle_event(&client, &TokioRuntime, Event::Message(own));
+            drain(&bot).await;
+
+            assert_eq!(*commands.lock().unwrap(), vec!["api,prod".to_string()]);
+            assert_eq!(messages.load(Ordering::SeqCst), 1);
+        }
+
+        #[tokio::test]
+        async fn test_per_chat_concurrency_limit() {
+            let running = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+            let (client, bot) = bot();
+            let (r, p) = (running.clone(), peak.clone());
+            let mut bot = bot.with_chat_concurrency(2).on_message(Filter::new(), move |_| {
+                let (running, peak) = (r.clone(), p.clone());
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    TokioRuntime.sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            });
+
+            for i in 0..6 {
+                bot.handle_event(&client, &TokioRuntime, Event::Message(message(&i.to_string(), "c1", "u1", "hi")));
+            }
+            drain(&bot).await;
+            assert_eq!(peak.load(Ordering::SeqCst), 2);
+        }
+
+        #[tokio::test]
+        async fn test_panicking_handler_releases_chat() {
+            let handled = Arc::new(AtomicUsize::new(0));
+            let (client, bot) = bot();
+            let count = handled.clone();
+            let mut bot = bot.on_message(Filter::new(), move |ctx| {
+                let count = count.clone();
+                async move {
+                    if ctx.message_id == "1" {
+                        panic!("handler bug");
+                    }
+                    count.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            });
+
+            bot.handle_event(&client, &TokioRuntime, Event::Message(message("1", "c1", "u1", "boom")));
+            bot.handle_event(&client, &TokioRuntime, Event::Message(message("2", "c1", "u1", "hi")));
+            drain(&bot).await;
+            assert_eq!(handled.load(Ordering::SeqCst), 1);
+        }
+
+        #[tokio::test]
+        async fn test_unauthorized_waits_for_handlers() {
+            let finished = Arc::new(AtomicUsize::new(0));
+            let client = BeeperClient::new("token", test_support::serve("401 Unauthorized", "{}".to_string()));
+            let count = finished.clone();
+            let mut bot = Bot::new(client.events()).on_message(Filter::new(), move |_| {
+                let count = count.clone();
+                async move {
+                    TokioRuntime.sleep(Duration::from_millis(50)).await;
+                    count.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            });
+
+            bot.handle_event(&client, &TokioRuntime, Event::Message(message("1", "c1", "u1", "hi")));
+            assert!(matches!(bot.run().await, Err(BeeperError::Unauthorized)));
+            assert_eq!(finished.load(Ordering::SeqCst), 1);
+        }
+
+        #[tokio::test]
+        async fn test_handler_errors_are_reported() {
+            let errors = Arc::new(AtomicUsize::new(0));
+            let (client, bot) = bot();
+            let count = errors.clone();
+            let mut bot = bot
+                .on_error(move |_| {
+                    count.fetch_add(1, Ordering::SeqCst);
+                })
+                .on_reaction(Filter::new(), |_| async { Err(BeeperError::MissingField("x".to_string())) });
+
+            bot.handle_event(&client, &TokioRuntime, Event::Reaction {
+                chat_id: "c1".to_string(),
+                message_id: "1".to_string(),
+                reaction: test_support::reaction("r1", "👍", "u2"),
+            });
+            drain(&bot).await;
+            assert_eq!(errors.load(Ordering::SeqCst), 1);
+        }
+    }
+}
@@ -0,0 +1,449 @@
+//! Sending one message to many chats
+//!
+//! [`BeeperClient::broadcast_with`] sends the same [`SendMessageInput`] to a list of chats,
+//! a few at a time and no faster than each network allows, and reports what happened for
+//! every recipient instead of stopping at the first error.
+//!
+//! The text may use these placeholders, filled in per recipient:
+//!
+//! | Placeholder    | Value |
+//! |----------------|-------|
+//! | `{first_name}` | First word of the other person's [`User::full_name`] in a direct chat |
+//! | `{full_name}`  | The other person's full name (or username) in a direct chat |
+//! | `{chat}`       | [`Chat::display_name`] |
+//! | `{network}`    | [`Chat::network`] |
+//!
+//! In group chats, and when a name is unknown, `{first_name}` and `{full_name}` fall back to
+//! the chat's display name. Extra per-recipient values can be added with
+//! [`BroadcastOptions::with_variables`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use beeper_desktop_api::{BeeperClient, SendMessageInput};
+//! use beeper_desktop_api::broadcast::BroadcastOptions;
+//!
+//! # async fn run(client: BeeperClient, chat_ids: Vec<String>) -> beeper_desktop_api::Result<()> {
+//! let input = SendMessageInput {
+//!     text: "Hi {first_name}, maintenance starts Saturday 02:00 UTC.".to_string(),
+//!     ..Default::default()
+//! };
+//! let options = BroadcastOptions::new()
+//!     .with_concurrency(4)
+//!     .with_network_interval("WhatsApp", Duration::from_secs(3));
+//!
+//! let report = client.broadcast(chat_ids, input, &options).await;
+//! for (chat_id, error) in report.failed() {
+//!     eprintln!("{}: {}", chat_id, error);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::client::BeeperClient;
+use crate::error::{BeeperError, Result};
+use crate::models::{Chat, ChatID, SendMessageInput, SendMessageOutput, User};
+use crate::runtime::Runtime;
+
+/// Number of chats sent to at once unless configured
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Minimum time between two sends on the same network unless configured
+pub const DEFAULT_NETWORK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often a broadcast checks whether all workers are done
+const WAIT_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Throttling and per-recipient values for a broadcast
+#[derive(Debug, Clone)]
+pub struct BroadcastOptions {
+    concurrency: usize,
+    default_interval: Duration,
+    network_intervals: HashMap<String, Duration>,
+    variables: HashMap<ChatID, HashMap<String, String>>,
+}
+
+impl Default for BroadcastOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            default_interval: DEFAULT_NETWORK_INTERVAL,
+            network_intervals: HashMap::new(),
+            variables: HashMap::new(),
+        }
+    }
+}
+
+impl BroadcastOptions {
+    /// Creates options with the default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many chats are sent to at once
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the minimum time between sends on networks without their own interval
+    pub fn with_default_interval(mut self, interval: Duration) -> Self {
+        self.default_interval = interval;
+        self
+    }
+
+    /// Sets the minimum time between sends on one network, e.g. `WhatsApp`
+    pub fn with_network_interval(mut self, network: &str, interval: Duration) -> Self {
+        self.network_intervals.insert(network.to_lowercase(), interval);
+        self
+    }
+
+    /// Adds `{name}` placeholders for one recipient
+    ///
+    /// These override the built-in placeholders of the same name.
+    pub fn with_variables<I, K, V>(mut self, chat_id: &str, variables: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.variables
+            .entry(chat_id.to_string())
+            .or_default()
+            .extend(variables.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    pub(crate) fn variables(&self, chat_id: &str) -> Option<&HashMap<String, String>> {
+        self.variables.get(chat_id)
+    }
+
+    pub(crate) fn interval(&self, network: &str) -> Duration {
+        self.network_intervals
+            .get(&network.to_lowercase())
+            .copied()
+            .unwrap_or(self.default_interval)
+    }
+}
+
+/// Outcome of sending to one chat
+#[derive(Debug)]
+pub struct BroadcastResult {
+    /// Recipient chat
+    pub chat_id: ChatID,
+    /// What `send_message` returned, or why the chat could not be sent to
+    pub outcome: Result<SendMessageOutput>,
+}
+
+/// Per-recipient outcomes of a broadcast, in the order the targets were given
+#[derive(Debug, Default)]
+pub struct BroadcastReport {
+    /// One entry per target
+    pub results: Vec<BroadcastResult>,
+}
+
+impl BroadcastReport {
+    /// Chats that were sent to, with the send output
+    pub fn succeeded(&self) -> impl Iterator<Item = (&str, &SendMessageOutput)> {
+        self.results
+            .iter()
+            .filter_map(|r| r.outcome.as_ref().ok().map(|o| (r.chat_id.as_str(), o)))
+    }
+
+    /// Chats that could not be sent to, with the error
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &BeeperError)> {
+        self.results
+            .iter()
+            .filter_map(|r| r.outcome.as_ref().err().map(|e| (r.chat_id.as_str(), e)))
+    }
+
+    /// True if every chat was sent to
+    pub fn is_complete(&self) -> bool {
+        self.results.iter().all(|r| r.outcome.is_ok())
+    }
+}
+
+/// Hands out send slots so each network sees at most one send per interval
+#[derive(Debug, Default)]
+pub(crate) struct NetworkThrottle {
+    next_slot: Mutex<HashMap<String, Instant>>,
+}
+
+impl NetworkThrottle {
+    /// Reserves the next slot on `network` and returns how long to wait for it
+    pub(crate) fn reserve(&self, network: &str, interval: Duration, now: Instant) -> Duration {
+        let mut next_slot = self.next_slot.lock().expect("throttle lock poisoned");
+        let slot = next_slot.get(&network.to_lowercase()).copied().unwrap_or(now).max(now);
+        next_slot.insert(network.to_lowercase(), slot + interval);
+        slot - now
+    }
+}
+
+/// The other person in a direct chat
+fn recipient(chat: &Chat) -> Option<&User> {
+    if chat.chat_type != "single" {
+        return None;
+    }
+    chat.participants.items.iter().find(|p| p.is_self != Some(true))
+}
+
+/// Fills the broadcast placeholders for one chat
+pub(crate) fn render_for(text: &str, chat: &Chat, extra: Option<&HashMap<String, String>>) -> String {
+    let display_name = chat.display_name();
+    let person = recipient(chat);
+    let full_name = person
+        .and_then(|u| u.full_name.clone().or_else(|| u.username.clone()))
+        .unwrap_or_else(|| display_name.clone());
+    let first_name = full_name.split_whitespace().next().unwrap_or(&full_name).to_string();
+
+    let mut variables: HashMap<&str, String> = HashMap::from([
+        ("first_name", first_name),
+        ("full_name", full_name),
+        ("chat", display_name),
+        ("network", chat.network.clone()),
+    ]);
+    for (name, value) in extra.into_iter().flatten() {
+        variables.insert(name, value.clone());
+    }
+    fill_placeholders(text, &variables)
+}
+
+/// Replaces each `{name}` in `text` with its value, leaving unknown names as they are
+///
+/// One pass, so placeholders inside substituted values are left alone.
+pub(crate) fn fill_placeholders(text: &str, variables: &HashMap<&str, String>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}').and_then(|end| Some((end, variables.get(&after[..end])?))) {
+            Some((end, value)) => {
+                rendered.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Orders the results by target and adds a failure for every target without one
+///
+/// A worker that panicked leaves its target without a result, and if every worker did, the
+/// targets still queued (`unattempted`) were never tried.
+fn complete_results(
+    chat_ids: Vec<ChatID>,
+    results: Vec<(usize, BroadcastResult)>,
+    unattempted: HashSet<usize>,
+) -> Vec<BroadcastResult> {
+    let mut by_index: HashMap<usize, BroadcastResult> = results.into_iter().collect();
+    chat_ids
+        .into_iter()
+        .enumerate()
+        .map(|(index, chat_id)| {
+            by_index.remove(&index).unwrap_or_else(|| {
+                let reason = if unattempted.contains(&index) {
+                    "broadcast stopped before sending"
+                } else {
+                    "broadcast worker panicked; the message may have been sent"
+                };
+                BroadcastResult {
+                    chat_id,
+                    outcome: Err(BeeperError::Internal(reason.to_string())),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Marks a broadcast worker as finished when dropped, including when it panics
+struct WorkerGuard(Arc<AtomicUsize>);
+
+impl Drop for WorkerGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl BeeperClient {
+    /// Sends `input` to every chat in `targets`
+    ///
+    /// Runs up to [`BroadcastOptions::with_concurrency`] sends at once and waits between sends
+    /// on the same network. Each chat is looked up first to fill in placeholders and find its
+    /// network. Failures are collected in the report, never returned early. With an
+    /// [`SendMessageInput::idempotency_key`], rerunning a broadcast only sends to chats that
+    /// did not get the message the first time.
+    pub async fn broadcast_with<R: Runtime>(
+        &self,
+        targets: Vec<ChatID>,
+        input: SendMessageInput,
+        options: &BroadcastOptions,
+        runtime: &R,
+    ) -> BroadcastReport {
+        let total = targets.len();
+        let chat_ids = targets.clone();
+        let queue = Arc::new(Mutex::new(targets.into_iter().enumerate().collect::<VecDeque<_>>()));
+        let results = Arc::new(Mutex::new(Vec::with_capacity(total)));
+        let throttle = Arc::new(NetworkThrottle::default());
+        let running = Arc::new(AtomicUsize::new(0));
+        let input = Arc::new(input);
+        let options = Arc::new(options.clone());
+
+        for _ in 0..options.concurrency.min(total) {
+            running.fetch_add(1, Ordering::SeqCst);
+            let (client, runtime, queue, results, throttle, running, input, options) = (
+                self.clone(),
+                runtime.clone(),
+                queue.clone(),
+                results.clone(),
+                throttle.clone(),
+                running.clone(),
+                input.clone(),
+                options.clone(),
+            );
+            runtime.clone().spawn(Box::pin(async move {
+                let _guard = WorkerGuard(running);
+                loop {
+                    let next = queue.lock().expect("broadcast lock poisoned").pop_front();
+                    let Some((index, chat_id)) = next else { break };
+                    let outcome = async {
+                        let chat = client.get_chat(&chat_id).await?;
+                        let wait = throttle.reserve(&chat.network, options.interval(&chat.network), Instant::now());
+                        if !wait.is_zero() {
+                            runtime.sleep(wait).await;
+                        }
+                        let mut personal = (*input).clone().resolve_mentions()?;
+                        personal.text = render_for(&personal.text, &chat, options.variables(&chat_id));
+                        client.send_message(&chat_id, personal).await
+                    }
+                    .await;
+                    results
+                        .lock()
+                        .expect("broadcast lock poisoned")
+                        .push((index, BroadcastResult { chat_id, outcome }));
+                }
+            }));
+        }
+
+        while running.load(Ordering::SeqCst) > 0 {
+            runtime.sleep(WAIT_INTERVAL).await;
+        }
+
+        let results = std::mem::take(&mut *results.lock().expect("broadcast lock poisoned"));
+        let unattempted = queue.lock().expect("broadcast lock poisoned").iter().map(|(i, _)| *i).collect();
+        BroadcastReport {
+            results: complete_results(chat_ids, results, unattempted),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl BeeperClient {
+    /// Sends `input` to every chat in `targets`, using tokio
+    ///
+    /// See [`BeeperClient::broadcast_with`].
+    pub async fn broadcast(
+        &self,
+        targets: Vec<ChatID>,
+        input: SendMessageInput,
+        options: &BroadcastOptions,
+    ) -> BroadcastReport {
+        self.broadcast_with(targets, input, options, &crate::runtime::TokioRuntime).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, me, user};
+
+    fn chat(chat_type: &str, participants: Vec<User>) -> Chat {
+        Chat {
+            id: "c1".to_string(),
+            title: "Ops Team".to_string(),
+            chat_type: chat_type.to_string(),
+            ..test_support::chat(participants)
+        }
+    }
+
+    #[test]
+    fn test_render_for_recipient() {
+        let dm = chat("single", vec![me(), user("u1", "Ada Lovelace", None)]);
+        let text = "Hi {first_name} ({full_name}) on {network}, ticket {ticket}";
+        let extra = HashMap::from([("ticket".to_string(), "#42".to_string())]);
+        assert_eq!(
+            render_for(text, &dm, Some(&extra)),
+            "Hi Ada (Ada Lovelace) on WhatsApp, ticket #42"
+        );
+
+        let group = chat("group", vec![user("u1", "Ada Lovelace", None)]);
+        assert_eq!(render_for("Hi {first_name}, {chat}", &group, None), "Hi Ops, Ops Team");
+
+        let tricky = chat("single", vec![me(), user("u1", "{chat} {network}", None)]);
+        assert_eq!(render_for("{full_name} {x} {", &tricky, None), "{chat} {network} {x} {");
+    }
+
+    #[test]
+    fn test_network_throttle() {
+        let throttle = NetworkThrottle::default();
+        let now = Instant::now();
+        let second = Duration::from_secs(1);
+
+        assert_eq!(throttle.reserve("WhatsApp", second, now), Duration::ZERO);
+        assert_eq!(throttle.reserve("whatsapp", second, now), second);
+        assert_eq!(throttle.reserve("WhatsApp", second, now), 2 * second);
+        assert_eq!(throttle.reserve("Telegram", second, now), Duration::ZERO);
+        assert_eq!(throttle.reserve("WhatsApp", second, now + 5 * second), Duration::ZERO);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_report_keeps_target_order() {
+        let client = BeeperClient::new("token", "http://127.0.0.1:9");
+        let targets = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let input = SendMessageInput {
+            text: "hi".to_string(),
+            ..Default::default()
+        };
+
+        let report = client.broadcast(targets, input, &BroadcastOptions::new().with_concurrency(2)).await;
+        let order: Vec<_> = report.results.iter().map(|r| r.chat_id.as_str()).collect();
+        assert_eq!(order, vec!["a", "b", "c"]);
+        assert_eq!(report.failed().count(), 3);
+        assert!(!report.is_complete());
+    }
+
+    #[test]
+    fn test_missing_results_are_failures() {
+        let chat_ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let sent = BroadcastResult {
+            chat_id: "a".to_string(),
+            outcome: Ok(SendMessageOutput {
+                chat_id: "a".to_string(),
+                pending_message_id: "p1".to_string(),
+                message_id: None,
+            }),
+        };
+
+        // "b" was taken by a worker that panicked, "c" was still queued
+        let report = BroadcastReport {
+            results: complete_results(chat_ids, vec![(0, sent)], HashSet::from([2])),
+        };
+        let failed: Vec<_> = report.failed().map(|(id, e)| (id, e.to_string())).collect();
+        assert_eq!(report.results.len(), 3);
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0].0, "b");
+        assert!(failed[1].1.contains("stopped before sending"));
+        assert!(!report.is_complete());
+    }
+}
@@ -1,6 +1,7 @@
 //! Chat-related API operations
 
-use crate::models::{Chat, CreateChatInput, CreateChatOutput, ListChatsOutput};
+use crate::models::search::Collector;
+use crate::models::{Chat, CreateChatInput, CreateChatOutput, ListChatsOutput, ListChatsParams};
 use crate::error::Result;
 use super::{BeeperClient, handle_response};
 
@@ -15,18 +16,7 @@ impl BeeperClient {
     ) -> Result<ListChatsOutput> {
         let mut url = format!("{}/v1/chats", self.get_base_url());
 
-        if let Some(c) = cursor {
-            url.push_str(&format!("?cursor={}", urlencoding::encode(c)));
-            if direction.is_some() {
-                url.push('&');
-            }
-        } else if direction.is_some() {
-            url.push('?');
-        }
-
-        if let Some(d) = direction {
-            url.push_str(&format!("direction={}", d));
-        }
+        super::utils::append_pagination(&mut url, cursor, direction);
 
         let response = self
             .get_http_client()
@@ -39,6 +29,43 @@ impl BeeperClient {
         handle_response(response).await
     }
 
+    /// Lists chats matching filters
+    ///
+    /// Pages are fetched until `params.limit` chats match or the list runs out. Pass the
+    /// returned cursor back with the same `direction` to continue.
+    pub async fn list_chats_with(
+        &self,
+        params: &ListChatsParams,
+        cursor: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<ListChatsOutput> {
+        let query = params.to_query_string();
+        let mut collector = Collector::new(params, direction);
+        let mut cursor = cursor.map(str::to_string);
+
+        loop {
+            let mut url = format!("{}/v1/chats", self.get_base_url());
+            if !query.is_empty() {
+                url.push('?');
+                url.push_str(&query);
+            }
+            super::utils::append_pagination(&mut url, cursor.as_deref(), direction);
+
+            let response = self
+                .get_http_client()
+                .get(&url)
+                .header("Authorization", self.get_auth_header())
+                .send()
+                .await
+                .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+            match collector.push(handle_response(response).await?) {
+                Some(next) => cursor = Some(next),
+                None => return Ok(collector.finish()),
+            }
+        }
+    }
+
     /// Retrieves details for a specific chat
     ///
     /// Returns chat metadata, participants, and latest message
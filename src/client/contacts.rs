@@ -0,0 +1,167 @@
+//! Contact lookup API operations
+
+use reqwest::StatusCode;
+use crate::models::{Chat, CreateChatInput, ResolvedDm, SearchContactsOutput, User};
+use crate::error::{BeeperError, Result};
+use super::{BeeperClient, handle_response};
+
+impl BeeperClient {
+    /// Searches an account's contacts by name, username, phone number, or email
+    pub async fn search_contacts(&self, account_id: &str, query: &str) -> Result<SearchContactsOutput> {
+        let url = format!(
+            "{}/v1/accounts/{}/contacts/search?q={}",
+            self.get_base_url(),
+            urlencoding::encode(account_id),
+            urlencoding::encode(query)
+        );
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .await
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        handle_response(response).await
+    }
+
+    /// Retrieves a user on an account
+    ///
+    /// Returns [`BeeperError::NotFound`] if the account has no such user.
+    pub async fn get_user(&self, account_id: &str, user_id: &str) -> Result<User> {
+        let url = format!(
+            "{}/v1/accounts/{}/users/{}",
+            self.get_base_url(),
+            urlencoding::encode(account_id),
+            urlencoding::encode(user_id)
+        );
+        let response = self
+            .get_http_client()
+            .get(&url)
+            .header("Authorization", self.get_auth_header())
+            .send()
+            .await
+            .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(user_not_found(account_id, user_id));
+        }
+        handle_response(response).await
+    }
+
+    /// Finds or creates the direct message chat with a person
+    ///
+    /// `handle` is a phone number, username, or email, and only a contact whose handle
+    /// matches it exactly is used. Every page of chats matching the contact's name is
+    /// checked for an existing single chat with them on the account; if there is none, one
+    /// is created. Returns [`BeeperError::NotFound`] if no contact matches exactly.
+    pub async fn resolve_dm(&self, account_id: &str, handle: &str) -> Result<ResolvedDm> {
+        let contacts = self.search_contacts(account_id, handle).await?;
+        let user = pick_contact(contacts.items, handle)?;
+
+        let query = user.full_name.as_deref().unwrap_or(handle);
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = self.search_chats(query, cursor.as_deref(), Some("before")).await?;
+            let next = page.oldest_cursor.filter(|_| page.has_more && !page.items.is_empty());
+            if let Some(chat) = find_dm(page.items, account_id, &user.id) {
+                return Ok(ResolvedDm { user, chat, created: false });
+            }
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let output = self
+            .create_chat(CreateChatInput {
+                account_id: account_id.to_string(),
+                participant_ids: vec![user.id.clone()],
+                title: None,
+            })
+            .await?;
+        let chat = self.get_chat(&output.chat_id).await?;
+        Ok(ResolvedDm { user, chat, created: true })
+    }
+}
+
+/// Picks the contact whose handle matches exactly from search results
+///
+/// Fuzzy matches are never used, so a similar phone number cannot pick the wrong person.
+pub(crate) fn pick_contact(mut contacts: Vec<User>, handle: &str) -> Result<User> {
+    contacts.retain(|u| !u.is_self.unwrap_or(false));
+    match contacts.iter().position(|u| u.matches_handle(handle)) {
+        Some(index) => Ok(contacts.swap_remove(index)),
+        None if contacts.is_empty() => Err(BeeperError::NotFound(format!("No contact matches '{}'", handle))),
+        None => Err(BeeperError::NotFound(format!(
+            "{} contacts are similar to '{}' but none match exactly; use their full phone number, username, or email",
+            contacts.len(),
+            handle
+        ))),
+    }
+}
+
+/// Error for a user lookup that returned 404
+pub(crate) fn user_not_found(account_id: &str, user_id: &str) -> BeeperError {
+    BeeperError::NotFound(format!("User {} on account {}", user_id, account_id))
+}
+
+/// Finds the single chat on an account whose other participant is `user_id`
+pub(crate) fn find_dm(chats: Vec<Chat>, account_id: &str, user_id: &str) -> Option<Chat> {
+    chats.into_iter().find(|chat| {
+        chat.chat_type == "single"
+            && chat.account_id == account_id
+            && chat.participants.items.iter().any(|p| p.id == user_id)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn user(id: &str, phone: Option<&str>, username: Option<&str>) -> User {
+        User {
+            username: username.map(str::to_string),
+            phone_number: phone.map(str::to_string),
+            ..test_support::user(id, id, None)
+        }
+    }
+
+    #[test]
+    fn test_pick_contact_prefers_exact_handle() {
+        let contacts = vec![
+            user("a", Some("+15550100000"), None),
+            user("b", Some("+15550102000"), Some("bob")),
+        ];
+        assert_eq!(pick_contact(contacts.clone(), "+1 (555) 010-2000").unwrap().id, "b");
+        assert_eq!(pick_contact(contacts.clone(), "@Bob").unwrap().id, "b");
+        assert!(matches!(pick_contact(contacts, "carol"), Err(BeeperError::NotFound(_))));
+        assert!(matches!(
+            pick_contact(vec![user("c", Some("+15550103000"), None)], "+15550103001"),
+            Err(BeeperError::NotFound(_))
+        ));
+        assert!(matches!(pick_contact(vec![], "carol"), Err(BeeperError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_find_dm() {
+        let chat: Chat = serde_json::from_value(serde_json::json!({
+            "id": "dm-1",
+            "accountID": "whatsapp",
+            "network": "WhatsApp",
+            "title": "Bob",
+            "type": "single",
+            "participants": {"items": [{"id": "me", "isSelf": true}, {"id": "b"}], "hasMore": false, "total": 2},
+            "unreadCount": 0,
+            "isArchived": false,
+            "isMuted": false,
+            "isPinned": false
+        }))
+        .unwrap();
+
+        assert!(find_dm(vec![chat.clone()], "whatsapp", "b").is_some());
+        assert!(find_dm(vec![chat.clone()], "signal", "b").is_none());
+        assert!(find_dm(vec![chat], "whatsapp", "c").is_none());
+    }
+}
@@ -1,9 +1,29 @@
 //! Message-related API operations
 
-use crate::models::{ListMessagesOutput, SendMessageInput, SendMessageOutput};
-use crate::error::Result;
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use crate::models::{
+    compare_sort_keys, Chat, Delivery, DeliveryStatus, ListMessagesOutput, Message, MessageContext, SendMessageInput,
+    SendMessageOutput,
+};
+use crate::error::{BeeperError, Result};
+use crate::idempotency::{never_sent, scoped_key, IdempotencyRecord, IdempotencyStore};
+use crate::runtime::Runtime;
+use crate::thread::{older_cursor, pages_exhausted, DEFAULT_MAX_PAGES};
 use super::{BeeperClient, handle_response};
 
+/// How often [`BeeperClient::send_and_confirm_with`] checks the chat
+pub const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Allowed difference between the local clock and message timestamps when confirming
+const CLOCK_SKEW: chrono::Duration = chrono::Duration::seconds(5);
+
+/// Prefix of the attribution header on forwarded messages
+///
+/// Relays skip messages starting with it so forwarded copies are never forwarded again.
+pub const FORWARD_MARKER: &str = "↪";
+
 impl BeeperClient {
     /// Lists all messages in a chat
     ///
@@ -20,18 +40,7 @@ impl BeeperClient {
             urlencoding::encode(chat_id)
         );
 
-        if let Some(c) = cursor {
-            url.push_str(&format!("?cursor={}", urlencoding::encode(c)));
-            if direction.is_some() {
-                url.push('&');
-            }
-        } else if direction.is_some() {
-            url.push('?');
-        }
-
-        if let Some(d) = direction {
-            url.push_str(&format!("direction={}", d));
-        }
+        super::utils::append_pagination(&mut url, cursor, direction);
 
         let response = self
             .get_http_client()
@@ -48,7 +57,49 @@ impl BeeperClient {
     ///
     /// Sends a text message to a specific chat. Supports replying to existing messages.
     /// Returns the sent message ID.
+    ///
+    /// With [`SendMessageInput::idempotency_key`] set, repeating the call returns the first
+    /// call's output instead of sending again; see [`crate::idempotency`]. Spans in
+    /// [`SendMessageInput::mentions`] are sent as mention links.
     pub async fn send_message(&self, chat_id: &str, input: SendMessageInput) -> Result<SendMessageOutput> {
+        let input = input.resolve_mentions()?;
+        let Some(key) = input.idempotency_key.as_deref() else {
+            return self.post_message(chat_id, &input).await;
+        };
+        let key = scoped_key(chat_id, key);
+        let store = self.idempotency_store();
+
+        // Claims the key atomically, so concurrent first sends cannot both go out
+        let attempt = IdempotencyRecord::Attempted { at: Utc::now() };
+        match store.insert_if_absent(&key, attempt.clone())? {
+            Some(IdempotencyRecord::Completed { output }) => return Ok(output),
+            Some(IdempotencyRecord::Attempted { at }) => {
+                // The earlier attempt may have gone through without us hearing back
+                let page = self.list_messages(chat_id, None, None).await?;
+                if let Some(output) = recovered_output(chat_id, &page.items, &input, at) {
+                    store.put(&key, IdempotencyRecord::Completed { output: output.clone() })?;
+                    return Ok(output);
+                }
+                store.put(&key, attempt)?;
+            }
+            None => {}
+        }
+
+        match self.post_message(chat_id, &input).await {
+            Ok(output) => {
+                store.put(&key, IdempotencyRecord::Completed { output: output.clone() })?;
+                Ok(output)
+            }
+            Err(e) => {
+                if never_sent(&e) {
+                    store.remove(&key)?;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn post_message(&self, chat_id: &str, input: &SendMessageInput) -> Result<SendMessageOutput> {
         let url = format!(
             "{}/v1/chats/{}/messages",
             self.get_base_url(),
@@ -59,11 +110,538 @@ impl BeeperClient {
             .get_http_client()
             .post(&url)
             .header("Authorization", self.get_auth_header())
-            .json(&input)
+            .json(input)
             .send()
             .await
             .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
 
         handle_response(response).await
     }
+
+    /// Sends a message and waits until it shows up in the chat
+    ///
+    /// Polls the newest page of the chat every [`CONFIRM_POLL_INTERVAL`] until the message
+    /// appears under its final ID ([`DeliveryStatus::Delivered`]) or `timeout` passes. On
+    /// timeout the status is [`DeliveryStatus::Sent`] if the message was seen under its
+    /// pending ID, and [`DeliveryStatus::Pending`] otherwise. An API error from the send
+    /// itself is reported as [`DeliveryStatus::Failed`]; connection and auth errors are
+    /// returned as errors, since nothing was sent.
+    ///
+    /// Once the message is sent, errors while polling are retried until `timeout`, so the
+    /// pending ID is never lost. The API does not map pending IDs to final ones, so the
+    /// final copy is the newest own message with the same text and reply target that sorts
+    /// after the chat's newest message before the send. A keyed send that replays an
+    /// earlier one is matched without that bound, and an output that already names the
+    /// final message is delivered as soon as that message is seen.
+    pub async fn send_and_confirm_with<R: Runtime>(
+        &self,
+        chat_id: &str,
+        input: SendMessageInput,
+        runtime: &R,
+        timeout: Duration,
+    ) -> Result<Delivery> {
+        let input = input.resolve_mentions()?;
+        let started = Instant::now();
+        let before = self.list_messages(chat_id, None, None).await?;
+        let (sent_at, after) = match replays_earlier_send(self.idempotency_store(), chat_id, &input)? {
+            true => (DateTime::UNIX_EPOCH, None),
+            false => (Utc::now(), newest_sort_key(&before.items)),
+        };
+        let output = match self.send_message(chat_id, input.clone()).await {
+            Ok(output) => output,
+            Err(e @ BeeperError::ApiError { .. }) => return Ok(failed_delivery(chat_id, &e)),
+            Err(e) => return Err(e),
+        };
+
+        let mut delivery = pending_delivery(chat_id, &output);
+        loop {
+            // The message is out; a failed poll only delays confirmation
+            if let Ok(page) = self.list_messages(chat_id, None, None).await {
+                let found = confirm_delivery(&page.items, &output, &input, sent_at, after.as_deref());
+                if let Some((status, message)) = found {
+                    delivery.status = status;
+                    delivery.message = Some(message);
+                }
+            }
+            if delivery.status == DeliveryStatus::Delivered || started.elapsed() >= timeout {
+                return Ok(delivery);
+            }
+            runtime.sleep(CONFIRM_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed()))).await;
+        }
+    }
+
+    /// Forwards a message to another chat
+    ///
+    /// Sends the text below an attribution header naming the sender and source chat. The
+    /// Desktop API cannot upload files, so each attachment is left out and noted as
+    /// `[attachment: name]`.
+    /// Returns [`BeeperError::NotFound`] if the message is not in the source chat.
+    pub async fn forward_message(
+        &self,
+        source_chat_id: &str,
+        message_id: &str,
+        target_chat_id: &str,
+    ) -> Result<SendMessageOutput> {
+        let chat = self.get_chat(source_chat_id).await?;
+        let message = self.get_message(source_chat_id, message_id).await?;
+        self.forward(&message, Some(&chat), target_chat_id).await
+    }
+
+    /// Forwards an already loaded message; see [`BeeperClient::forward_message`]
+    pub(crate) async fn forward(
+        &self,
+        message: &Message,
+        chat: Option<&Chat>,
+        target_chat_id: &str,
+    ) -> Result<SendMessageOutput> {
+        let input = SendMessageInput {
+            text: forward_text(message, chat),
+            ..Default::default()
+        };
+        self.send_message(target_chat_id, input).await
+    }
+
+    /// Returns the message with `message_id`
+    ///
+    /// The Desktop API cannot look up a message by ID, so this pages back from the newest
+    /// message until it is found; older messages take more requests. Fails with
+    /// [`BeeperError::NotFound`] if the message is not in the chat, or not within
+    /// [`DEFAULT_MAX_PAGES`] pages; see [`BeeperClient::get_message_with`].
+    pub async fn get_message(&self, chat_id: &str, message_id: &str) -> Result<Message> {
+        self.get_message_with(chat_id, message_id, DEFAULT_MAX_PAGES).await
+    }
+
+    /// Returns the message with `message_id`, reading at most `max_pages` pages
+    pub async fn get_message_with(&self, chat_id: &str, message_id: &str, max_pages: usize) -> Result<Message> {
+        let mut cursor: Option<String> = None;
+        for _ in 0..max_pages {
+            let page = self.list_messages(chat_id, cursor.as_deref(), cursor.as_ref().map(|_| "before")).await?;
+            if let Some(message) = page.items.iter().find(|m| m.id == message_id) {
+                return Ok(message.clone());
+            }
+            cursor = older_cursor(&page);
+            if cursor.is_none() {
+                return Err(BeeperError::NotFound(format!("Message {} in chat {}", message_id, chat_id)));
+            }
+        }
+        Err(pages_exhausted(&format!("Message {}", message_id), chat_id, max_pages))
+    }
+
+    /// Returns the message with `message_id` together with up to `before` older and
+    /// `after` newer messages
+    ///
+    /// Finds the message like [`BeeperClient::get_message`], then loads its surroundings
+    /// with [`BeeperClient::get_context_around`].
+    pub async fn get_message_context(
+        &self,
+        chat_id: &str,
+        message_id: &str,
+        before: usize,
+        after: usize,
+    ) -> Result<MessageContext> {
+        let message = self.get_message(chat_id, message_id).await?;
+        self.get_context_around(message, before, after).await
+    }
+
+    /// Loads up to `before` older and `after` newer messages around an already loaded
+    /// message, e.g. a hit from [`BeeperClient::search_messages`]
+    ///
+    /// Pages outward from the message's sort key, so no lookup by ID is needed.
+    pub async fn get_context_around(&self, message: Message, before: usize, after: usize) -> Result<MessageContext> {
+        let mut older = ContextSide::new(&message, "before", before);
+        while let Some(cursor) = older.cursor() {
+            let page = self.list_messages(&message.chat_id, Some(&cursor), Some("before")).await?;
+            older.push(page);
+        }
+        let mut newer = ContextSide::new(&message, "after", after);
+        while let Some(cursor) = newer.cursor() {
+            let page = self.list_messages(&message.chat_id, Some(&cursor), Some("after")).await?;
+            newer.push(page);
+        }
+        Ok(context(message, older, newer))
+    }
+}
+
+/// Messages collected on one side of a message for [`BeeperClient::get_context_around`]
+pub(crate) struct ContextSide {
+    anchor_id: String,
+    anchor_key: String,
+    direction: &'static str,
+    limit: usize,
+    items: Vec<Message>,
+    next: Option<String>,
+    has_more: bool,
+}
+
+impl ContextSide {
+    pub(crate) fn new(anchor: &Message, direction: &'static str, limit: usize) -> Self {
+        Self {
+            anchor_id: anchor.id.clone(),
+            anchor_key: anchor.sort_key.clone(),
+            direction,
+            limit,
+            items: Vec::new(),
+            next: (limit > 0).then(|| anchor.sort_key.clone()),
+            has_more: false,
+        }
+    }
+
+    /// Cursor for the next page to load, or `None` once enough messages are collected
+    pub(crate) fn cursor(&self) -> Option<String> {
+        self.next.clone()
+    }
+
+    /// Adds the messages of a page that lie on this side of the anchor
+    pub(crate) fn push(&mut self, page: ListMessagesOutput) {
+        let wanted = if self.direction == "before" { Ordering::Less } else { Ordering::Greater };
+        let edge = page
+            .items
+            .iter()
+            .map(|m| m.sort_key.as_str())
+            .reduce(|a, b| if compare_sort_keys(b, a) == wanted { b } else { a })
+            .map(str::to_string);
+        self.items.extend(
+            page.items
+                .into_iter()
+                .filter(|m| m.id != self.anchor_id && compare_sort_keys(&m.sort_key, &self.anchor_key) == wanted),
+        );
+        self.has_more = page.has_more;
+        // Stop on an empty page or one that does not move past the last cursor
+        self.next = edge
+            .filter(|edge| page.has_more && self.items.len() < self.limit && self.next.as_deref() != Some(edge));
+    }
+
+    /// The nearest `limit` messages, oldest first, and whether there are more beyond them
+    pub(crate) fn finish(mut self) -> (Vec<Message>, bool) {
+        self.items.sort_by(|a, b| compare_sort_keys(&a.sort_key, &b.sort_key));
+        self.items.dedup_by(|a, b| a.id == b.id);
+        let extra = self.items.len().saturating_sub(self.limit);
+        if self.direction == "before" {
+            self.items.drain(..extra);
+        } else {
+            self.items.truncate(self.limit);
+        }
+        (self.items, self.has_more || extra > 0)
+    }
+}
+
+/// Assembles a [`MessageContext`] from both sides of `message`
+pub(crate) fn context(message: Message, older: ContextSide, newer: ContextSide) -> MessageContext {
+    let (before, has_more_before) = older.finish();
+    let (after, has_more_after) = newer.finish();
+    MessageContext {
+        before,
+        message,
+        after,
+        has_more_before,
+        has_more_after,
+    }
+}
+
+/// Delivery for a message the API rejected
+pub(crate) fn failed_delivery(chat_id: &str, error: &BeeperError) -> Delivery {
+    Delivery {
+        status: DeliveryStatus::Failed,
+        chat_id: chat_id.to_string(),
+        pending_message_id: None,
+        message: None,
+        error: Some(error.to_string()),
+    }
+}
+
+/// Delivery for a message the API accepted but that has not been seen yet
+pub(crate) fn pending_delivery(chat_id: &str, output: &SendMessageOutput) -> Delivery {
+    Delivery {
+        status: DeliveryStatus::Pending,
+        chat_id: chat_id.to_string(),
+        pending_message_id: Some(output.pending_message_id.clone()).filter(|id| !id.is_empty()),
+        message: None,
+        error: None,
+    }
+}
+
+/// Output for an earlier keyed send found in the chat, if it went through
+///
+/// A heuristic: without a sort key from before the attempt, any own message with the same
+/// text and reply target from no earlier than `attempted_at` (less clock skew) counts.
+pub(crate) fn recovered_output(
+    chat_id: &str,
+    messages: &[Message],
+    input: &SendMessageInput,
+    attempted_at: DateTime<Utc>,
+) -> Option<SendMessageOutput> {
+    match_delivery(messages, None, input, attempted_at, None).map(|(_, message)| SendMessageOutput {
+        chat_id: chat_id.to_string(),
+        pending_message_id: String::new(),
+        message_id: Some(message.id),
+    })
+}
+
+/// True if `input` carries an idempotency key that already has a record
+///
+/// Such a send returns the earlier output, so its copy in the chat predates the send.
+pub(crate) fn replays_earlier_send(
+    store: &dyn IdempotencyStore,
+    chat_id: &str,
+    input: &SendMessageInput,
+) -> Result<bool> {
+    match input.idempotency_key.as_deref() {
+        Some(key) => Ok(store.get(&scoped_key(chat_id, key))?.is_some()),
+        None => Ok(false),
+    }
+}
+
+/// Finds the message behind `output` in a page of the chat
+///
+/// An output that names the final message, as one recovered from an earlier keyed send
+/// does, is [`DeliveryStatus::Delivered`] once that message is seen. Otherwise the page is
+/// matched with [`match_delivery`] on the pending ID.
+pub(crate) fn confirm_delivery(
+    messages: &[Message],
+    output: &SendMessageOutput,
+    input: &SendMessageInput,
+    sent_at: DateTime<Utc>,
+    after: Option<&str>,
+) -> Option<(DeliveryStatus, Message)> {
+    if let Some(message) = output.message_id.as_deref().and_then(|id| messages.iter().find(|m| m.id == id)) {
+        return Some((DeliveryStatus::Delivered, message.clone()));
+    }
+    let pending_message_id = Some(output.pending_message_id.as_str()).filter(|id| !id.is_empty());
+    match_delivery(messages, pending_message_id, input, sent_at, after)
+}
+
+/// Sort key of the newest message in a page
+pub(crate) fn newest_sort_key(messages: &[Message]) -> Option<String> {
+    messages
+        .iter()
+        .max_by(|a, b| compare_sort_keys(&a.sort_key, &b.sort_key))
+        .map(|m| m.sort_key.clone())
+}
+
+/// Finds a sent message in a page of the chat
+///
+/// A message still carrying the pending ID is [`DeliveryStatus::Sent`]. Otherwise the newest
+/// own message with the same text and reply target, sent no earlier than `sent_at`, is taken
+/// to be the delivered copy. This is a heuristic, since the API does not link pending and
+/// final IDs; passing `after`, the newest sort key before the send, rules out earlier
+/// identical messages.
+pub(crate) fn match_delivery(
+    messages: &[Message],
+    pending_message_id: Option<&str>,
+    input: &SendMessageInput,
+    sent_at: DateTime<Utc>,
+    after: Option<&str>,
+) -> Option<(DeliveryStatus, Message)> {
+    if let Some(message) = messages.iter().find(|m| Some(m.id.as_str()) == pending_message_id) {
+        return Some((DeliveryStatus::Sent, message.clone()));
+    }
+
+    messages
+        .iter()
+        .filter(|m| m.is_sender == Some(true))
+        .filter(|m| after.is_none_or(|after| compare_sort_keys(&m.sort_key, after) == Ordering::Greater))
+        .filter(|m| m.text.as_deref() == Some(input.text.as_str()) && m.reply_to_id == input.reply_to_id)
+        .filter(|m| {
+            DateTime::parse_from_rfc3339(&m.timestamp).is_ok_and(|t| t.with_timezone(&Utc) >= sent_at - CLOCK_SKEW)
+        })
+        .max_by(|a, b| compare_sort_keys(&a.sort_key, &b.sort_key))
+        .map(|m| (DeliveryStatus::Delivered, m.clone()))
+}
+
+/// Builds the text of a forwarded message: attribution header, text, then a note per attachment
+pub(crate) fn forward_text(message: &Message, chat: Option<&Chat>) -> String {
+    let sender = message.sender_name.as_deref().unwrap_or(&message.sender_id);
+    let source = chat.map(|c| c.display_name()).unwrap_or_else(|| message.chat_id.clone());
+    let mut text = format!("{} {} in {}:", FORWARD_MARKER, sender, source);
+    if let Some(body) = message.text.as_deref().filter(|t| !t.is_empty()) {
+        text.push('\n');
+        text.push_str(body);
+    }
+    for attachment in message.attachments.iter().flatten() {
+        let name = attachment.file_name.as_deref().unwrap_or(&attachment.typ);
+        text.push_str(&format!("\n[attachment: {}]", name));
+    }
+    text
+}
+
+/// True if the text starts with the attribution header of [`forward_text`]
+pub(crate) fn is_forwarded(text: &str) -> bool {
+    text.trim_start().starts_with(FORWARD_MARKER)
+}
+
+#[cfg(feature = "tokio")]
+impl BeeperClient {
+    /// Sends a message and waits until it shows up in the chat, using tokio
+    ///
+    /// See [`BeeperClient::send_and_confirm_with`].
+    pub async fn send_and_confirm(&self, chat_id: &str, input: SendMessageInput, timeout: Duration) -> Result<Delivery> {
+        self.send_and_confirm_with(chat_id, input, &crate::runtime::TokioRuntime, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Attachment;
+    use crate::test_support;
+
+    fn own_message(id: &str, text: &str, timestamp: &str, sort_key: &str) -> Message {
+        Message {
+            is_sender: Some(true),
+            ..test_support::message(id, "me", sort_key, timestamp, text)
+        }
+    }
+
+    #[test]
+    fn test_match_delivery() {
+        let input = SendMessageInput {
+            text: "Paging on-call".to_string(),
+            ..Default::default()
+        };
+        let sent_at: DateTime<Utc> = "2025-01-01T10:00:00Z".parse().unwrap();
+
+        let echo = vec![own_message("pending-1", "Paging on-call", "2025-01-01T10:00:00Z", "5")];
+        let (status, _) = match_delivery(&echo, Some("pending-1"), &input, sent_at, None).unwrap();
+        assert_eq!(status, DeliveryStatus::Sent);
+
+        let page = vec![
+            own_message("old", "Paging on-call", "2025-01-01T09:00:00Z", "3"),
+            own_message("$final", "Paging on-call", "2025-01-01T10:00:01Z", "6"),
+            own_message("other", "Something else", "2025-01-01T10:00:02Z", "7"),
+        ];
+        let (status, message) = match_delivery(&page, Some("pending-1"), &input, sent_at, Some("3")).unwrap();
+        assert_eq!(status, DeliveryStatus::Delivered);
+        assert_eq!(message.id, "$final");
+        assert_eq!(newest_sort_key(&page).as_deref(), Some("7"));
+
+        assert!(match_delivery(&page[..1], Some("pending-1"), &input, sent_at, None).is_none());
+
+        // An identical ping from just before the send is not taken for the new one
+        let earlier = vec![own_message("ping", "Paging on-call", "2025-01-01T09:59:58Z", "4")];
+        assert!(match_delivery(&earlier, Some("pending-1"), &input, sent_at, None).is_some());
+        assert!(match_delivery(&earlier, Some("pending-1"), &input, sent_at, Some("4")).is_none());
+    }
+
+    #[test]
+    fn test_forward_text() {
+        let mut message: Message = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "chatID": "c1",
+            "senderID": "u1",
+            "senderName": "Alice",
+            "text": "Order #42 is late",
+            "timestamp": "2025-01-01T10:00:00Z",
+            "sortKey": "1"
+        }))
+        .unwrap();
+        let text = forward_text(&message, None);
+        assert_eq!(text, "↪ Alice in c1:\nOrder #42 is late");
+        assert!(is_forwarded(&text));
+
+        message.text = None;
+        let attachment = |file_name: Option<&str>| Attachment {
+            typ: "img".to_string(),
+            src_url: Some("mxc://example.com/a".to_string()),
+            mime_type: None,
+            file_name: file_name.map(str::to_string),
+            file_size: None,
+            is_gif: None,
+            is_sticker: None,
+            is_voice_note: None,
+            duration: None,
+            poster_img: None,
+        };
+        message.attachments = Some(vec![attachment(Some("invoice.pdf")), attachment(None)]);
+        assert_eq!(
+            forward_text(&message, None),
+            "↪ Alice in c1:\n[attachment: invoice.pdf]\n[attachment: img]"
+        );
+        assert!(!is_forwarded("Order #42 is late"));
+    }
+
+    fn page(keys: &[u32], has_more: bool) -> ListMessagesOutput {
+        ListMessagesOutput {
+            items: keys
+                .iter()
+                .map(|k| own_message(&format!("m{}", k), "", "2025-01-01T10:00:00Z", &k.to_string()))
+                .collect(),
+            has_more,
+        }
+    }
+
+    fn ids(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_context_sides() {
+        let anchor = own_message("m10", "", "2025-01-01T10:00:00Z", "10");
+
+        // Older side keeps the nearest messages, even if a page holds more than needed
+        let mut older = ContextSide::new(&anchor, "before", 3);
+        assert_eq!(older.cursor().as_deref(), Some("10"));
+        older.push(page(&[9, 10], true));
+        assert_eq!(older.cursor().as_deref(), Some("9"));
+        older.push(page(&[8, 7, 6], true));
+        assert_eq!(older.cursor(), None);
+
+        // Newer side stops at the end of the chat
+        let mut newer = ContextSide::new(&anchor, "after", 5);
+        newer.push(page(&[11, 12], false));
+        assert_eq!(newer.cursor(), None);
+
+        let ctx = context(anchor.clone(), older, newer);
+        assert_eq!(ids(&ctx.before), vec!["m7", "m8", "m9"]);
+        assert_eq!(ids(&ctx.after), vec!["m11", "m12"]);
+        assert!(ctx.has_more_before);
+        assert!(!ctx.has_more_after);
+        assert_eq!(ctx.messages().count(), 6);
+
+        // Nothing is loaded for an empty side
+        assert_eq!(ContextSide::new(&anchor, "after", 0).cursor(), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_message_page_limit() {
+        let client = BeeperClient::new("token", "http://127.0.0.1:9");
+        let err = client.get_message_with("c1", "m1", 0).await.unwrap_err();
+        assert!(matches!(err, BeeperError::NotFound(m) if m.contains("within 0 pages")));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_replayed_send() {
+        use crate::idempotency::MemoryIdempotencyStore;
+
+        let now = Utc::now();
+        let copy = own_message("m9", "Paging on-call", &now.to_rfc3339(), "9");
+        let page = ListMessagesOutput { items: vec![copy], has_more: false };
+        let base_url = test_support::serve_json(serde_json::to_string(&page).unwrap());
+        let input = SendMessageInput {
+            text: "Paging on-call".to_string(),
+            idempotency_key: Some("page-1".to_string()),
+            ..Default::default()
+        };
+
+        // Recovered from an interrupted attempt: the output already names m9
+        let store = MemoryIdempotencyStore::new();
+        let attempt = IdempotencyRecord::Attempted { at: now - chrono::Duration::minutes(1) };
+        store.put(&scoped_key("c1", "page-1"), attempt).unwrap();
+        let client = BeeperClient::new("token", base_url.as_str()).with_idempotency_store(store);
+        let delivery = client.send_and_confirm("c1", input.clone(), Duration::from_secs(5)).await.unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Delivered);
+        assert_eq!(delivery.message.unwrap().id, "m9");
+
+        // Replayed from a completed record: the copy sorts before the send
+        let store = MemoryIdempotencyStore::new();
+        let output = SendMessageOutput {
+            chat_id: "c1".to_string(),
+            pending_message_id: "p1".to_string(),
+            message_id: None,
+        };
+        store.put(&scoped_key("c1", "page-1"), IdempotencyRecord::Completed { output }).unwrap();
+        let client = BeeperClient::new("token", base_url.as_str()).with_idempotency_store(store);
+        let delivery = client.send_and_confirm("c1", input, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Delivered);
+        assert_eq!(delivery.message.unwrap().id, "m9");
+    }
 }
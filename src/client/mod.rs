@@ -1,16 +1,19 @@
-/// Client module for Beeper Desktop API
-///
-/// Contains the main BeeperClient and method implementations for different API areas.
+//! Client module for Beeper Desktop API
+//!
+//! Contains the main BeeperClient and method implementations for different API areas.
 
 pub mod chats;
 pub mod messages;
 pub mod accounts;
+pub mod contacts;
 pub mod search;
 pub mod app;
-mod utils;
+pub(crate) mod utils;
 
+use std::sync::Arc;
 use reqwest::Client;
 use serde::Deserialize;
+use crate::idempotency::{IdempotencyStore, MemoryIdempotencyStore};
 use self::utils::handle_response;
 
 /// Main Beeper API client
@@ -22,6 +25,7 @@ pub struct BeeperClient {
     token: String,
     base_url: String,
     http_client: Client,
+    idempotency: Arc<dyn IdempotencyStore>,
 }
 
 impl BeeperClient {
@@ -36,6 +40,7 @@ impl BeeperClient {
             token: token.into(),
             base_url: base_url.into(),
             http_client: Client::new(),
+            idempotency: Arc::new(MemoryIdempotencyStore::new()),
         }
     }
 
@@ -63,6 +68,14 @@ impl BeeperClient {
         &self.base_url
     }
 
+    /// Keeps idempotency keys in `store` instead of in memory
+    ///
+    /// See [`crate::idempotency`].
+    pub fn with_idempotency_store(mut self, store: impl IdempotencyStore + 'static) -> Self {
+        self.idempotency = Arc::new(store);
+        self
+    }
+
     pub(crate) fn get_auth_header(&self) -> String {
         format!("Bearer {}", self.token)
     }
@@ -74,9 +87,14 @@ impl BeeperClient {
     pub(crate) fn get_http_client(&self) -> &Client {
         &self.http_client
     }
+
+    pub(crate) fn idempotency_store(&self) -> &dyn IdempotencyStore {
+        self.idempotency.as_ref()
+    }
 }
 
-const DEFAULT_BASE_URL: &str = "http://localhost:23373";
+/// Address Beeper Desktop serves its API on by default
+pub const DEFAULT_BASE_URL: &str = "http://localhost:23373";
 
 #[derive(Debug, Deserialize)]
 pub(crate) struct ApiErrorResponse {
@@ -1,6 +1,7 @@
 //! Search-related API operations
 
-use crate::models::{SearchChatsOutput, SearchMessagesOutput};
+use crate::models::search::Collector;
+use crate::models::{MessageSearchParams, SearchChatsOutput, SearchMessagesOutput};
 use crate::error::Result;
 use super::{BeeperClient, handle_response};
 
@@ -16,13 +17,7 @@ impl BeeperClient {
     ) -> Result<SearchMessagesOutput> {
         let mut url = format!("{}/v1/messages/search?q={}", self.get_base_url(), urlencoding::encode(query));
 
-        if let Some(c) = cursor {
-            url.push_str(&format!("&cursor={}", urlencoding::encode(c)));
-        }
-
-        if let Some(d) = direction {
-            url.push_str(&format!("&direction={}", d));
-        }
+        super::utils::append_pagination(&mut url, cursor, direction);
 
         let response = self
             .get_http_client()
@@ -35,6 +30,40 @@ impl BeeperClient {
         handle_response(response).await
     }
 
+    /// Searches messages with structured filters
+    ///
+    /// Filters are sent to the Desktop API and re-checked on every result; pages are fetched
+    /// `params.page_size` messages at a time until `params.limit` matches are collected or the
+    /// results run out. Pass the returned cursor back with the same `direction` to continue.
+    pub async fn search_messages_with(
+        &self,
+        params: &MessageSearchParams,
+        cursor: Option<&str>,
+        direction: Option<&str>,
+    ) -> Result<SearchMessagesOutput> {
+        let query = params.to_query_string();
+        let mut collector = Collector::new(params, direction);
+        let mut cursor = cursor.map(str::to_string);
+
+        loop {
+            let mut url = format!("{}/v1/messages/search?{}", self.get_base_url(), query);
+            super::utils::append_pagination(&mut url, cursor.as_deref(), direction);
+
+            let response = self
+                .get_http_client()
+                .get(&url)
+                .header("Authorization", self.get_auth_header())
+                .send()
+                .await
+                .map_err(|e| super::utils::map_request_error(e, self.get_base_url()))?;
+
+            match collector.push(handle_response(response).await?) {
+                Some(next) => cursor = Some(next),
+                None => return Ok(collector.finish()),
+            }
+        }
+    }
+
     /// Searches chats by title, network, or participants
     ///
     /// Uses Beeper Desktop's renderer algorithm.
@@ -46,13 +75,7 @@ impl BeeperClient {
     ) -> Result<SearchChatsOutput> {
         let mut url = format!("{}/v1/chats/search?q={}", self.get_base_url(), urlencoding::encode(query));
 
-        if let Some(c) = cursor {
-            url.push_str(&format!("&cursor={}", urlencoding::encode(c)));
-        }
-
-        if let Some(d) = direction {
-            url.push_str(&format!("&direction={}", d));
-        }
+        super::utils::append_pagination(&mut url, cursor, direction);
 
         let response = self
             .get_http_client()
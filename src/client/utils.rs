@@ -5,7 +5,7 @@ use serde::de::DeserializeOwned;
 use crate::error::{BeeperError, Result};
 use super::ApiErrorResponse;
 
-pub(super) fn map_request_error(error: reqwest::Error, base_url: &str) -> BeeperError {
+pub(crate) fn map_request_error(error: reqwest::Error, base_url: &str) -> BeeperError {
     if error.is_connect() {
         BeeperError::ApiNotReachable {
             url: base_url.to_string(),
@@ -15,6 +15,22 @@ pub(super) fn map_request_error(error: reqwest::Error, base_url: &str) -> Beeper
     }
 }
 
+/// Appends `cursor` and `direction` query parameters to a URL that may already have a query string
+pub(crate) fn append_pagination(url: &mut String, cursor: Option<&str>, direction: Option<&str>) {
+    let mut separator = if url.contains('?') { '&' } else { '?' };
+
+    if let Some(c) = cursor {
+        url.push(separator);
+        url.push_str(&format!("cursor={}", urlencoding::encode(c)));
+        separator = '&';
+    }
+
+    if let Some(d) = direction {
+        url.push(separator);
+        url.push_str(&format!("direction={}", d));
+    }
+}
+
 pub(super) async fn handle_response<T: DeserializeOwned>(
     response: reqwest::Response,
 ) -> Result<T> {
@@ -23,37 +39,76 @@ pub(super) async fn handle_response<T: DeserializeOwned>(
             let data = response.json::<T>().await?;
             Ok(data)
         }
-        StatusCode::BAD_REQUEST => {
-            let error = response.json::<ApiErrorResponse>().await?;
-            Err(BeeperError::ApiError {
-                code: error.code,
-                message: error.message,
-            })
-        }
-        StatusCode::UNAUTHORIZED => {
-            Err(BeeperError::Unauthorized)
+        status => {
+            let body = response.text().await.unwrap_or_default();
+            Err(status_error(status, &body))
         }
-        StatusCode::FORBIDDEN => {
-            let error = response.json::<ApiErrorResponse>().await?;
-            Err(BeeperError::ApiError {
+    }
+}
+
+/// Maps an unsuccessful response to an error; shared by the async and blocking clients
+pub(crate) fn status_error(status: StatusCode, body: &str) -> BeeperError {
+    match status {
+        StatusCode::BAD_REQUEST | StatusCode::FORBIDDEN => match serde_json::from_str::<ApiErrorResponse>(body) {
+            Ok(error) => BeeperError::ApiError {
                 code: error.code,
                 message: error.message,
-            })
+            },
+            Err(e) => e.into(),
+        },
+        StatusCode::UNAUTHORIZED => {
+            BeeperError::Unauthorized
         }
         StatusCode::NOT_FOUND => {
-            Err(BeeperError::InvalidConfig("Resource not found".to_string()))
+            BeeperError::InvalidConfig("Resource not found".to_string())
         }
         StatusCode::TOO_MANY_REQUESTS => {
-            Err(BeeperError::InvalidConfig(
+            BeeperError::InvalidConfig(
                 "Rate limit exceeded".to_string(),
-            ))
+            )
         }
         status => {
-            let text = response.text().await.unwrap_or_default();
-            Err(BeeperError::ApiError {
+            BeeperError::ApiError {
                 code: status.to_string(),
-                message: text,
-            })
+                message: body.to_string(),
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_pagination_without_params() {
+        let mut url = "http://localhost/v1/chats".to_string();
+        append_pagination(&mut url, None, None);
+        assert_eq!(url, "http://localhost/v1/chats");
+    }
+
+    #[test]
+    fn test_append_pagination_cursor_and_direction() {
+        let mut url = "http://localhost/v1/chats".to_string();
+        append_pagination(&mut url, Some("a b"), Some("before"));
+        assert_eq!(url, "http://localhost/v1/chats?cursor=a%20b&direction=before");
+    }
+
+    #[test]
+    fn test_append_pagination_existing_query() {
+        let mut url = "http://localhost/v1/messages/search?q=hi".to_string();
+        append_pagination(&mut url, None, Some("after"));
+        assert_eq!(url, "http://localhost/v1/messages/search?q=hi&direction=after");
+    }
+
+    #[test]
+    fn test_status_error() {
+        let error = status_error(StatusCode::FORBIDDEN, r#"{"code":"FORBIDDEN","message":"no access"}"#);
+        assert!(matches!(error, BeeperError::ApiError { code, .. } if code == "FORBIDDEN"));
+        assert!(matches!(status_error(StatusCode::UNAUTHORIZED, ""), BeeperError::Unauthorized));
+        assert!(matches!(status_error(StatusCode::BAD_REQUEST, "oops"), BeeperError::SerializationError(_)));
+        assert!(matches!(status_error(StatusCode::NOT_FOUND, ""), BeeperError::InvalidConfig(_)));
+        let error = status_error(StatusCode::BAD_GATEWAY, "down");
+        assert!(matches!(error, BeeperError::ApiError { message, .. } if message == "down"));
+    }
+}
@@ -3,7 +3,10 @@
 use thiserror::Error;
 
 /// Error type for Beeper API operations
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum BeeperError {
     #[error("Beeper API is not reachable at {url}. Make sure Beeper Desktop is running and the API is enabled")]
     ApiNotReachable { url: String },
@@ -23,8 +26,29 @@ pub enum BeeperError {
     #[error("Missing required field: {0}")]
     MissingField(String),
 
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A task stopped without a result, e.g. because a callback panicked
+    #[error("Internal error: {0}")]
+    Internal(String),
+
+    #[cfg(feature = "sqlite")]
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[cfg(feature = "search-index")]
+    #[error("Search index error: {0}")]
+    Index(#[from] tantivy::TantivyError),
 }
 
 /// Result type for Beeper API operations
@@ -0,0 +1,391 @@
+//! Polling-based event stream
+//!
+//! Beeper Desktop API does not push updates, so [`EventPoller`] periodically lists the most
+//! recently active chats and diffs their latest messages against what it has already seen.
+//! Waiting between rounds goes through a [`Runtime`], so the poller works on any executor.
+//! The API exposes neither typing indicators nor presence, so there are no events for them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::{BeeperClient, events::Event};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = BeeperClient::new("your-token-here", "http://localhost:23373");
+//!     let mut events = client.events();
+//!
+//!     loop {
+//!         if let Event::Message(message) = events.next_event().await? {
+//!             println!("{}: {:?}", message.sender_id, message.text);
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use crate::client::BeeperClient;
+use crate::error::Result;
+use crate::models::{compare_sort_keys, Chat, Message, Reaction};
+use crate::runtime::Runtime;
+
+/// Default delay between polling rounds
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maximum number of older pages fetched per chat to close a gap after a long pause
+const MAX_BACKFILL_PAGES: usize = 5;
+
+/// An update observed by the [`EventPoller`]
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A chat's last activity changed
+    ChatUpdated(Chat),
+    /// A new message arrived in a chat
+    Message(Message),
+    /// A reaction was added to a recent message
+    Reaction {
+        /// Chat the message belongs to
+        chat_id: String,
+        /// Message that received the reaction
+        message_id: String,
+        /// The new reaction
+        reaction: Reaction,
+    },
+}
+
+/// What the poller remembers about a single chat
+#[derive(Debug)]
+struct ChatState {
+    last_activity: Option<String>,
+    last_sort_key: Option<String>,
+    /// When the chat was first observed; messages older than this are not reported
+    seen_at: DateTime<Utc>,
+    /// Reaction IDs of messages in the latest fetched page
+    reactions: Option<HashMap<String, HashSet<String>>>,
+}
+
+impl ChatState {
+    fn new(last_activity: Option<String>, seen_at: DateTime<Utc>) -> Self {
+        Self {
+            last_activity,
+            last_sort_key: None,
+            seen_at,
+            reactions: None,
+        }
+    }
+
+    fn is_new(&self, message: &Message, baseline: Option<&str>) -> bool {
+        match baseline {
+            Some(last) => compare_sort_keys(&message.sort_key, last) == Ordering::Greater,
+            None => DateTime::parse_from_rfc3339(&message.timestamp)
+                .map(|ts| ts.with_timezone(&Utc) > self.seen_at)
+                .unwrap_or(false),
+        }
+    }
+
+    /// Diffs fetched messages against the known state and returns events in chronological order
+    fn ingest(&mut self, chat_id: &str, messages: &[Message]) -> Vec<Event> {
+        let mut sorted: Vec<&Message> = messages.iter().collect();
+        sorted.sort_by(|a, b| compare_sort_keys(&a.sort_key, &b.sort_key));
+
+        let baseline = self.last_sort_key.clone();
+        let mut events = Vec::new();
+        let mut reactions = HashMap::new();
+
+        for message in sorted {
+            let reaction_ids: HashSet<String> = message
+                .reactions
+                .iter()
+                .flatten()
+                .map(|r| r.id.clone())
+                .collect();
+
+            if self.is_new(message, baseline.as_deref()) {
+                events.push(Event::Message(message.clone()));
+            } else if let Some(known) = self.reactions.as_ref().and_then(|r| r.get(&message.id)) {
+                for reaction in message.reactions.iter().flatten() {
+                    if !known.contains(&reaction.id) {
+                        events.push(Event::Reaction {
+                            chat_id: chat_id.to_string(),
+                            message_id: message.id.clone(),
+                            reaction: reaction.clone(),
+                        });
+                    }
+                }
+            }
+
+            let is_newest = self
+                .last_sort_key
+                .as_deref()
+                .is_none_or(|last| compare_sort_keys(&message.sort_key, last) == Ordering::Greater);
+            if is_newest {
+                self.last_sort_key = Some(message.sort_key.clone());
+            }
+            reactions.insert(message.id.clone(), reaction_ids);
+        }
+
+        self.reactions = Some(reactions);
+        events
+    }
+}
+
+/// Polls Beeper Desktop for new messages, reactions, and chat activity
+///
+/// Only chats in the first page of [`BeeperClient::list_chats`] are inspected each round,
+/// which always contains the most recently active chats. The first round records the
+/// current state and produces no events.
+pub struct EventPoller<R: Runtime> {
+    client: BeeperClient,
+    runtime: R,
+    interval: Duration,
+    chat_ids: Option<HashSet<String>>,
+    chats: HashMap<String, ChatState>,
+    pending: VecDeque<Event>,
+    last_round: Option<DateTime<Utc>>,
+}
+
+impl<R: Runtime> EventPoller<R> {
+    /// Creates a poller that waits between rounds using the given runtime
+    pub fn new(client: BeeperClient, runtime: R) -> Self {
+        Self {
+            client,
+            runtime,
+            interval: DEFAULT_POLL_INTERVAL,
+            chat_ids: None,
+            chats: HashMap::new(),
+            pending: VecDeque::new(),
+            last_round: None,
+        }
+    }
+
+    /// Sets the delay between polling rounds
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Restricts the poller to the given chats
+    pub fn with_chat_ids<I, S>(mut self, chat_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.chat_ids = Some(chat_ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Returns the runtime used by this poller
+    pub fn runtime(&self) -> &R {
+        &self.runtime
+    }
+
+    /// Returns the client used by this poller
+    pub fn client(&self) -> &BeeperClient {
+        &self.client
+    }
+
+    /// Returns the delay between polling rounds
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Runs a single polling round and returns the events it produced
+    ///
+    /// Does not wait; callers driving their own loop should sleep between calls.
+    pub async fn poll(&mut self) -> Result<Vec<Event>> {
+        let round_started = Utc::now();
+        let chats = self.client.list_chats(None, None).await?;
+        let mut events = Vec::new();
+
+        for chat in chats.items {
+            if let Some(ids) = &self.chat_ids {
+                if !ids.contains(&chat.id) {
+                    continue;
+                }
+            }
+
+            let Some(state) = self.chats.get_mut(&chat.id) else {
+                // Chats seen in the priming round are baselined at "now"; chats that show up
+                // later report anything newer than the previous round.
+                let seen_at = self.last_round.unwrap_or(round_started);
+                let state = ChatState::new(chat.last_activity.clone(), seen_at);
+                let chat_id = chat.id.clone();
+                self.chats.insert(chat_id.clone(), state);
+                if self.last_round.is_some() {
+                    let messages = self.fetch_new_messages(&chat_id, None).await?;
+                    let state = self.chats.get_mut(&chat_id).expect("state was just inserted");
+                    events.push(Event::ChatUpdated(chat));
+                    events.extend(state.ingest(&chat_id, &messages));
+                }
+                continue;
+            };
+
+            if state.last_activity == chat.last_activity {
+                continue;
+            }
+            state.last_activity = chat.last_activity.clone();
+            let last_sort_key = state.last_sort_key.clone();
+
+            let messages = self.fetch_new_messages(&chat.id, last_sort_key.as_deref()).await?;
+            let chat_id = chat.id.clone();
+            events.push(Event::ChatUpdated(chat));
+            if let Some(state) = self.chats.get_mut(&chat_id) {
+                events.extend(state.ingest(&chat_id, &messages));
+            }
+        }
+
+        self.last_round = Some(round_started);
+        Ok(events)
+    }
+
+    /// Waits for and returns the next event
+    ///
+    /// Polls every [`EventPoller::with_interval`] until at least one event is available.
+    pub async fn next_event(&mut self) -> Result<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+
+            if self.last_round.is_some() {
+                self.runtime.sleep(self.interval).await;
+            }
+
+            let events = self.poll().await?;
+            self.pending.extend(events);
+        }
+    }
+
+    /// Fetches the latest page of a chat, paging back until `last_sort_key` is reached
+    async fn fetch_new_messages(&self, chat_id: &str, last_sort_key: Option<&str>) -> Result<Vec<Message>> {
+        let page = self.client.list_messages(chat_id, None, None).await?;
+        let mut messages = page.items;
+        let mut has_more = page.has_more;
+
+        let Some(last) = last_sort_key else {
+            return Ok(messages);
+        };
+
+        for _ in 0..MAX_BACKFILL_PAGES {
+            let oldest = messages
+                .iter()
+                .map(|m| m.sort_key.as_str())
+                .min_by(|a, b| compare_sort_keys(a, b))
+                .map(str::to_string);
+
+            let Some(oldest) = oldest else { break };
+            if !has_more || compare_sort_keys(&oldest, last) != Ordering::Greater {
+                break;
+            }
+
+            let older = self.client.list_messages(chat_id, Some(&oldest), Some("before")).await?;
+            if older.items.is_empty() {
+                break;
+            }
+            has_more = older.has_more;
+            messages.extend(older.items);
+        }
+
+        Ok(messages)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl BeeperClient {
+    /// Creates an [`EventPoller`] driven by tokio
+    pub fn events(&self) -> EventPoller<crate::runtime::TokioRuntime> {
+        EventPoller::new(self.clone(), crate::runtime::TokioRuntime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, reaction};
+
+    fn message(id: &str, sort_key: &str, timestamp: &str, reactions: &[&str]) -> Message {
+        let reactions: Vec<Reaction> = reactions.iter().map(|r| reaction(r, "👍", "user-2")).collect();
+        Message {
+            reactions: (!reactions.is_empty()).then_some(reactions),
+            ..test_support::message(id, "user-1", sort_key, timestamp, &format!("text {}", id))
+        }
+    }
+
+    fn seen_at(ts: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(ts).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_ingest_uses_timestamp_for_unknown_chat() {
+        let mut state = ChatState::new(None, seen_at("2025-01-01T12:00:00Z"));
+        let events = state.ingest("chat-1", &[
+            message("m1", "100", "2025-01-01T11:00:00Z", &[]),
+            message("m2", "200", "2025-01-01T12:30:00Z", &[]),
+        ]);
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], Event::Message(m) if m.id == "m2"));
+        assert_eq!(state.last_sort_key.as_deref(), Some("200"));
+    }
+
+    #[test]
+    fn test_ingest_ignores_old_messages_interleaved_by_sort_key() {
+        let mut state = ChatState::new(None, seen_at("2025-01-01T12:00:00Z"));
+        let events = state.ingest("chat-1", &[
+            message("m1", "100", "2025-01-01T11:00:00Z", &[]),
+            message("m0", "150", "2025-01-01T11:30:00Z", &[]),
+        ]);
+
+        assert!(events.is_empty());
+        assert_eq!(state.last_sort_key.as_deref(), Some("150"));
+    }
+
+    #[test]
+    fn test_ingest_reports_new_messages_in_order() {
+        let mut state = ChatState::new(None, seen_at("2025-01-01T12:00:00Z"));
+        state.ingest("chat-1", &[message("m1", "100", "2025-01-01T11:00:00Z", &[])]);
+
+        let events = state.ingest("chat-1", &[
+            message("m3", "1000", "2025-01-01T13:00:00Z", &[]),
+            message("m2", "999", "2025-01-01T12:59:00Z", &[]),
+            message("m1", "100", "2025-01-01T11:00:00Z", &[]),
+        ]);
+
+        let ids: Vec<&str> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Message(m) => Some(m.id.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, vec!["m2", "m3"]);
+        assert_eq!(state.last_sort_key.as_deref(), Some("1000"));
+    }
+
+    #[test]
+    fn test_ingest_reports_new_reactions() {
+        let mut state = ChatState::new(None, seen_at("2025-01-01T12:00:00Z"));
+        state.ingest("chat-1", &[message("m1", "100", "2025-01-01T11:00:00Z", &["r1"])]);
+
+        let events = state.ingest("chat-1", &[message("m1", "100", "2025-01-01T11:00:00Z", &["r1", "r2"])]);
+
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            Event::Reaction { message_id, reaction, .. } => {
+                assert_eq!(message_id, "m1");
+                assert_eq!(reaction.id, "r2");
+            }
+            other => panic!("Expected reaction event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ingest_first_fetch_does_not_report_existing_reactions() {
+        let mut state = ChatState::new(None, seen_at("2025-01-01T12:00:00Z"));
+        let events = state.ingest("chat-1", &[message("m1", "100", "2025-01-01T11:00:00Z", &["r1"])]);
+        assert!(events.is_empty());
+    }
+}
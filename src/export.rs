@@ -0,0 +1,534 @@
+//! Full chat history export
+//!
+//! [`Exporter`] walks every chat with [`BeeperClient::list_chats`], pages each chat's
+//! history back to the beginning with [`BeeperClient::list_messages`], and writes it to disk:
+//!
+//! ```text
+//! <output>/
+//!   checkpoint.json
+//!   chats/<chat>/chat.json
+//!   chats/<chat>/messages.ndjson      newest message first, one JSON object per line
+//!   chats/<chat>/attachments.ndjson   src_url -> local path for downloaded attachments
+//!   chats/<chat>/attachments/...
+//! ```
+//!
+//! Progress is saved to `checkpoint.json` after every page, so an interrupted export picks up
+//! where it stopped when run again with the same output directory.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::{BeeperClient, export::Exporter};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = BeeperClient::new("your-token-here", "http://localhost:23373");
+//!     let summary = Exporter::new(client, "./beeper-export")
+//!         .with_attachments(true)
+//!         .run()
+//!         .await?;
+//!     println!("Exported {} messages from {} chats", summary.messages, summary.chats);
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::client::BeeperClient;
+use crate::error::Result;
+use crate::models::{compare_sort_keys, Attachment, Chat, Message};
+
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// Export progress for a single chat
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatProgress {
+    /// Directory name under `chats/`
+    pub dir: String,
+    /// Sort key of the oldest exported message, used as the cursor to resume from
+    pub oldest_sort_key: Option<String>,
+    /// Number of bytes of `messages.ndjson` covered by this checkpoint
+    pub bytes_written: u64,
+    /// Number of bytes of `attachments.ndjson` covered by this checkpoint
+    pub attachment_bytes_written: u64,
+    /// Number of messages exported so far
+    pub message_count: u64,
+    /// True once the beginning of the chat's history was reached
+    pub complete: bool,
+    /// Attachments that could not be downloaded; the rest of the chat is still exported
+    #[serde(default)]
+    pub failed_attachments: Vec<FailedAttachment>,
+}
+
+/// An attachment that could not be downloaded, recorded in the checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedAttachment {
+    /// Message the attachment belongs to
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    /// Original attachment URL
+    #[serde(rename = "srcURL")]
+    pub src_url: String,
+    /// Why the download failed
+    pub error: String,
+}
+
+/// Resumable export state stored in `checkpoint.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Cursor for the next page of chats
+    pub chats_cursor: Option<String>,
+    /// True once every chat has been discovered
+    pub chats_complete: bool,
+    /// Per-chat progress keyed by chat ID
+    pub chats: BTreeMap<String, ChatProgress>,
+}
+
+impl Checkpoint {
+    /// Loads a checkpoint, returning an empty one if the file does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Atomically writes the checkpoint
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Totals for a finished export run
+#[derive(Debug, Clone, Default)]
+pub struct ExportSummary {
+    /// Number of chats in the export
+    pub chats: usize,
+    /// Number of messages written during this run
+    pub messages: u64,
+    /// Number of attachments copied during this run
+    pub attachments: u64,
+    /// Number of attachments that failed to download during this run
+    pub failed_attachments: u64,
+}
+
+/// A downloaded attachment, one per line in `attachments.ndjson`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedAttachment {
+    /// Message the attachment belongs to
+    #[serde(rename = "messageID")]
+    pub message_id: String,
+    /// Original attachment URL
+    #[serde(rename = "srcURL")]
+    pub src_url: String,
+    /// Path relative to the chat directory
+    pub path: String,
+}
+
+/// Exports every chat's full history to NDJSON files
+pub struct Exporter {
+    client: BeeperClient,
+    output_dir: PathBuf,
+    download_attachments: bool,
+    chat_ids: Option<Vec<String>>,
+}
+
+impl Exporter {
+    /// Creates an exporter writing into `output_dir`
+    pub fn new(client: BeeperClient, output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            client,
+            output_dir: output_dir.into(),
+            download_attachments: false,
+            chat_ids: None,
+        }
+    }
+
+    /// Downloads attachments next to each chat's messages
+    pub fn with_attachments(mut self, download: bool) -> Self {
+        self.download_attachments = download;
+        self
+    }
+
+    /// Exports only the given chats instead of walking every chat
+    pub fn with_chat_ids<I, S>(mut self, chat_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.chat_ids = Some(chat_ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    fn checkpoint_path(&self) -> PathBuf {
+        self.output_dir.join(CHECKPOINT_FILE)
+    }
+
+    fn chat_dir(&self, progress: &ChatProgress) -> PathBuf {
+        self.output_dir.join("chats").join(&progress.dir)
+    }
+
+    /// Runs (or resumes) the export
+    pub async fn run(&self) -> Result<ExportSummary> {
+        fs::create_dir_all(self.output_dir.join("chats"))?;
+        let checkpoint_path = self.checkpoint_path();
+        let mut checkpoint = Checkpoint::load(&checkpoint_path)?;
+
+        self.discover_chats(&mut checkpoint, &checkpoint_path).await?;
+
+        let mut summary = ExportSummary {
+            chats: checkpoint.chats.len(),
+            ..Default::default()
+        };
+
+        let chat_ids: Vec<String> = checkpoint.chats.keys().cloned().collect();
+        for chat_id in chat_ids {
+            self.export_chat(&chat_id, &mut checkpoint, &checkpoint_path, &mut summary).await?;
+        }
+
+        Ok(summary)
+    }
+
+    async fn discover_chats(&self, checkpoint: &mut Checkpoint, checkpoint_path: &Path) -> Result<()> {
+        if let Some(ids) = &self.chat_ids {
+            for id in ids {
+                if !checkpoint.chats.contains_key(id) {
+                    let chat = self.client.get_chat(id).await?;
+                    self.register_chat(checkpoint, &chat)?;
+                }
+            }
+            checkpoint.chats_complete = true;
+            return checkpoint.save(checkpoint_path);
+        }
+
+        while !checkpoint.chats_complete {
+            let cursor = checkpoint.chats_cursor.clone();
+            let direction = cursor.as_ref().map(|_| "before");
+            let page = self.client.list_chats(cursor.as_deref(), direction).await?;
+
+            for chat in &page.items {
+                if !checkpoint.chats.contains_key(&chat.id) {
+                    self.register_chat(checkpoint, chat)?;
+                }
+            }
+
+            checkpoint.chats_cursor = page.oldest_cursor.clone();
+            checkpoint.chats_complete = !page.has_more || page.oldest_cursor.is_none() || page.items.is_empty();
+            checkpoint.save(checkpoint_path)?;
+        }
+
+        Ok(())
+    }
+
+    fn register_chat(&self, checkpoint: &mut Checkpoint, chat: &Chat) -> Result<()> {
+        let progress = ChatProgress {
+            dir: unique_dir(checkpoint, &sanitize_file_name(&chat.id)),
+            ..Default::default()
+        };
+        let chat_dir = self.chat_dir(&progress);
+        fs::create_dir_all(&chat_dir)?;
+        fs::write(chat_dir.join("chat.json"), serde_json::to_vec_pretty(chat)?)?;
+        checkpoint.chats.insert(chat.id.clone(), progress);
+        Ok(())
+    }
+
+    async fn export_chat(
+        &self,
+        chat_id: &str,
+        checkpoint: &mut Checkpoint,
+        checkpoint_path: &Path,
+        summary: &mut ExportSummary,
+    ) -> Result<()> {
+        let mut progress = checkpoint.chats[chat_id].clone();
+        if progress.complete {
+            return Ok(());
+        }
+
+        let chat_dir = self.chat_dir(&progress);
+        fs::create_dir_all(&chat_dir)?;
+        // Drop anything written after the last checkpoint so a crash never duplicates lines
+        let mut messages_file = open_truncated(&chat_dir.join("messages.ndjson"), progress.bytes_written)?;
+        let mut attachments_file =
+            open_truncated(&chat_dir.join("attachments.ndjson"), progress.attachment_bytes_written)?;
+
+        loop {
+            let page = match &progress.oldest_sort_key {
+                Some(cursor) => self.client.list_messages(chat_id, Some(cursor), Some("before")).await?,
+                None => self.client.list_messages(chat_id, None, None).await?,
+            };
+
+            let mut messages = page.items;
+            messages.sort_by(|a, b| compare_sort_keys(&b.sort_key, &a.sort_key));
+            if let Some(oldest) = &progress.oldest_sort_key {
+                messages.retain(|m| compare_sort_keys(&m.sort_key, oldest).is_lt());
+            }
+
+            for message in &messages {
+                let mut line = serde_json::to_vec(message)?;
+                line.push(b'\n');
+                messages_file.write_all(&line)?;
+                progress.bytes_written += line.len() as u64;
+                progress.message_count += 1;
+                summary.messages += 1;
+
+                if self.download_attachments {
+                    for attachment in message.attachments.iter().flatten() {
+                        match self.download_attachment(&chat_dir, message, attachment).await {
+                            Ok(Some(exported)) => {
+                                let mut line = serde_json::to_vec(&exported)?;
+                                line.push(b'\n');
+                                attachments_file.write_all(&line)?;
+                                progress.attachment_bytes_written += line.len() as u64;
+                                summary.attachments += 1;
+                            }
+                            Ok(None) => {}
+                            // One broken attachment must not abort a long export
+                            Err(e) => {
+                                progress.failed_attachments.push(FailedAttachment {
+                                    message_id: message.id.clone(),
+                                    src_url: attachment.src_url.clone().unwrap_or_default(),
+                                    error: e.to_string(),
+                                });
+                                summary.failed_attachments += 1;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(oldest) = messages.last() {
+                progress.oldest_sort_key = Some(oldest.sort_key.clone());
+            }
+            progress.complete = !page.has_more || messages.is_empty();
+
+            messages_file.flush()?;
+            attachments_file.flush()?;
+            checkpoint.chats.insert(chat_id.to_string(), progress.clone());
+            checkpoint.save(checkpoint_path)?;
+
+            if progress.complete {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Copies an attachment into the chat's `attachments/` directory
+    ///
+    /// Matrix assets are first downloaded by Beeper Desktop with
+    /// [`BeeperClient::download_asset`]. Attachments that do not resolve to a local file are
+    /// skipped.
+    async fn download_attachment(
+        &self,
+        chat_dir: &Path,
+        message: &Message,
+        attachment: &Attachment,
+    ) -> Result<Option<ExportedAttachment>> {
+        let Some(src_url) = &attachment.src_url else {
+            return Ok(None);
+        };
+
+        let local_url = if src_url.starts_with("file://") {
+            src_url.clone()
+        } else if src_url.starts_with("mxc://") || src_url.starts_with("localmxc://") {
+            self.client.download_asset(src_url).await?.local_url
+        } else {
+            return Ok(None);
+        };
+
+        let Some(source) = file_url_to_path(&local_url) else {
+            return Ok(None);
+        };
+
+        let file_name = attachment
+            .file_name
+            .clone()
+            .or_else(|| source.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| attachment.typ.clone());
+        let relative = Path::new("attachments")
+            .join(format!("{}-{}", sanitize_file_name(&message.id), sanitize_file_name(&file_name)));
+
+        fs::create_dir_all(chat_dir.join("attachments"))?;
+        fs::copy(&source, chat_dir.join(&relative))?;
+
+        Ok(Some(ExportedAttachment {
+            message_id: message.id.clone(),
+            src_url: src_url.clone(),
+            path: relative.to_string_lossy().into_owned(),
+        }))
+    }
+}
+
+/// Reads a chat directory's `attachments.ndjson` as `(src_url, path)` pairs
+///
+/// Paths are joined onto `chat_dir`, ready to pass to
+/// [`crate::transcript::TranscriptOptions::with_asset_paths`].
+pub fn read_attachment_index(chat_dir: &Path) -> Result<Vec<(String, String)>> {
+    let contents = match fs::read_to_string(chat_dir.join("attachments.ndjson")) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let entry: ExportedAttachment = serde_json::from_str(line)?;
+            let path = chat_dir.join(&entry.path).to_string_lossy().into_owned();
+            Ok((entry.src_url, path))
+        })
+        .collect()
+}
+
+/// `base`, or `base-N` with the smallest N that no other chat uses
+fn unique_dir(checkpoint: &Checkpoint, base: &str) -> String {
+    let taken = |dir: &str| checkpoint.chats.values().any(|p| p.dir == dir);
+    let mut dir = base.to_string();
+    let mut n = 1;
+    while taken(&dir) {
+        dir = format!("{}-{}", base, n);
+        n += 1;
+    }
+    dir
+}
+
+fn open_truncated(path: &Path, len: u64) -> Result<File> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.set_len(len)?;
+    Ok(file)
+}
+
+/// Replaces characters that are unsafe in file names
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    let trimmed = sanitized.trim_start_matches('.');
+    if trimmed.is_empty() {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Converts a `file://` URL returned by Beeper Desktop into a filesystem path
+pub(crate) fn file_url_to_path(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("file://")?;
+    let decoded = urlencoding::decode(path).ok()?;
+    Some(PathBuf::from(decoded.into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("beeper-export-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("!abc:beeper.com"), "_abc_beeper.com");
+        assert_eq!(sanitize_file_name("../etc/passwd"), "_etc_passwd");
+        assert_eq!(sanitize_file_name(""), "_");
+    }
+
+    #[test]
+    fn test_file_url_to_path() {
+        assert_eq!(
+            file_url_to_path("file:///home/user/My%20File.png"),
+            Some(PathBuf::from("/home/user/My File.png"))
+        );
+        assert_eq!(file_url_to_path("mxc://example.com/abc"), None);
+    }
+
+    #[test]
+    fn test_unique_dir() {
+        let mut checkpoint = Checkpoint::default();
+        for (id, dir) in [("a", "chat"), ("b", "chat-2"), ("c", "chat-1")] {
+            checkpoint.chats.insert(
+                id.to_string(),
+                ChatProgress {
+                    dir: dir.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        assert_eq!(unique_dir(&checkpoint, "chat"), "chat-3");
+        assert_eq!(unique_dir(&checkpoint, "other"), "other");
+    }
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let dir = temp_dir("checkpoint");
+        let path = dir.join(CHECKPOINT_FILE);
+
+        assert!(Checkpoint::load(&path).unwrap().chats.is_empty());
+
+        let mut checkpoint = Checkpoint {
+            chats_cursor: Some("cursor-1".to_string()),
+            ..Default::default()
+        };
+        checkpoint.chats.insert(
+            "chat-1".to_string(),
+            ChatProgress {
+                dir: "chat-1".to_string(),
+                oldest_sort_key: Some("100".to_string()),
+                bytes_written: 42,
+                message_count: 3,
+                ..Default::default()
+            },
+        );
+        checkpoint.save(&path).unwrap();
+
+        let loaded = Checkpoint::load(&path).unwrap();
+        assert_eq!(loaded.chats_cursor.as_deref(), Some("cursor-1"));
+        assert_eq!(loaded.chats["chat-1"].oldest_sort_key.as_deref(), Some("100"));
+        assert_eq!(loaded.chats["chat-1"].bytes_written, 42);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_attachment_index() {
+        let dir = temp_dir("attachments");
+        assert!(read_attachment_index(&dir).unwrap().is_empty());
+
+        let entry = ExportedAttachment {
+            message_id: "m1".to_string(),
+            src_url: "mxc://example.com/a".to_string(),
+            path: "attachments/m1-a.png".to_string(),
+        };
+        fs::write(dir.join("attachments.ndjson"), format!("{}\n", serde_json::to_string(&entry).unwrap())).unwrap();
+
+        let index = read_attachment_index(&dir).unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(index[0].0, "mxc://example.com/a");
+        assert!(index[0].1.ends_with("attachments/m1-a.png"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_truncated_discards_unsaved_lines() {
+        let dir = temp_dir("truncate");
+        let path = dir.join("messages.ndjson");
+        fs::write(&path, "line-1\nline-2\npartial").unwrap();
+
+        let mut file = open_truncated(&path, 7).unwrap();
+        file.write_all(b"line-3\n").unwrap();
+        drop(file);
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line-1\nline-3\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+//! Client-side deduplication of message sends
+//!
+//! The Desktop API has no idempotency keys of its own. When
+//! [`SendMessageInput::idempotency_key`](crate::models::SendMessageInput::idempotency_key)
+//! is set, the client records the send in an [`IdempotencyStore`] and a later call with the
+//! same key and chat returns the original [`SendMessageOutput`] instead of sending again.
+//!
+//! If an earlier attempt ended without an answer, e.g. a timeout, the client looks for the
+//! message among the newest messages in the chat before sending it again; a message found
+//! that way is returned with [`SendMessageOutput::message_id`] set, since its pending ID was
+//! never received. Sends that failed in a way that proves nothing was sent (Desktop
+//! unreachable, rejected by the API with a 4xx status) are forgotten, so retrying them sends
+//! normally. Any retry loop can therefore simply repeat the call with the same key.
+//!
+//! Clients keep keys in a [`MemoryIdempotencyStore`] by default; use
+//! [`FileIdempotencyStore`] to remember them across restarts. Both forget keys after
+//! [`DEFAULT_KEY_TTL`] unless configured with `with_ttl`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::{BeeperClient, SendMessageInput};
+//! use beeper_desktop_api::idempotency::FileIdempotencyStore;
+//!
+//! # async fn run() -> beeper_desktop_api::Result<()> {
+//! let client = BeeperClient::new("your-token-here", "http://localhost:23373")
+//!     .with_idempotency_store(FileIdempotencyStore::open("sent-keys.json")?);
+//!
+//! let input = SendMessageInput {
+//!     text: "Database is down".to_string(),
+//!     idempotency_key: Some("incident-4711-page".to_string()),
+//!     ..Default::default()
+//! };
+//! // Safe to retry: a second call returns the first call's output
+//! let output = client.send_message("oncall-chat", input).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::error::{BeeperError, Result};
+use crate::models::SendMessageOutput;
+
+/// How long stores remember a key unless configured with `with_ttl`
+pub const DEFAULT_KEY_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// What is known about a keyed send
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum IdempotencyRecord {
+    /// A send started at this time and did not report back
+    Attempted {
+        /// When the send started
+        at: DateTime<Utc>,
+    },
+    /// The send succeeded
+    Completed {
+        /// What `send_message` returned
+        output: SendMessageOutput,
+    },
+}
+
+/// Where idempotency records are kept
+///
+/// Keys passed to the store already include the chat ID.
+pub trait IdempotencyStore: Send + Sync {
+    /// Returns the record for a key, if any
+    fn get(&self, key: &str) -> Result<Option<IdempotencyRecord>>;
+
+    /// Stores or replaces the record for a key
+    fn put(&self, key: &str, record: IdempotencyRecord) -> Result<()>;
+
+    /// Stores `record` unless the key already has one, returning the existing record
+    ///
+    /// Must be atomic: of two concurrent first sends with the same key, only one may get
+    /// `None` back and go on to send.
+    fn insert_if_absent(&self, key: &str, record: IdempotencyRecord) -> Result<Option<IdempotencyRecord>>;
+
+    /// Forgets a key
+    fn remove(&self, key: &str) -> Result<()>;
+}
+
+/// A record and when it was stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredRecord {
+    stored_at: DateTime<Utc>,
+    record: IdempotencyRecord,
+}
+
+/// Records that expire `ttl` after they were stored
+#[derive(Debug)]
+struct Records {
+    entries: HashMap<String, StoredRecord>,
+    ttl: chrono::Duration,
+}
+
+impl Records {
+    fn new(entries: HashMap<String, StoredRecord>) -> Self {
+        let mut records = Self { entries, ttl: chrono::Duration::zero() };
+        records.set_ttl(DEFAULT_KEY_TTL);
+        records
+    }
+
+    fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        self.prune();
+    }
+
+    fn is_live(&self, stored: &StoredRecord) -> bool {
+        Utc::now().signed_duration_since(stored.stored_at) < self.ttl
+    }
+
+    /// Drops expired records
+    fn prune(&mut self) {
+        let ttl = self.ttl;
+        self.entries.retain(|_, stored| Utc::now().signed_duration_since(stored.stored_at) < ttl);
+    }
+
+    fn get(&self, key: &str) -> Option<IdempotencyRecord> {
+        self.entries.get(key).filter(|s| self.is_live(s)).map(|s| s.record.clone())
+    }
+
+    fn put(&mut self, key: &str, record: IdempotencyRecord) {
+        self.prune();
+        self.entries.insert(key.to_string(), StoredRecord { stored_at: Utc::now(), record });
+    }
+
+    fn insert_if_absent(&mut self, key: &str, record: IdempotencyRecord) -> Option<IdempotencyRecord> {
+        self.prune();
+        match self.entries.entry(key.to_string()) {
+            Entry::Occupied(entry) => Some(entry.get().record.clone()),
+            Entry::Vacant(entry) => {
+                entry.insert(StoredRecord { stored_at: Utc::now(), record });
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> bool {
+        self.entries.remove(key).is_some()
+    }
+}
+
+/// Keeps records for the lifetime of the process, or until they expire
+#[derive(Debug)]
+pub struct MemoryIdempotencyStore {
+    records: Mutex<Records>,
+}
+
+impl Default for MemoryIdempotencyStore {
+    fn default() -> Self {
+        Self {
+            records: Mutex::new(Records::new(HashMap::new())),
+        }
+    }
+}
+
+impl MemoryIdempotencyStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets keys `ttl` after they were last stored instead of after [`DEFAULT_KEY_TTL`]
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        self.records.lock().expect("idempotency lock poisoned").set_ttl(ttl);
+        self
+    }
+}
+
+impl IdempotencyStore for MemoryIdempotencyStore {
+    fn get(&self, key: &str) -> Result<Option<IdempotencyRecord>> {
+        Ok(self.records.lock().expect("idempotency lock poisoned").get(key))
+    }
+
+    fn put(&self, key: &str, record: IdempotencyRecord) -> Result<()> {
+        self.records.lock().expect("idempotency lock poisoned").put(key, record);
+        Ok(())
+    }
+
+    fn insert_if_absent(&self, key: &str, record: IdempotencyRecord) -> Result<Option<IdempotencyRecord>> {
+        Ok(self.records.lock().expect("idempotency lock poisoned").insert_if_absent(key, record))
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        self.records.lock().expect("idempotency lock poisoned").remove(key);
+        Ok(())
+    }
+}
+
+/// Keeps records in a JSON file, rewritten on every change
+///
+/// Expired records are dropped when the file is opened and on every write.
+#[derive(Debug)]
+pub struct FileIdempotencyStore {
+    path: PathBuf,
+    records: Mutex<Records>,
+}
+
+impl FileIdempotencyStore {
+    /// Opens the store at `path`, starting empty if the file does not exist
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            records: Mutex::new(Records::new(entries)),
+        })
+    }
+
+    /// Forgets keys `ttl` after they were last stored instead of after [`DEFAULT_KEY_TTL`]
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        self.records.lock().expect("idempotency lock poisoned").set_ttl(ttl);
+        self
+    }
+
+    fn save(&self, records: &Records) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&records.entries)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+impl IdempotencyStore for FileIdempotencyStore {
+    fn get(&self, key: &str) -> Result<Option<IdempotencyRecord>> {
+        Ok(self.records.lock().expect("idempotency lock poisoned").get(key))
+    }
+
+    fn put(&self, key: &str, record: IdempotencyRecord) -> Result<()> {
+        let mut records = self.records.lock().expect("idempotency lock poisoned");
+        records.put(key, record);
+        self.save(&records)
+    }
+
+    fn insert_if_absent(&self, key: &str, record: IdempotencyRecord) -> Result<Option<IdempotencyRecord>> {
+        let mut records = self.records.lock().expect("idempotency lock poisoned");
+        let existing = records.insert_if_absent(key, record);
+        if existing.is_none() {
+            self.save(&records)?;
+        }
+        Ok(existing)
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        let mut records = self.records.lock().expect("idempotency lock poisoned");
+        if records.remove(key) {
+            self.save(&records)?;
+        }
+        Ok(())
+    }
+}
+
+/// Store key for an idempotency key in a chat
+pub(crate) fn scoped_key(chat_id: &str, key: &str) -> String {
+    format!("{}\n{}", chat_id, key)
+}
+
+/// True if the error proves the message was not sent
+///
+/// Timeouts, unreadable responses, and 5xx errors do not: the server may have failed after
+/// handing the message on.
+pub(crate) fn never_sent(error: &BeeperError) -> bool {
+    match error {
+        BeeperError::RequestError(_) | BeeperError::SerializationError(_) => false,
+        BeeperError::ApiError { code, .. } => !code.starts_with('5'),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(id: &str) -> IdempotencyRecord {
+        IdempotencyRecord::Completed {
+            output: SendMessageOutput {
+                chat_id: "c1".to_string(),
+                pending_message_id: id.to_string(),
+                message_id: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_file_store_persists() {
+        let path = std::env::temp_dir().join(format!("beeper-idempotency-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let store = FileIdempotencyStore::open(&path).unwrap();
+        store.put(&scoped_key("c1", "a"), output("p1")).unwrap();
+        store.put(&scoped_key("c1", "b"), output("p2")).unwrap();
+        store.remove(&scoped_key("c1", "b")).unwrap();
+
+        let reopened = FileIdempotencyStore::open(&path).unwrap();
+        assert!(matches!(
+            reopened.get(&scoped_key("c1", "a")).unwrap(),
+            Some(IdempotencyRecord::Completed { output }) if output.pending_message_id == "p1"
+        ));
+        assert!(reopened.get(&scoped_key("c1", "b")).unwrap().is_none());
+        assert!(reopened.get(&scoped_key("c2", "a")).unwrap().is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_client_returns_recorded_output() {
+        use crate::models::SendMessageInput;
+        use crate::BeeperClient;
+
+        let store = MemoryIdempotencyStore::new();
+        store.put(&scoped_key("c1", "page-1"), output("p1")).unwrap();
+        let client = BeeperClient::new("token", "http://127.0.0.1:9").with_idempotency_store(store);
+        let input = |key: &str| SendMessageInput {
+            text: "Database is down".to_string(),
+            idempotency_key: Some(key.to_string()),
+            ..Default::default()
+        };
+
+        // Answered from the store, without reaching the (absent) server
+        let sent = client.send_message("c1", input("page-1")).await.unwrap();
+        assert_eq!(sent.pending_message_id, "p1");
+
+        // Unreachable means nothing was sent, so the key is not kept
+        let err = client.send_message("c1", input("page-2")).await.unwrap_err();
+        assert!(matches!(err, BeeperError::ApiNotReachable { .. }));
+        assert!(client.idempotency_store().get(&scoped_key("c1", "page-2")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_insert_if_absent_and_expiry() {
+        let store = MemoryIdempotencyStore::new();
+        let attempt = || IdempotencyRecord::Attempted { at: Utc::now() };
+        assert!(store.insert_if_absent("k", attempt()).unwrap().is_none());
+        assert!(matches!(store.insert_if_absent("k", output("p1")).unwrap(), Some(IdempotencyRecord::Attempted { .. })));
+
+        let expired = MemoryIdempotencyStore::new().with_ttl(Duration::ZERO);
+        expired.put("k", output("p1")).unwrap();
+        assert!(expired.get("k").unwrap().is_none());
+        assert!(expired.insert_if_absent("k", attempt()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_never_sent() {
+        assert!(never_sent(&BeeperError::ApiNotReachable { url: "x".to_string() }));
+        assert!(never_sent(&BeeperError::Unauthorized));
+        let rejected = crate::client::utils::status_error(reqwest::StatusCode::FORBIDDEN, r#"{"code":"FORBIDDEN","message":"no"}"#);
+        assert!(never_sent(&rejected));
+        let failed = crate::client::utils::status_error(reqwest::StatusCode::INTERNAL_SERVER_ERROR, "oops");
+        assert!(!never_sent(&failed));
+        assert!(!never_sent(&BeeperError::SerializationError(serde_json::from_str::<u8>("x").unwrap_err())));
+    }
+}
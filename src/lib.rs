@@ -4,6 +4,13 @@
 //! It handles authentication via bearer tokens and provides methods for managing accounts, chats,
 //! messages, and more.
 //!
+//! A synchronous client mirroring every method is available in the `blocking` module when the
+//! `blocking` feature is enabled.
+//!
+//! The core client works on any async executor. Polling utilities such as [`events`] wait
+//! through the [`runtime::Runtime`] trait; a tokio implementation is included with the
+//! default `tokio` feature.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -21,7 +28,25 @@
 pub mod client;
 pub mod models;
 pub mod error;
+pub mod runtime;
+pub mod events;
+pub mod idempotency;
+pub mod bot;
+pub mod broadcast;
+pub mod relay;
+pub mod scheduler;
+#[cfg(feature = "rules")]
+pub mod rules;
+pub mod export;
+pub mod transcript;
+pub mod template;
+pub mod thread;
+pub mod store;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(test)]
+pub(crate) mod test_support;
 
-pub use client::BeeperClient;
+pub use client::{BeeperClient, DEFAULT_BASE_URL};
 pub use error::{BeeperError, Result};
 pub use models::*;
@@ -84,6 +84,11 @@ pub struct Chat {
     /// True if chat is pinned
     #[serde(rename = "isPinned")]
     pub is_pinned: bool,
+    /// True if chat is in the low-priority inbox
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "isLowPriority")]
+    pub is_low_priority: Option<bool>,
     /// Last message preview for this chat, if available
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preview: Option<Box<Message>>,
@@ -97,7 +102,7 @@ impl Chat {
     pub fn display_name(&self) -> String {
         if self.chat_type == "single" {
             // For direct messages, try to add the other person's name
-            if let Some(first_participant) = self.participants.items.iter().filter(|p| !p.is_self.unwrap_or(false)).next() {
+            if let Some(first_participant) = self.participants.items.iter().find(|p| !p.is_self.unwrap_or(false)) {
                 if let Some(full_name) = &first_participant.full_name {
                     return full_name.clone();
                 }
@@ -134,6 +139,17 @@ pub struct CreateChatOutput {
     pub chat_id: String,
 }
 
+/// A direct message chat found or created by [`crate::BeeperClient::resolve_dm`]
+#[derive(Debug, Clone)]
+pub struct ResolvedDm {
+    /// The other participant
+    pub user: User,
+    /// The single chat with them
+    pub chat: Chat,
+    /// True if the chat was created by this call
+    pub created: bool,
+}
+
 /// Output from listing chats
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListChatsOutput {
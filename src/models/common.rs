@@ -1,5 +1,6 @@
 //! Common types and utilities
 
+use std::cmp::Ordering;
 use serde::{Deserialize, Serialize};
 
 pub type ChatID = String;
@@ -13,3 +14,14 @@ pub enum Direction {
     Before,
     After,
 }
+
+/// Compares two message sort keys
+///
+/// Sort keys are numeric strings, so they are compared by value when both parse as
+/// integers and lexicographically otherwise.
+pub fn compare_sort_keys(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
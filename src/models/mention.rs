@@ -0,0 +1,301 @@
+//! Mentions of chat participants
+//!
+//! Mentions are sent as Markdown links to the user (see [`super::rich_text`]), so networks
+//! that support mentions can turn them into real ones. Received messages are checked both
+//! for such links and for plain `@name` text, which is how most networks show a mention.
+
+use std::ops::Range;
+use super::chat::Chat;
+use super::message::{Message, SendMessageInput};
+use super::rich_text::{RichNode, RichText};
+use super::user::User;
+use crate::error::{BeeperError, Result};
+
+/// A part of [`SendMessageInput::text`] that mentions a chat participant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MentionSpan {
+    /// [`User::id`] of the mentioned participant
+    pub user_id: String,
+    /// Byte range of the mention in the text, e.g. of `@Alice`
+    pub range: Range<usize>,
+}
+
+/// A mention found in a received message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mention {
+    /// ID of the mentioned user, if the text links to them
+    pub user_id: Option<String>,
+    /// Name as written, without the leading `@`
+    pub name: String,
+}
+
+/// Name used when mentioning `user`
+fn mention_name(user: &User) -> &str {
+    user.full_name
+        .as_deref()
+        .or(user.username.as_deref())
+        .unwrap_or(&user.id)
+        .trim_start_matches('@')
+}
+
+/// True if `c` can be part of a plain `@name`
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
+
+/// True if `text` contains `@name`, ignoring case, not followed by another name character
+pub(crate) fn mentions_name(text: &str, name: &str) -> bool {
+    let name = name.trim_start_matches('@');
+    if name.is_empty() {
+        return false;
+    }
+    let text = text.to_lowercase();
+    let needle = format!("@{}", name.to_lowercase());
+    text.match_indices(&needle).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Plain `@name` mentions in text
+fn plain_mentions(text: &str, found: &mut Vec<Mention>) {
+    for (start, _) in text.match_indices('@') {
+        if text[..start].chars().next_back().is_some_and(char::is_alphanumeric) {
+            continue;
+        }
+        let rest = &text[start + 1..];
+        let end = rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len());
+        let name = rest[..end].trim_end_matches(['.', '-']);
+        if !name.is_empty() {
+            found.push(Mention {
+                user_id: None,
+                name: name.to_string(),
+            });
+        }
+    }
+}
+
+fn collect_mentions(nodes: &[RichNode], found: &mut Vec<Mention>) {
+    for node in nodes {
+        match node {
+            RichNode::Text(text) => plain_mentions(text, found),
+            RichNode::Mention { user_id, name } => found.push(Mention {
+                user_id: Some(user_id.clone()),
+                name: name.clone(),
+            }),
+            RichNode::Bold(children)
+            | RichNode::Italic(children)
+            | RichNode::Strikethrough(children)
+            | RichNode::Quote(children)
+            | RichNode::Link { children, .. } => collect_mentions(children, found),
+            RichNode::Code(_) | RichNode::CodeBlock { .. } => {}
+        }
+    }
+}
+
+impl Message {
+    /// Mentions in the message text, in order
+    ///
+    /// Mention links carry the user's ID; plain `@name` text only the name. Text in code is
+    /// ignored.
+    pub fn mentions(&self) -> Vec<Mention> {
+        let mut found = Vec::new();
+        if let Some(text) = self.rich_text() {
+            collect_mentions(&text.nodes, &mut found);
+        }
+        found
+    }
+
+    /// True if the message mentions `me`, by ID or as `@` followed by the user's full name
+    /// or username
+    pub fn mentions_me(&self, me: &User) -> bool {
+        if self.mentions().iter().any(|m| m.user_id.as_deref() == Some(me.id.as_str())) {
+            return true;
+        }
+        let text = self.text.as_deref().unwrap_or_default();
+        [me.full_name.as_deref(), me.username.as_deref()]
+            .into_iter()
+            .flatten()
+            .any(|name| mentions_name(text, name))
+    }
+}
+
+impl SendMessageInput {
+    /// Marks `range` of the text as a mention of `user_id`
+    pub fn with_mention(mut self, user_id: impl Into<String>, range: Range<usize>) -> Self {
+        self.mentions.push(MentionSpan {
+            user_id: user_id.into(),
+            range,
+        });
+        self
+    }
+
+    /// Appends `@name` for `user` to the text and marks it as a mention
+    pub fn mention(mut self, user: &User) -> Self {
+        let start = self.text.len();
+        self.text.push('@');
+        self.text.push_str(mention_name(user));
+        let end = self.text.len();
+        self.with_mention(user.id.clone(), start..end)
+    }
+
+    /// Marks every `@name` in the text that names a participant of `chat` as a mention
+    ///
+    /// Names are matched against each participant's full name and username, ignoring case;
+    /// the longest match wins, so `@Alice Smith` is preferred over `@Alice`.
+    pub fn mention_participants(mut self, chat: &Chat) -> Self {
+        let mut names: Vec<(&str, &str)> = chat
+            .participants
+            .items
+            .iter()
+            .flat_map(|u| {
+                [u.full_name.as_deref(), u.username.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .map(move |name| (name.trim_start_matches('@'), u.id.as_str()))
+            })
+            .filter(|(name, _)| !name.is_empty())
+            .collect();
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+
+        let mut spans = Vec::new();
+        for (start, _) in self.text.match_indices('@') {
+            if self.mentions.iter().chain(&spans).any(|s: &MentionSpan| s.range.contains(&start)) {
+                continue;
+            }
+            let found = names.iter().find(|(name, _)| {
+                let end = start + 1 + name.len();
+                self.text.get(start + 1..end).is_some_and(|s| s.to_lowercase() == name.to_lowercase())
+                    && !self.text[end..].chars().next().is_some_and(|c| c.is_alphanumeric() || c == '_')
+            });
+            if let Some((name, user_id)) = found {
+                spans.push(MentionSpan {
+                    user_id: user_id.to_string(),
+                    range: start..start + 1 + name.len(),
+                });
+            }
+        }
+        self.mentions.extend(spans);
+        self
+    }
+
+    /// Writes the mention spans into the text as mention links
+    ///
+    /// Fails with [`BeeperError::InvalidInput`] if a span lies outside the text, splits a
+    /// character, or overlaps another span.
+    pub(crate) fn resolve_mentions(mut self) -> Result<Self> {
+        if self.mentions.is_empty() {
+            return Ok(self);
+        }
+        let mut spans = std::mem::take(&mut self.mentions);
+        spans.sort_by_key(|s| s.range.start);
+
+        let mut text = String::with_capacity(self.text.len());
+        let mut last = 0;
+        for span in &spans {
+            let Range { start, end } = span.range;
+            let name = self
+                .text
+                .get(start..end)
+                .filter(|_| start >= last && start < end)
+                .ok_or_else(|| {
+                    BeeperError::InvalidInput(format!("mention of {} at {}..{} does not fit the text", span.user_id, start, end))
+                })?;
+            text.push_str(&self.text[last..start]);
+            let link = RichText::new().mention(span.user_id.clone(), name.trim_start_matches('@'));
+            text.push_str(&link.to_markdown());
+            last = end;
+        }
+        text.push_str(&self.text[last..]);
+        self.text = text;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn message(text: &str) -> Message {
+        test_support::message("m1", "u1", "1", "2025-01-01T10:00:00Z", text)
+    }
+
+    #[test]
+    fn test_resolve_mentions() {
+        let alice = test_support::user("@alice:beeper.com", "Alice Smith", None);
+        let input = SendMessageInput {
+            text: "cc ".to_string(),
+            ..Default::default()
+        }
+        .mention(&alice);
+        assert_eq!(input.text, "cc @Alice Smith");
+
+        let resolved = input.resolve_mentions().unwrap();
+        assert_eq!(resolved.text, "cc [Alice Smith](https://matrix.to/#/@alice:beeper.com)");
+        assert!(resolved.mentions.is_empty());
+
+        let overlapping = SendMessageInput {
+            text: "@Alice".to_string(),
+            ..Default::default()
+        }
+        .with_mention("a", 0..6)
+        .with_mention("b", 3..6);
+        assert!(matches!(overlapping.resolve_mentions(), Err(BeeperError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_mention_participants() {
+        let chat: Chat = serde_json::from_value(serde_json::json!({
+            "id": "c1",
+            "accountID": "slack",
+            "network": "Slack",
+            "title": "dev",
+            "type": "group",
+            "participants": {"items": [
+                {"id": "u-alice", "fullName": "Alice"},
+                {"id": "u-alice-smith", "fullName": "Alice Smith", "username": "asmith"}
+            ], "hasMore": false, "total": 2},
+            "unreadCount": 0,
+            "isArchived": false,
+            "isMuted": false,
+            "isPinned": false
+        }))
+        .unwrap();
+        let input = SendMessageInput {
+            text: "@alice smith and @Alice, not @Alicia or @asmith2".to_string(),
+            ..Default::default()
+        }
+        .mention_participants(&chat);
+        assert_eq!(
+            input.mentions,
+            vec![
+                MentionSpan { user_id: "u-alice-smith".to_string(), range: 0..12 },
+                MentionSpan { user_id: "u-alice".to_string(), range: 17..23 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_received_mentions() {
+        let msg = message("hey [Dev Bot](https://matrix.to/#/@bot:beeper.com) and @carol. mail a@b.com `@code`");
+        assert_eq!(
+            msg.mentions(),
+            vec![
+                Mention { user_id: Some("@bot:beeper.com".to_string()), name: "Dev Bot".to_string() },
+                Mention { user_id: None, name: "carol".to_string() },
+            ]
+        );
+
+        let bot = User {
+            username: Some("devbot".to_string()),
+            ..test_support::user("@bot:beeper.com", "Dev Bot", None)
+        };
+        assert!(msg.mentions_me(&bot));
+        assert!(message("ping @DevBot? no, @devbot!").mentions_me(&bot));
+        assert!(message("@Dev Bot please").mentions_me(&bot));
+        assert!(!message("@devbots unite").mentions_me(&bot));
+        assert!(!message("mail devbot@example.com").mentions_me(&bot));
+    }
+}
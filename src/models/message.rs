@@ -1,6 +1,7 @@
 //! Message, attachment, and reaction models
 
 use serde::{Deserialize, Serialize};
+use super::mention::MentionSpan;
 
 /// File attachment or media
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,8 +117,91 @@ pub struct Message {
     pub is_sender: Option<bool>,
 }
 
+/// Kind of media carried by a message, used by search filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    /// Images, GIFs, and stickers
+    Image,
+    /// Videos
+    Video,
+    /// Voice notes and audio
+    Voice,
+    /// Any other file
+    File,
+    /// A URL in the message text
+    Link,
+}
+
+impl MediaType {
+    /// Classifies an attachment
+    pub fn of(attachment: &Attachment) -> Self {
+        let mime = attachment.mime_type.as_deref().unwrap_or("");
+        if attachment.is_voice_note.unwrap_or(false) || attachment.typ == "audio" || mime.starts_with("audio/") {
+            MediaType::Voice
+        } else if attachment.is_gif.unwrap_or(false)
+            || attachment.is_sticker.unwrap_or(false)
+            || matches!(attachment.typ.as_str(), "img" | "image")
+            || mime.starts_with("image/")
+        {
+            MediaType::Image
+        } else if attachment.typ == "video" || mime.starts_with("video/") {
+            MediaType::Video
+        } else {
+            MediaType::File
+        }
+    }
+
+    /// Lowercase name, matching the serialized form
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Image => "image",
+            MediaType::Video => "video",
+            MediaType::Voice => "voice",
+            MediaType::File => "file",
+            MediaType::Link => "link",
+        }
+    }
+}
+
+impl std::str::FromStr for MediaType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "image" => Ok(MediaType::Image),
+            "video" => Ok(MediaType::Video),
+            "voice" => Ok(MediaType::Voice),
+            "file" => Ok(MediaType::File),
+            "link" => Ok(MediaType::Link),
+            other => Err(format!("unknown media type '{}', expected image, video, voice, file, or link", other)),
+        }
+    }
+}
+
+impl Message {
+    /// Media types present in this message's attachments and text, without duplicates
+    pub fn media_types(&self) -> Vec<MediaType> {
+        let mut types: Vec<MediaType> = Vec::new();
+        for attachment in self.attachments.iter().flatten() {
+            let media = MediaType::of(attachment);
+            if !types.contains(&media) {
+                types.push(media);
+            }
+        }
+        let has_link = self
+            .text
+            .as_deref()
+            .is_some_and(|t| t.contains("http://") || t.contains("https://"));
+        if has_link {
+            types.push(MediaType::Link);
+        }
+        types
+    }
+}
+
 /// Input for sending a message
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SendMessageInput {
     /// Message text
     pub text: String,
@@ -125,6 +209,17 @@ pub struct SendMessageInput {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "replyToID")]
     pub reply_to_id: Option<String>,
+    /// Client-side deduplication key; not sent to the API
+    ///
+    /// Sends to the same chat with the same key return the first send's output. See
+    /// [`crate::idempotency`].
+    #[serde(skip)]
+    pub idempotency_key: Option<String>,
+    /// Parts of the text that mention chat participants; sent as mention links
+    ///
+    /// See [`SendMessageInput::mention_participants`].
+    #[serde(skip)]
+    pub mentions: Vec<MentionSpan>,
 }
 
 /// Output from sending a message
@@ -134,8 +229,46 @@ pub struct SendMessageOutput {
     #[serde(rename = "chatID")]
     pub chat_id: String,
     /// Pending message ID
+    ///
+    /// Empty if an idempotent retry found an earlier send in the chat, since its pending
+    /// ID was never received; [`SendMessageOutput::message_id`] is set instead.
     #[serde(rename = "pendingMessageID")]
     pub pending_message_id: String,
+    /// ID of the message as found in the chat, set only by idempotent retries
+    #[serde(rename = "messageID", default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+}
+
+/// How far a sent message has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Accepted by Beeper Desktop but not yet visible in the chat
+    Pending,
+    /// Visible in the chat under its pending ID, waiting for the network
+    Sent,
+    /// Visible in the chat under the final ID assigned by the network
+    Delivered,
+    /// Rejected by the API
+    Failed,
+}
+
+/// Result of [`crate::BeeperClient::send_and_confirm_with`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    /// Final status when confirmation stopped
+    pub status: DeliveryStatus,
+    /// Chat the message was sent to
+    #[serde(rename = "chatID")]
+    pub chat_id: String,
+    /// Pending ID returned by `send_message`, if it was accepted
+    #[serde(rename = "pendingMessageID")]
+    pub pending_message_id: Option<String>,
+    /// The message as it appears in the chat, once seen
+    pub message: Option<Message>,
+    /// Why the API rejected the message
+    pub error: Option<String>,
 }
 
 /// Output from listing messages
@@ -148,6 +281,29 @@ pub struct ListMessagesOutput {
     pub has_more: bool,
 }
 
+/// A message with the messages around it, see [`crate::BeeperClient::get_message_context`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageContext {
+    /// Older messages, oldest first
+    pub before: Vec<Message>,
+    /// The message itself
+    pub message: Message,
+    /// Newer messages, oldest first
+    pub after: Vec<Message>,
+    /// Whether the chat has older messages than `before`
+    pub has_more_before: bool,
+    /// Whether the chat has newer messages than `after`
+    pub has_more_after: bool,
+}
+
+impl MessageContext {
+    /// All messages in chat order, the message itself included
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.before.iter().chain(std::iter::once(&self.message)).chain(&self.after)
+    }
+}
+
 /// Output from searching messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchMessagesOutput {
@@ -5,17 +5,25 @@ pub mod user;
 pub mod message;
 pub mod chat;
 pub mod app;
+pub mod search;
+pub mod rich_text;
+pub mod mention;
 
 // Re-export commonly used types
-pub use common::{ChatID, AccountID, Cursor, Direction};
-pub use user::{User, Account, GetAccountsOutput};
+pub use common::{ChatID, AccountID, Cursor, Direction, compare_sort_keys};
+pub use user::{User, Account, GetAccountsOutput, SearchContactsOutput};
 pub use message::{
-    Attachment, Reaction, Message, SendMessageInput, SendMessageOutput,
-    ListMessagesOutput, SearchMessagesOutput,
+    Attachment, Reaction, Message, MediaType, SendMessageInput, SendMessageOutput, Delivery, DeliveryStatus,
+    ListMessagesOutput, MessageContext, SearchMessagesOutput,
 };
 pub use chat::{
-    Chat, Participants, CreateChatInput, CreateChatOutput, ListChatsOutput, SearchChatsOutput,
+    Chat, Participants, CreateChatInput, ResolvedDm, CreateChatOutput, ListChatsOutput, SearchChatsOutput,
 };
+pub use search::{
+    ChatType, Inbox, ListChatsParams, MessageSearchParams, SenderFilter, DEFAULT_CHAT_LIMIT, DEFAULT_SEARCH_LIMIT,
+};
+pub use rich_text::{RichText, RichNode, escape_markdown};
+pub use mention::{Mention, MentionSpan};
 pub use app::{
     FocusAppInput, FocusAppOutput, DownloadAssetInput, DownloadAssetOutput,
 };
@@ -32,8 +40,40 @@ mod tests {
         
         // Just verify they're different
         match (before, after) {
-            (Direction::Before, Direction::After) => assert!(true),
+            (Direction::Before, Direction::After) => {}
             _ => panic!("Direction enum values don't match expected"),
         }
     }
+
+    #[test]
+    fn test_compare_sort_keys_numeric() {
+        use std::cmp::Ordering;
+        assert_eq!(compare_sort_keys("9", "10"), Ordering::Less);
+        assert_eq!(compare_sort_keys("453400065536", "453400065536"), Ordering::Equal);
+        assert_eq!(compare_sort_keys("b", "a"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_message_media_types() {
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "id": "m1",
+            "chatID": "c1",
+            "senderID": "u1",
+            "text": "see https://example.com",
+            "timestamp": "2025-01-01T10:00:00Z",
+            "sortKey": "1",
+            "attachments": [
+                {"type": "img", "mimeType": "image/png"},
+                {"type": "unknown", "isGif": true},
+                {"type": "audio", "isVoiceNote": true},
+                {"type": "unknown", "mimeType": "application/pdf"}
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            message.media_types(),
+            vec![MediaType::Image, MediaType::Voice, MediaType::File, MediaType::Link]
+        );
+    }
 }
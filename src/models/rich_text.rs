@@ -0,0 +1,656 @@
+//! Formatted message text
+//!
+//! The Desktop API sends and returns message text as a string; formatting travels inside it
+//! as Markdown. [`RichText`] is the parsed form: build one to send formatted text, or parse
+//! [`Message::text`](super::Message::text) to read the formatting of a received message.
+//! Mentions are Matrix permalinks, `[Alice](https://matrix.to/#/@alice:beeper.com)`.
+
+use super::message::{Message, SendMessageInput};
+
+/// Prefix of the links that mention a user
+pub const MENTION_URL_PREFIX: &str = "https://matrix.to/#/";
+
+/// Characters with a meaning in message Markdown
+const MARKDOWN_CHARS: &[char] = &['\\', '*', '_', '~', '`', '[', ']', '(', ')', '>'];
+
+/// Inline markers and the style they toggle, longest first
+const MARKERS: &[(&str, Style)] = &[
+    ("**", Style::Bold),
+    ("__", Style::Bold),
+    ("~~", Style::Strikethrough),
+    ("*", Style::Italic),
+    ("_", Style::Italic),
+];
+
+/// A piece of formatted text
+#[derive(Debug, Clone, PartialEq)]
+pub enum RichNode {
+    /// Unformatted text; may contain line breaks
+    Text(String),
+    /// Bold text
+    Bold(Vec<RichNode>),
+    /// Italic text
+    Italic(Vec<RichNode>),
+    /// Struck-through text
+    Strikethrough(Vec<RichNode>),
+    /// Inline code
+    Code(String),
+    /// Multi-line code
+    CodeBlock {
+        /// Language name given after the opening fence, e.g. `rust`
+        language: Option<String>,
+        /// The code, without the fences
+        code: String,
+    },
+    /// A link
+    Link {
+        /// Link target
+        url: String,
+        /// Link text
+        children: Vec<RichNode>,
+    },
+    /// A mention of a chat participant
+    Mention {
+        /// [`User::id`](super::User::id) of the mentioned user
+        user_id: String,
+        /// Name shown for the mention, without a leading `@`
+        name: String,
+    },
+    /// Quoted text
+    Quote(Vec<RichNode>),
+}
+
+/// Formatted message text
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RichText {
+    /// The text, in order
+    pub nodes: Vec<RichNode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Bold,
+    Italic,
+    Strikethrough,
+}
+
+/// How a Markdown-like dialect writes each style
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MarkdownSyntax {
+    pub bold: &'static str,
+    pub italic: &'static str,
+    pub strikethrough: &'static str,
+    /// Writes links as `[text](url)` rather than `text (url)`
+    pub links: bool,
+    /// Writes mentions as links rather than `@name`
+    pub mention_links: bool,
+    /// Escapes text so it cannot be read as formatting
+    pub escape: bool,
+}
+
+impl MarkdownSyntax {
+    /// Markdown as accepted by the Desktop API
+    pub(crate) const MARKDOWN: MarkdownSyntax = MarkdownSyntax {
+        bold: "**",
+        italic: "_",
+        strikethrough: "~~",
+        links: true,
+        mention_links: true,
+        escape: true,
+    };
+}
+
+/// Escapes `text` so it appears literally in Markdown
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if MARKDOWN_CHARS.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn escape_code(code: &str) -> String {
+    code.replace('\\', "\\\\").replace('`', "\\`")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next().or(Some('\\'))),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn push_text(nodes: &mut Vec<RichNode>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match nodes.last_mut() {
+        Some(RichNode::Text(last)) => last.push_str(text),
+        _ => nodes.push(RichNode::Text(text.to_string())),
+    }
+}
+
+fn push_node(nodes: &mut Vec<RichNode>, node: RichNode) {
+    match node {
+        RichNode::Text(text) => push_text(nodes, &text),
+        node => nodes.push(node),
+    }
+}
+
+/// Finds the unescaped `end` at or after `start`, returning the raw text before it and the
+/// index after it
+fn find_closing(chars: &[char], start: usize, end: &[char]) -> Option<(String, usize)> {
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+            i += 2;
+        } else if chars[i..].starts_with(end) {
+            return Some((chars[start..i].iter().collect(), i + end.len()));
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Open styles while parsing, innermost last; the root has no marker
+type Frame = (Option<(&'static str, Style)>, Vec<RichNode>);
+
+fn top(stack: &mut [Frame]) -> &mut Vec<RichNode> {
+    &mut stack.last_mut().expect("root frame").1
+}
+
+fn styled(style: Style, children: Vec<RichNode>) -> RichNode {
+    match style {
+        Style::Bold => RichNode::Bold(children),
+        Style::Italic => RichNode::Italic(children),
+        Style::Strikethrough => RichNode::Strikethrough(children),
+    }
+}
+
+fn link(text: &str, url: String) -> RichNode {
+    let mentioned = url
+        .strip_prefix(MENTION_URL_PREFIX)
+        .filter(|id| id.starts_with('@'))
+        .map(|id| id.replace("%3A", ":").replace("%3a", ":"));
+    match mentioned {
+        Some(user_id) => RichNode::Mention {
+            user_id,
+            name: unescape(text).trim_start_matches('@').to_string(),
+        },
+        None => RichNode::Link {
+            url,
+            children: parse_inline(text),
+        },
+    }
+}
+
+fn parse_inline(text: &str) -> Vec<RichNode> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut stack: Vec<Frame> = vec![(None, Vec::new())];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let rest = &chars[i..];
+
+        if rest[0] == '\\' && rest.len() > 1 {
+            push_text(top(&mut stack), &rest[1].to_string());
+            i += 2;
+            continue;
+        }
+        if rest.starts_with(&['`', '`', '`']) {
+            if let Some((raw, next)) = find_closing(&chars, i + 3, &['`', '`', '`']) {
+                let raw = unescape(&raw);
+                let (language, code) = match raw.split_once('\n') {
+                    Some((first, code)) if !first.contains(char::is_whitespace) => {
+                        (Some(first.to_string()).filter(|l| !l.is_empty()), code)
+                    }
+                    _ => (None, raw.as_str()),
+                };
+                let code = code.strip_suffix('\n').unwrap_or(code).to_string();
+                top(&mut stack).push(RichNode::CodeBlock { language, code });
+                i = next;
+                continue;
+            }
+        } else if rest[0] == '`' {
+            if let Some((raw, next)) = find_closing(&chars, i + 1, &['`']) {
+                top(&mut stack).push(RichNode::Code(unescape(&raw)));
+                i = next;
+                continue;
+            }
+        } else if rest[0] == '[' {
+            if let Some((text, after_text)) = find_closing(&chars, i + 1, &[']']) {
+                if chars.get(after_text) == Some(&'(') {
+                    if let Some((url, next)) = find_closing(&chars, after_text + 1, &[')']) {
+                        top(&mut stack).push(link(&text, unescape(&url)));
+                        i = next;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        let marker = MARKERS
+            .iter()
+            .copied()
+            .find(|(m, _)| rest.iter().take(m.len()).copied().eq(m.chars()));
+        let Some((marker, style)) = marker else {
+            push_text(top(&mut stack), &rest[0].to_string());
+            i += 1;
+            continue;
+        };
+
+        let len = marker.len();
+        let before = i.checked_sub(1).map(|j| chars[j]);
+        let after = chars.get(i + len).copied();
+        // Underscores only count at word boundaries, so snake_case stays intact
+        let underscore = marker.starts_with('_');
+        let open_marker = stack.last().and_then(|(m, _)| m.map(|(m, _)| m));
+        let can_close = open_marker == Some(marker)
+            && before.is_some_and(|c| !c.is_whitespace())
+            && !(underscore && after.is_some_and(char::is_alphanumeric));
+        let can_open = after.is_some_and(|c| !c.is_whitespace())
+            && !(underscore && before.is_some_and(char::is_alphanumeric))
+            && !stack.iter().any(|(m, _)| m.is_some_and(|(_, s)| s == style));
+
+        if can_close {
+            let (_, children) = stack.pop().expect("styled frame");
+            top(&mut stack).push(styled(style, children));
+        } else if can_open {
+            stack.push((Some((marker, style)), Vec::new()));
+        } else {
+            push_text(top(&mut stack), marker);
+        }
+        i += len;
+    }
+
+    // Unclosed markers become text again
+    while stack.len() > 1 {
+        let (marker, children) = stack.pop().expect("styled frame");
+        let parent = top(&mut stack);
+        push_text(parent, marker.expect("only the root has no marker").0);
+        for child in children {
+            push_node(parent, child);
+        }
+    }
+    stack.pop().expect("root frame").1
+}
+
+/// True if the line starts a quote
+fn quoted_line(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix('>')?;
+    Some(rest.strip_prefix(' ').unwrap_or(rest))
+}
+
+impl RichText {
+    /// Creates empty text
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses Markdown: `**bold**`, `_italic_` or `*italic*`, `~~strike~~`, `` `code` ``,
+    /// fenced code blocks, `[text](url)`, `> quotes` and mention links
+    ///
+    /// A backslash makes the next character literal. Markers that are never closed are kept
+    /// as text, so plain text parses to a single [`RichNode::Text`].
+    pub fn parse_markdown(markdown: &str) -> Self {
+        let mut nodes = Vec::new();
+        let mut plain = String::new();
+        let mut quote: Vec<&str> = Vec::new();
+        let mut in_fence = false;
+
+        let flush_quote = |nodes: &mut Vec<RichNode>, quote: &mut Vec<&str>, plain: &mut String| {
+            if quote.is_empty() {
+                return;
+            }
+            let text = quote.concat();
+            let (text, newline) = match text.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (text.as_str(), ""),
+            };
+            nodes.push(RichNode::Quote(parse_inline(text)));
+            plain.push_str(newline);
+            quote.clear();
+        };
+
+        for line in markdown.split_inclusive('\n') {
+            match quoted_line(line).filter(|_| !in_fence) {
+                Some(content) => {
+                    for node in parse_inline(&std::mem::take(&mut plain)) {
+                        push_node(&mut nodes, node);
+                    }
+                    quote.push(content);
+                }
+                None => {
+                    flush_quote(&mut nodes, &mut quote, &mut plain);
+                    plain.push_str(line);
+                }
+            }
+            if line.matches("```").count() % 2 == 1 {
+                in_fence = !in_fence;
+            }
+        }
+        flush_quote(&mut nodes, &mut quote, &mut plain);
+        for node in parse_inline(&plain) {
+            push_node(&mut nodes, node);
+        }
+        Self { nodes }
+    }
+
+    /// Appends a node
+    pub fn push(mut self, node: RichNode) -> Self {
+        push_node(&mut self.nodes, node);
+        self
+    }
+
+    /// Appends unformatted text
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.push(RichNode::Text(text.into()))
+    }
+
+    /// Appends bold text
+    pub fn bold(self, text: impl Into<String>) -> Self {
+        self.push(RichNode::Bold(vec![RichNode::Text(text.into())]))
+    }
+
+    /// Appends italic text
+    pub fn italic(self, text: impl Into<String>) -> Self {
+        self.push(RichNode::Italic(vec![RichNode::Text(text.into())]))
+    }
+
+    /// Appends inline code
+    pub fn code(self, code: impl Into<String>) -> Self {
+        self.push(RichNode::Code(code.into()))
+    }
+
+    /// Appends a code block, optionally naming its language
+    pub fn code_block(self, language: Option<&str>, code: impl Into<String>) -> Self {
+        self.push(RichNode::CodeBlock {
+            language: language.map(str::to_string),
+            code: code.into(),
+        })
+    }
+
+    /// Appends a link
+    pub fn link(self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.push(RichNode::Link {
+            url: url.into(),
+            children: vec![RichNode::Text(text.into())],
+        })
+    }
+
+    /// Appends a mention of `user_id`, shown as `name`
+    pub fn mention(self, user_id: impl Into<String>, name: impl Into<String>) -> Self {
+        self.push(RichNode::Mention {
+            user_id: user_id.into(),
+            name: name.into(),
+        })
+    }
+
+    /// Appends quoted text
+    pub fn quote(self, text: impl Into<String>) -> Self {
+        self.push(RichNode::Quote(vec![RichNode::Text(text.into())]))
+    }
+
+    /// Writes the text as Markdown, the form [`SendMessageInput::text`] takes
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        write_markdown(&mut out, &self.nodes, &MarkdownSyntax::MARKDOWN);
+        out
+    }
+
+    /// Writes the text without formatting; links become `text (url)` and mentions `@name`
+    pub fn to_plain_text(&self) -> String {
+        let mut out = String::new();
+        write_plain(&mut out, &self.nodes);
+        out
+    }
+
+    /// Writes the text as HTML; mentions become links to the user
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        write_html(&mut out, &self.nodes);
+        out
+    }
+}
+
+/// Prefixes every line of `text` with `> `
+fn quote_lines(text: &str) -> String {
+    text.split('\n').map(|line| format!("> {}", line)).collect::<Vec<_>>().join("\n")
+}
+
+/// Plain text of a link, used when its URL is written next to it
+fn plain_of(nodes: &[RichNode]) -> String {
+    let mut out = String::new();
+    write_plain(&mut out, nodes);
+    out
+}
+
+pub(crate) fn write_markdown(out: &mut String, nodes: &[RichNode], syntax: &MarkdownSyntax) {
+    for node in nodes {
+        match node {
+            RichNode::Text(text) if syntax.escape => out.push_str(&escape_markdown(text)),
+            RichNode::Text(text) => out.push_str(text),
+            RichNode::Bold(children) | RichNode::Italic(children) | RichNode::Strikethrough(children) => {
+                let marker = match node {
+                    RichNode::Bold(_) => syntax.bold,
+                    RichNode::Italic(_) => syntax.italic,
+                    _ => syntax.strikethrough,
+                };
+                out.push_str(marker);
+                write_markdown(out, children, syntax);
+                out.push_str(marker);
+            }
+            RichNode::Code(code) if syntax.escape => out.push_str(&format!("`{}`", escape_code(code))),
+            RichNode::Code(code) => out.push_str(&format!("`{}`", code)),
+            RichNode::CodeBlock { language, code } => {
+                let code = if syntax.escape { escape_code(code) } else { code.clone() };
+                out.push_str(&format!("```{}\n{}\n```", language.as_deref().unwrap_or_default(), code));
+            }
+            RichNode::Link { url, children } if syntax.links => {
+                out.push('[');
+                write_markdown(out, children, syntax);
+                out.push_str(&format!("]({})", url.replace(')', "\\)")));
+            }
+            RichNode::Link { url, children } => {
+                let text = plain_of(children);
+                if text.is_empty() || text == *url {
+                    out.push_str(url);
+                } else {
+                    out.push_str(&format!("{} ({})", text, url));
+                }
+            }
+            RichNode::Mention { user_id, name } if syntax.mention_links => {
+                let name = if syntax.escape { escape_markdown(name) } else { name.clone() };
+                out.push_str(&format!("[{}]({}{})", name, MENTION_URL_PREFIX, user_id));
+            }
+            RichNode::Mention { name, .. } => out.push_str(&format!("@{}", name)),
+            RichNode::Quote(children) => {
+                let mut inner = String::new();
+                write_markdown(&mut inner, children, syntax);
+                out.push_str(&quote_lines(&inner));
+            }
+        }
+    }
+}
+
+fn write_plain(out: &mut String, nodes: &[RichNode]) {
+    for node in nodes {
+        match node {
+            RichNode::Text(text) | RichNode::Code(text) | RichNode::CodeBlock { code: text, .. } => out.push_str(text),
+            RichNode::Bold(children) | RichNode::Italic(children) | RichNode::Strikethrough(children) => {
+                write_plain(out, children)
+            }
+            RichNode::Link { url, children } => {
+                let text = plain_of(children);
+                if text.is_empty() || text == *url {
+                    out.push_str(url);
+                } else {
+                    out.push_str(&format!("{} ({})", text, url));
+                }
+            }
+            RichNode::Mention { name, .. } => out.push_str(&format!("@{}", name)),
+            RichNode::Quote(children) => out.push_str(&quote_lines(&plain_of(children))),
+        }
+    }
+}
+
+fn write_html(out: &mut String, nodes: &[RichNode]) {
+    for node in nodes {
+        match node {
+            RichNode::Text(text) => out.push_str(&escape_html(text).replace('\n', "<br>")),
+            RichNode::Bold(children) | RichNode::Italic(children) | RichNode::Strikethrough(children) => {
+                let tag = match node {
+                    RichNode::Bold(_) => "strong",
+                    RichNode::Italic(_) => "em",
+                    _ => "del",
+                };
+                out.push_str(&format!("<{}>", tag));
+                write_html(out, children);
+                out.push_str(&format!("</{}>", tag));
+            }
+            RichNode::Code(code) => out.push_str(&format!("<code>{}</code>", escape_html(code))),
+            RichNode::CodeBlock { language: Some(language), code } => out.push_str(&format!(
+                "<pre><code class=\"language-{}\">{}</code></pre>",
+                escape_html(language),
+                escape_html(code)
+            )),
+            RichNode::CodeBlock { language: None, code } => {
+                out.push_str(&format!("<pre><code>{}</code></pre>", escape_html(code)))
+            }
+            RichNode::Link { url, children } => {
+                out.push_str(&format!("<a href=\"{}\">", escape_html(url)));
+                write_html(out, children);
+                out.push_str("</a>");
+            }
+            RichNode::Mention { user_id, name } => out.push_str(&format!(
+                "<a href=\"{}{}\">{}</a>",
+                MENTION_URL_PREFIX,
+                escape_html(user_id),
+                escape_html(name)
+            )),
+            RichNode::Quote(children) => {
+                out.push_str("<blockquote>");
+                write_html(out, children);
+                out.push_str("</blockquote>");
+            }
+        }
+    }
+}
+
+impl Message {
+    /// Parses the message text, see [`RichText::parse_markdown`]
+    pub fn rich_text(&self) -> Option<RichText> {
+        self.text.as_deref().map(RichText::parse_markdown)
+    }
+}
+
+impl SendMessageInput {
+    /// Creates input that sends `text` with its formatting
+    pub fn from_rich_text(text: &RichText) -> Self {
+        Self {
+            text: text.to_markdown(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> RichNode {
+        RichNode::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let parsed = RichText::parse_markdown(
+            "**Deploy** of _api_ failed, see [logs](https://ci.example.com/1) \
+             [@Alice](https://matrix.to/#/@alice:beeper.com)\n> retry?\n```sh\nmake deploy\n```",
+        );
+        assert_eq!(
+            parsed.nodes,
+            vec![
+                RichNode::Bold(vec![text("Deploy")]),
+                text(" of "),
+                RichNode::Italic(vec![text("api")]),
+                text(" failed, see "),
+                RichNode::Link {
+                    url: "https://ci.example.com/1".to_string(),
+                    children: vec![text("logs")],
+                },
+                text(" "),
+                RichNode::Mention {
+                    user_id: "@alice:beeper.com".to_string(),
+                    name: "Alice".to_string(),
+                },
+                text("\n"),
+                RichNode::Quote(vec![text("retry?")]),
+                text("\n"),
+                RichNode::CodeBlock {
+                    language: Some("sh".to_string()),
+                    code: "make deploy".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plain_text_is_unchanged() {
+        for plain in ["snake_case_name", "5 * 3 = 15", "**unclosed", "a > b", "price: $5 (approx)"] {
+            let parsed = RichText::parse_markdown(plain);
+            assert_eq!(parsed.nodes, vec![text(plain)], "{}", plain);
+        }
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let rich = RichText::new()
+            .bold("Build #42")
+            .text(" failed in `main.rs`: ")
+            .code("let x = a * b_c;")
+            .text("\n")
+            .quote("first\nsecond")
+            .text("\ncc ")
+            .mention("@bob:beeper.com", "Bob")
+            .text(" ")
+            .link("diff", "https://git.example.com/c/1")
+            .text("\n")
+            .code_block(Some("rust"), "fn main() {}");
+        assert_eq!(RichText::parse_markdown(&rich.to_markdown()), rich);
+    }
+
+    #[test]
+    fn test_html_and_plain() {
+        let rich = RichText::parse_markdown("**a** <b> [@Bob](https://matrix.to/#/@bob:beeper.com)\n> q");
+        assert_eq!(
+            rich.to_html(),
+            "<strong>a</strong> &lt;b&gt; <a href=\"https://matrix.to/#/@bob:beeper.com\">Bob</a><br>\
+             <blockquote>q</blockquote>"
+        );
+        assert_eq!(rich.to_plain_text(), "a <b> @Bob\n> q");
+    }
+}
@@ -0,0 +1,752 @@
+//! Structured search and listing filters
+
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use super::chat::Chat;
+use super::chat::ListChatsOutput;
+use super::message::{MediaType, Message, SearchMessagesOutput};
+
+/// Number of matches collected when [`MessageSearchParams::limit`] is not set
+pub const DEFAULT_SEARCH_LIMIT: u32 = 20;
+
+/// Media types the Desktop API can filter on server-side
+const SERVER_MEDIA_TYPES: &[MediaType] = &[MediaType::Image, MediaType::Video, MediaType::File, MediaType::Link];
+
+/// Whose messages to include in a search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SenderFilter {
+    /// Only messages sent by the current user
+    Me,
+    /// Only messages sent by other participants
+    Others,
+    /// Only messages sent by this user ID
+    #[serde(untagged)]
+    User(String),
+}
+
+impl std::str::FromStr for SenderFilter {
+    type Err = std::convert::Infallible;
+
+    /// Parses `me`, `others`, or a user ID
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "me" => SenderFilter::Me,
+            "others" => SenderFilter::Others,
+            id => SenderFilter::User(id.to_string()),
+        })
+    }
+}
+
+impl SenderFilter {
+    fn as_param(&self) -> &str {
+        match self {
+            SenderFilter::Me => "me",
+            SenderFilter::Others => "others",
+            SenderFilter::User(id) => id,
+        }
+    }
+}
+
+/// Filters for [`crate::BeeperClient::search_messages_with`]
+///
+/// Every filter is sent to the Desktop API and checked again on the returned messages, so
+/// filters the server cannot apply (such as [`MediaType::Voice`]) still hold.
+#[derive(Debug, Clone, Default)]
+pub struct MessageSearchParams {
+    /// Free-text query
+    pub query: Option<String>,
+    /// Only messages in these chats
+    pub chat_ids: Vec<String>,
+    /// Only messages from these accounts
+    pub account_ids: Vec<String>,
+    /// Only messages from this sender
+    pub sender: Option<SenderFilter>,
+    /// Only messages at or after this time
+    pub date_after: Option<DateTime<Utc>>,
+    /// Only messages before this time
+    pub date_before: Option<DateTime<Utc>>,
+    /// Only messages carrying any of these media types
+    pub media_types: Vec<MediaType>,
+    /// Whether to include messages from muted chats
+    pub include_muted: Option<bool>,
+    /// Whether to skip messages from low-priority chats
+    pub exclude_low_priority: Option<bool>,
+    /// Number of matching messages to collect before returning, [`DEFAULT_SEARCH_LIMIT`] if unset
+    pub limit: Option<u32>,
+    /// Number of messages the Desktop API returns per page, the server default if unset
+    ///
+    /// Independent of `limit`: with filters the server cannot apply, larger pages reach the
+    /// `limit` matches in fewer requests.
+    pub page_size: Option<u32>,
+}
+
+impl MessageSearchParams {
+    /// Creates empty search parameters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the free-text query
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.query = Some(query.into());
+        self
+    }
+
+    /// Restricts results to these chats
+    pub fn chat_ids<I: IntoIterator<Item = S>, S: Into<String>>(mut self, chat_ids: I) -> Self {
+        self.chat_ids = chat_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Restricts results to these accounts
+    pub fn account_ids<I: IntoIterator<Item = S>, S: Into<String>>(mut self, account_ids: I) -> Self {
+        self.account_ids = account_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Restricts results to one sender
+    pub fn sender(mut self, sender: SenderFilter) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Restricts results to messages at or after `time`
+    pub fn date_after(mut self, time: DateTime<Utc>) -> Self {
+        self.date_after = Some(time);
+        self
+    }
+
+    /// Restricts results to messages before `time`
+    pub fn date_before(mut self, time: DateTime<Utc>) -> Self {
+        self.date_before = Some(time);
+        self
+    }
+
+    /// Restricts results to messages carrying any of these media types
+    pub fn media_types<I: IntoIterator<Item = MediaType>>(mut self, media_types: I) -> Self {
+        self.media_types = media_types.into_iter().collect();
+        self
+    }
+
+    /// Includes or excludes messages from muted chats
+    pub fn include_muted(mut self, include: bool) -> Self {
+        self.include_muted = Some(include);
+        self
+    }
+
+    /// Skips messages from low-priority chats
+    pub fn exclude_low_priority(mut self, exclude: bool) -> Self {
+        self.exclude_low_priority = Some(exclude);
+        self
+    }
+
+    /// Sets the number of matching messages to collect
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the number of messages requested per page
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Builds the query string for `/v1/messages/search`, without a leading `?`
+    pub(crate) fn to_query_string(&self) -> String {
+        let mut pairs: Vec<(&str, String)> = Vec::new();
+        pairs.push(("q", self.query.clone().unwrap_or_default()));
+        for id in &self.chat_ids {
+            pairs.push(("chatIDs", id.clone()));
+        }
+        for id in &self.account_ids {
+            pairs.push(("accountIDs", id.clone()));
+        }
+        if let Some(sender) = &self.sender {
+            pairs.push(("sender", sender.as_param().to_string()));
+        }
+        if let Some(after) = self.date_after {
+            pairs.push(("dateAfter", after.to_rfc3339()));
+        }
+        if let Some(before) = self.date_before {
+            pairs.push(("dateBefore", before.to_rfc3339()));
+        }
+        // Narrowing server-side to a subset would drop matches of the unsupported types
+        if self.media_types.iter().all(|m| SERVER_MEDIA_TYPES.contains(m)) {
+            for media in &self.media_types {
+                pairs.push(("mediaTypes", media.as_str().to_string()));
+            }
+        }
+        if let Some(include) = self.include_muted {
+            pairs.push(("includeMuted", include.to_string()));
+        }
+        if let Some(exclude) = self.exclude_low_priority {
+            pairs.push(("excludeLowPriority", exclude.to_string()));
+        }
+        if let Some(page_size) = self.page_size {
+            pairs.push(("limit", page_size.to_string()));
+        }
+
+        pairs
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// True if the message satisfies every filter
+    ///
+    /// `chats` is the chat map returned alongside search results; chat-level filters are
+    /// skipped for chats missing from it.
+    pub fn matches(&self, message: &Message, chats: Option<&HashMap<String, Chat>>) -> bool {
+        if !self.chat_ids.is_empty() && !self.chat_ids.contains(&message.chat_id) {
+            return false;
+        }
+
+        let chat = chats.and_then(|c| c.get(&message.chat_id));
+        if !self.account_ids.is_empty() {
+            let account = message.account_id.as_deref().or(chat.map(|c| c.account_id.as_str()));
+            if account.is_some_and(|a| !self.account_ids.iter().any(|id| id == a)) {
+                return false;
+            }
+        }
+
+        let sender_ok = match &self.sender {
+            None => true,
+            Some(SenderFilter::Me) => message.is_sender == Some(true),
+            Some(SenderFilter::Others) => message.is_sender != Some(true),
+            Some(SenderFilter::User(id)) => &message.sender_id == id,
+        };
+        if !sender_ok {
+            return false;
+        }
+
+        if self.date_after.is_some() || self.date_before.is_some() {
+            if let Ok(time) = DateTime::parse_from_rfc3339(&message.timestamp) {
+                let time = time.with_timezone(&Utc);
+                if self.date_after.is_some_and(|after| time < after) || self.date_before.is_some_and(|before| time >= before) {
+                    return false;
+                }
+            }
+        }
+
+        if !self.media_types.is_empty() {
+            let types = message.media_types();
+            if !self.media_types.iter().any(|m| types.contains(m)) {
+                return false;
+            }
+        }
+
+        if let Some(chat) = chat {
+            if self.include_muted == Some(false) && chat.is_muted {
+                return false;
+            }
+            if self.exclude_low_priority == Some(true) && chat.is_low_priority == Some(true) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Number of chats collected when [`ListChatsParams::limit`] is not set
+pub const DEFAULT_CHAT_LIMIT: u32 = 25;
+
+/// Which inbox a chat lives in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Inbox {
+    /// Unarchived chats that are not low priority
+    Primary,
+    /// Unarchived low-priority chats
+    LowPriority,
+    /// Archived chats
+    Archive,
+}
+
+impl Inbox {
+    /// Inbox the chat currently belongs to
+    pub fn of(chat: &Chat) -> Self {
+        if chat.is_archived {
+            Inbox::Archive
+        } else if chat.is_low_priority == Some(true) {
+            Inbox::LowPriority
+        } else {
+            Inbox::Primary
+        }
+    }
+}
+
+impl std::str::FromStr for Inbox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "primary" => Ok(Inbox::Primary),
+            "low-priority" => Ok(Inbox::LowPriority),
+            "archive" => Ok(Inbox::Archive),
+            other => Err(format!("unknown inbox '{}', expected primary, low-priority, or archive", other)),
+        }
+    }
+}
+
+/// Kind of chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatType {
+    /// Direct message with one person
+    Single,
+    /// Group chat
+    Group,
+}
+
+impl ChatType {
+    /// Value of [`Chat::chat_type`] for this kind
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatType::Single => "single",
+            ChatType::Group => "group",
+        }
+    }
+}
+
+impl std::str::FromStr for ChatType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(ChatType::Single),
+            "group" => Ok(ChatType::Group),
+            other => Err(format!("unknown chat type '{}', expected single or group", other)),
+        }
+    }
+}
+
+/// Filters for [`crate::BeeperClient::list_chats_with`]
+///
+/// Account IDs are sent to the Desktop API; every filter is also applied to the returned
+/// chats, and further pages are fetched until `limit` chats match.
+#[derive(Debug, Clone, Default)]
+pub struct ListChatsParams {
+    /// Only chats on these accounts
+    pub account_ids: Vec<String>,
+    /// Only chats in this inbox
+    ///
+    /// Applied client-side only. If Beeper Desktop leaves archived chats out of
+    /// `/v1/chats`, [`Inbox::Archive`] matches nothing.
+    pub inbox: Option<Inbox>,
+    /// Only chats with unread messages
+    pub unread_only: bool,
+    /// Only direct or only group chats
+    pub chat_type: Option<ChatType>,
+    /// Only pinned chats
+    pub pinned_only: bool,
+    /// Only muted (`true`) or only unmuted (`false`) chats
+    pub muted: Option<bool>,
+    /// Number of matching chats to collect before returning, [`DEFAULT_CHAT_LIMIT`] if unset
+    pub limit: Option<u32>,
+}
+
+impl ListChatsParams {
+    /// Creates empty filters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts results to these accounts
+    pub fn account_ids<I: IntoIterator<Item = S>, S: Into<String>>(mut self, account_ids: I) -> Self {
+        self.account_ids = account_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Restricts results to one inbox
+    ///
+    /// The Desktop API's chat list has no inbox filter, so this only filters the chats it
+    /// returns; see [`ListChatsParams::inbox`].
+    pub fn inbox(mut self, inbox: Inbox) -> Self {
+        self.inbox = Some(inbox);
+        self
+    }
+
+    /// Restricts results to chats with unread messages
+    pub fn unread_only(mut self, unread_only: bool) -> Self {
+        self.unread_only = unread_only;
+        self
+    }
+
+    /// Restricts results to one kind of chat
+    pub fn chat_type(mut self, chat_type: ChatType) -> Self {
+        self.chat_type = Some(chat_type);
+        self
+    }
+
+    /// Restricts results to pinned chats
+    pub fn pinned_only(mut self, pinned_only: bool) -> Self {
+        self.pinned_only = pinned_only;
+        self
+    }
+
+    /// Restricts results to muted or unmuted chats
+    pub fn muted(mut self, muted: bool) -> Self {
+        self.muted = Some(muted);
+        self
+    }
+
+    /// Sets the number of matching chats to collect
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Builds the query string for `/v1/chats`, without a leading `?`
+    pub(crate) fn to_query_string(&self) -> String {
+        self.account_ids
+            .iter()
+            .map(|id| format!("accountIDs={}", urlencoding::encode(id)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// True if the chat satisfies every filter
+    pub fn matches(&self, chat: &Chat) -> bool {
+        (self.account_ids.is_empty() || self.account_ids.contains(&chat.account_id))
+            && self.inbox.is_none_or(|inbox| Inbox::of(chat) == inbox)
+            && (!self.unread_only || chat.unread_count > 0)
+            && self.chat_type.is_none_or(|t| chat.chat_type == t.as_str())
+            && (!self.pinned_only || chat.is_pinned)
+            && self.muted.is_none_or(|muted| chat.is_muted == muted)
+    }
+}
+
+/// Filters whose matches [`Collector`] gathers across pages
+pub(crate) trait PageFilter {
+    /// Page type returned by the endpoint being filtered
+    type Page: CollectedPage;
+
+    /// Drops the page's items that do not match
+    fn retain(&self, page: &mut Self::Page);
+
+    /// Number of matches to collect before stopping
+    fn target(&self) -> usize;
+}
+
+/// Paginated outputs that [`Collector`] can merge
+pub(crate) trait CollectedPage: Sized {
+    fn item_count(&self) -> usize;
+    fn has_more(&self) -> bool;
+    fn cursor(&self, newer: bool) -> Option<String>;
+    /// Appends a later page, taking its `has_more` and the cursor for the paging direction
+    fn append(&mut self, next: Self, newer: bool);
+    fn empty() -> Self;
+}
+
+impl PageFilter for ListChatsParams {
+    type Page = ListChatsOutput;
+
+    fn retain(&self, page: &mut ListChatsOutput) {
+        page.items.retain(|c| self.matches(c));
+    }
+
+    fn target(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_CHAT_LIMIT) as usize
+    }
+}
+
+impl PageFilter for MessageSearchParams {
+    type Page = SearchMessagesOutput;
+
+    fn retain(&self, page: &mut SearchMessagesOutput) {
+        let chats = page.chats.as_ref();
+        page.items.retain(|m| self.matches(m, chats));
+    }
+
+    fn target(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_SEARCH_LIMIT) as usize
+    }
+}
+
+impl CollectedPage for ListChatsOutput {
+    fn item_count(&self) -> usize {
+        self.items.len()
+    }
+
+    fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    fn cursor(&self, newer: bool) -> Option<String> {
+        if newer { self.newest_cursor.clone() } else { self.oldest_cursor.clone() }
+    }
+
+    fn append(&mut self, next: Self, newer: bool) {
+        self.items.extend(next.items);
+        self.has_more = next.has_more;
+        if newer {
+            self.newest_cursor = next.newest_cursor;
+        } else {
+            self.oldest_cursor = next.oldest_cursor;
+        }
+    }
+
+    fn empty() -> Self {
+        ListChatsOutput {
+            items: Vec::new(),
+            has_more: false,
+            oldest_cursor: None,
+            newest_cursor: None,
+        }
+    }
+}
+
+impl CollectedPage for SearchMessagesOutput {
+    fn item_count(&self) -> usize {
+        self.items.len()
+    }
+
+    fn has_more(&self) -> bool {
+        self.has_more
+    }
+
+    fn cursor(&self, newer: bool) -> Option<String> {
+        if newer { self.newest_cursor.clone() } else { self.oldest_cursor.clone() }
+    }
+
+    fn append(&mut self, next: Self, newer: bool) {
+        self.items.extend(next.items);
+        if let Some(chats) = next.chats {
+            self.chats.get_or_insert_with(HashMap::new).extend(chats);
+        }
+        self.has_more = next.has_more;
+        if newer {
+            self.newest_cursor = next.newest_cursor;
+        } else {
+            self.oldest_cursor = next.oldest_cursor;
+        }
+    }
+
+    fn empty() -> Self {
+        SearchMessagesOutput {
+            items: Vec::new(),
+            chats: None,
+            has_more: false,
+            oldest_cursor: None,
+            newest_cursor: None,
+        }
+    }
+}
+
+/// Accumulates filtered pages until enough matches are collected
+pub(crate) struct Collector<'a, F: PageFilter> {
+    params: &'a F,
+    newer: bool,
+    output: Option<F::Page>,
+}
+
+impl<'a, F: PageFilter> Collector<'a, F> {
+    pub(crate) fn new(params: &'a F, direction: Option<&str>) -> Self {
+        Self {
+            params,
+            newer: direction == Some("after"),
+            output: None,
+        }
+    }
+
+    /// Adds a page and returns the cursor to fetch next, if more pages are needed
+    ///
+    /// Whole pages are kept, so the result may hold a few more than the target number of
+    /// matches but its cursor never skips any.
+    pub(crate) fn push(&mut self, mut page: F::Page) -> Option<String> {
+        let page_empty = page.item_count() == 0;
+        self.params.retain(&mut page);
+
+        let output = match self.output.as_mut() {
+            None => self.output.insert(page),
+            Some(output) => {
+                output.append(page, self.newer);
+                output
+            }
+        };
+
+        if output.item_count() >= self.params.target() || !output.has_more() || page_empty {
+            return None;
+        }
+        output.cursor(self.newer)
+    }
+
+    pub(crate) fn finish(self) -> F::Page {
+        self.output.unwrap_or_else(F::Page::empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(json: serde_json::Value) -> Message {
+        let mut base = serde_json::json!({
+            "id": "m1",
+            "chatID": "c1",
+            "accountID": "slack",
+            "senderID": "u1",
+            "text": "hello",
+            "timestamp": "2025-03-01T12:00:00Z",
+            "sortKey": "1"
+        });
+        base.as_object_mut().unwrap().extend(json.as_object().unwrap().clone());
+        serde_json::from_value(base).unwrap()
+    }
+
+    #[test]
+    fn test_query_string() {
+        let params = MessageSearchParams::new()
+            .query("deploy plan")
+            .chat_ids(["c1", "c2"])
+            .sender(SenderFilter::Me)
+            .date_after("2025-01-01T00:00:00Z".parse().unwrap())
+            .media_types([MediaType::Image, MediaType::Link])
+            .include_muted(false)
+            .limit(5)
+            .page_size(20);
+
+        assert_eq!(
+            params.to_query_string(),
+            "q=deploy%20plan&chatIDs=c1&chatIDs=c2&sender=me&dateAfter=2025-01-01T00%3A00%3A00%2B00%3A00\
+             &mediaTypes=image&mediaTypes=link&includeMuted=false&limit=20"
+        );
+        assert_eq!(MessageSearchParams::new().limit(5).to_query_string(), "q=");
+    }
+
+    #[test]
+    fn test_voice_filter_is_client_side_only() {
+        let params = MessageSearchParams::new().media_types([MediaType::Image, MediaType::Voice]);
+        assert_eq!(params.to_query_string(), "q=");
+
+        let voice = message(serde_json::json!({"attachments": [{"type": "audio", "isVoiceNote": true}]}));
+        let plain = message(serde_json::json!({}));
+        assert!(params.matches(&voice, None));
+        assert!(!params.matches(&plain, None));
+    }
+
+    fn page(ids: &[(&str, bool)], has_more: bool, oldest: &str) -> SearchMessagesOutput {
+        SearchMessagesOutput {
+            items: ids
+                .iter()
+                .map(|(id, mine)| message(serde_json::json!({"id": id, "isSender": mine})))
+                .collect(),
+            chats: None,
+            has_more,
+            oldest_cursor: Some(oldest.to_string()),
+            newest_cursor: Some("newest".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_collector_pages_until_limit() {
+        let params = MessageSearchParams::new().sender(SenderFilter::Me).limit(2);
+        let mut collector = Collector::new(&params, None);
+
+        assert_eq!(collector.push(page(&[("m1", true), ("m2", false)], true, "c1")).as_deref(), Some("c1"));
+        assert_eq!(collector.push(page(&[("m3", false), ("m4", true), ("m5", true)], true, "c2")), None);
+
+        let output = collector.finish();
+        let ids: Vec<&str> = output.items.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m4", "m5"]);
+        assert_eq!(output.oldest_cursor.as_deref(), Some("c2"));
+        assert_eq!(output.newest_cursor.as_deref(), Some("newest"));
+        assert!(output.has_more);
+    }
+
+    #[test]
+    fn test_collector_stops_when_exhausted() {
+        let params = MessageSearchParams::new().sender(SenderFilter::Me);
+        let mut collector = Collector::new(&params, None);
+        assert_eq!(collector.push(page(&[("m1", false)], false, "c1")), None);
+        assert!(collector.finish().items.is_empty());
+    }
+
+    fn chat(json: serde_json::Value) -> Chat {
+        let mut base = serde_json::json!({
+            "id": "c1",
+            "accountID": "slack",
+            "network": "Slack",
+            "title": "General",
+            "type": "group",
+            "participants": {"items": [], "hasMore": false, "total": 0},
+            "unreadCount": 0,
+            "isArchived": false,
+            "isMuted": false,
+            "isPinned": false
+        });
+        base.as_object_mut().unwrap().extend(json.as_object().unwrap().clone());
+        serde_json::from_value(base).unwrap()
+    }
+
+    #[test]
+    fn test_chat_filters() {
+        let archived = chat(serde_json::json!({"isArchived": true}));
+        let low = chat(serde_json::json!({"isLowPriority": true, "unreadCount": 2}));
+        let dm = chat(serde_json::json!({"type": "single", "accountID": "whatsapp", "isPinned": true, "isMuted": true}));
+
+        assert_eq!(Inbox::of(&archived), Inbox::Archive);
+        assert_eq!(Inbox::of(&low), Inbox::LowPriority);
+        assert_eq!(Inbox::of(&dm), Inbox::Primary);
+
+        let primary = ListChatsParams::new().inbox(Inbox::Primary);
+        assert!(primary.matches(&dm) && !primary.matches(&low) && !primary.matches(&archived));
+        assert!(ListChatsParams::new().unread_only(true).matches(&low));
+        assert!(!ListChatsParams::new().unread_only(true).matches(&dm));
+        assert!(ListChatsParams::new().chat_type(ChatType::Single).pinned_only(true).muted(true).matches(&dm));
+        assert!(!ListChatsParams::new().muted(false).matches(&dm));
+        assert!(!ListChatsParams::new().account_ids(["slack"]).matches(&dm));
+        assert_eq!(ListChatsParams::new().account_ids(["a b", "c"]).to_query_string(), "accountIDs=a%20b&accountIDs=c");
+    }
+
+    #[test]
+    fn test_chat_collector_keeps_cursor_of_last_page() {
+        let params = ListChatsParams::new().unread_only(true).limit(1);
+        let mut collector = Collector::new(&params, None);
+        let page = |ids: &[(&str, u32)], cursor: &str| ListChatsOutput {
+            items: ids.iter().map(|(id, unread)| chat(serde_json::json!({"id": id, "unreadCount": unread}))).collect(),
+            has_more: true,
+            oldest_cursor: Some(cursor.to_string()),
+            newest_cursor: None,
+        };
+
+        assert_eq!(collector.push(page(&[("c1", 0), ("c2", 0)], "k1")).as_deref(), Some("k1"));
+        assert_eq!(collector.push(page(&[("c3", 4), ("c4", 1)], "k2")), None);
+        let output = collector.finish();
+        assert_eq!(output.items.len(), 2);
+        assert_eq!(output.oldest_cursor.as_deref(), Some("k2"));
+    }
+
+    #[test]
+    fn test_search_excludes_low_priority_chats() {
+        let params = MessageSearchParams::new().exclude_low_priority(true);
+        let chats = HashMap::from([("c1".to_string(), chat(serde_json::json!({"isLowPriority": true})))]);
+        assert!(!params.matches(&message(serde_json::json!({})), Some(&chats)));
+        assert!(params.matches(&message(serde_json::json!({})), None));
+    }
+
+    #[test]
+    fn test_matches_sender_account_and_dates() {
+        let mine = message(serde_json::json!({"isSender": true}));
+        let theirs = message(serde_json::json!({"senderID": "u2"}));
+
+        assert!(MessageSearchParams::new().sender(SenderFilter::Me).matches(&mine, None));
+        assert!(!MessageSearchParams::new().sender(SenderFilter::Me).matches(&theirs, None));
+        assert!(MessageSearchParams::new().sender(SenderFilter::Others).matches(&theirs, None));
+        assert!(MessageSearchParams::new().sender(SenderFilter::User("u2".into())).matches(&theirs, None));
+        assert!(!MessageSearchParams::new().account_ids(["whatsapp"]).matches(&mine, None));
+
+        let march = MessageSearchParams::new()
+            .date_after("2025-03-01T00:00:00Z".parse().unwrap())
+            .date_before("2025-03-02T00:00:00Z".parse().unwrap());
+        assert!(march.matches(&mine, None));
+        assert!(!MessageSearchParams::new()
+            .date_before("2025-03-01T12:00:00Z".parse().unwrap())
+            .matches(&mine, None));
+    }
+}
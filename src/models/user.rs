@@ -35,6 +35,30 @@ pub struct User {
     pub is_self: Option<bool>,
 }
 
+impl User {
+    /// True if `handle` is this user's phone number, username, or email
+    ///
+    /// Phone numbers are compared by digits only, so `+1 (555) 010-2000` matches
+    /// `+15550102000`. Usernames ignore a leading `@` and case.
+    pub fn matches_handle(&self, handle: &str) -> bool {
+        let handle = handle.trim();
+        let digits = phone_digits(handle);
+        if digits.len() >= 5 && self.phone_number.as_deref().is_some_and(|p| phone_digits(p) == digits) {
+            return true;
+        }
+
+        let name = handle.trim_start_matches('@');
+        self.username
+            .as_deref()
+            .is_some_and(|u| u.trim_start_matches('@').eq_ignore_ascii_case(name))
+            || self.email.as_deref().is_some_and(|e| e.eq_ignore_ascii_case(handle))
+    }
+}
+
+fn phone_digits(value: &str) -> String {
+    value.chars().filter(char::is_ascii_digit).collect()
+}
+
 /// A chat account connected to Beeper
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
@@ -49,3 +73,10 @@ pub struct Account {
 
 /// Output for get_accounts
 pub type GetAccountsOutput = Vec<Account>;
+
+/// Output from searching contacts on an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchContactsOutput {
+    /// Matching users
+    pub items: Vec<User>,
+}
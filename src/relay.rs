@@ -0,0 +1,138 @@
+//! Mirroring chats into other chats, across networks
+//!
+//! A [`Relay`] forwards every new message in a source chat to one or more target chats with
+//! [`BeeperClient::forward_message`](crate::BeeperClient::forward_message)'s attribution
+//! header, e.g. a customer WhatsApp group into an internal Matrix room.
+//!
+//! Relayed copies are never relayed again: the bot skips messages you sent, and the relay
+//! skips any message that starts with the forwarding header, so a two-way bridge (or two
+//! relays run by different people) cannot loop.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::BeeperClient;
+//! use beeper_desktop_api::relay::Relay;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = BeeperClient::new("your-token-here", "http://localhost:23373");
+//!
+//!     Relay::new()
+//!         .bridge("customer-whatsapp-group", "!support:beeper.com")
+//!         .attach(client.bot())
+//!         .run()
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::bot::{Bot, Filter};
+use crate::client::messages::is_forwarded;
+use crate::models::Message;
+use crate::runtime::Runtime;
+
+/// Source-to-target chat links
+#[derive(Debug, Clone, Default)]
+pub struct Relay {
+    links: Vec<(String, String)>,
+}
+
+impl Relay {
+    /// Creates a relay with no links
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forwards new messages in `source_chat_id` to `target_chat_id`
+    ///
+    /// Linking a chat to itself does nothing.
+    pub fn link(mut self, source_chat_id: impl Into<String>, target_chat_id: impl Into<String>) -> Self {
+        let link = (source_chat_id.into(), target_chat_id.into());
+        if link.0 != link.1 && !self.links.contains(&link) {
+            self.links.push(link);
+        }
+        self
+    }
+
+    /// Forwards new messages both ways between two chats
+    pub fn bridge(self, chat_a: impl Into<String>, chat_b: impl Into<String>) -> Self {
+        let (a, b) = (chat_a.into(), chat_b.into());
+        self.link(a.clone(), b.clone()).link(b, a)
+    }
+
+    /// Chats that messages from `chat_id` are forwarded to
+    pub fn targets<'a>(&'a self, chat_id: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.links
+            .iter()
+            .filter(move |(source, _)| source == chat_id)
+            .map(|(_, target)| target.as_str())
+    }
+
+    /// True if the message is new content rather than a relayed copy
+    fn should_relay(message: &Message) -> bool {
+        !message.text.as_deref().is_some_and(is_forwarded)
+    }
+
+    /// Registers the relay as a message handler on a bot
+    ///
+    /// Every target is tried even if an earlier one fails; the first failure is then
+    /// reported to the bot's error handler.
+    pub fn attach<R: Runtime>(self, bot: Bot<R>) -> Bot<R> {
+        let sources: Vec<String> = self.links.iter().map(|(source, _)| source.clone()).collect();
+        if sources.is_empty() {
+            return bot;
+        }
+
+        bot.on_message(Filter::new().chats(sources), move |ctx| {
+            let relay = self.clone();
+            async move {
+                let Some(message) = ctx.message.as_ref().filter(|m| Self::should_relay(m)) else {
+                    return Ok(());
+                };
+                let chat = match &ctx.chat {
+                    Some(chat) => chat.clone(),
+                    None => ctx.client().get_chat(&ctx.chat_id).await?,
+                };
+                // One unreachable target must not keep the message from the others
+                let mut first_error = None;
+                for target in relay.targets(&ctx.chat_id) {
+                    if let Err(e) = ctx.client().forward(message, Some(&chat), target).await {
+                        first_error.get_or_insert(e);
+                    }
+                }
+                first_error.map_or(Ok(()), Err)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn test_links() {
+        let relay = Relay::new()
+            .bridge("wa", "matrix")
+            .link("wa", "archive")
+            .link("wa", "archive")
+            .link("matrix", "matrix");
+
+        assert_eq!(relay.targets("wa").collect::<Vec<_>>(), vec!["matrix", "archive"]);
+        assert_eq!(relay.targets("matrix").collect::<Vec<_>>(), vec!["wa"]);
+        assert_eq!(relay.targets("archive").count(), 0);
+    }
+
+    #[test]
+    fn test_relayed_copies_are_skipped() {
+        let message = |text: &str| Message {
+            chat_id: "wa".to_string(),
+            ..test_support::message("m1", "u1", "1", "2025-01-01T10:00:00Z", text)
+        };
+
+        assert!(Relay::should_relay(&message("Where is my order?")));
+        assert!(!Relay::should_relay(&message("↪ Bob in Support:\nOn its way")));
+    }
+}
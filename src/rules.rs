@@ -0,0 +1,629 @@
+//! Declarative auto-responder rules
+//!
+//! A [`RuleSet`] is loaded from TOML or YAML and compiled into a [`RuleEngine`], which
+//! matches incoming messages by chat, account, network, sender, chat type, a regex on the
+//! text, and time windows, then runs the rule's actions. Each rule fires at most once per
+//! sender within its cooldown, so an away message is not repeated on every reply.
+//!
+//! ```toml
+//! [[rules]]
+//! name = "out-of-office"
+//! networks = ["WhatsApp", "Telegram", "Signal"]
+//! chat_type = "single"
+//! cooldown = "12h"
+//! windows = [{ days = ["sat", "sun"], start = "00:00", end = "23:59", timezone = "Europe/Berlin" }]
+//! actions = [
+//!     { type = "reply", template = "Hi {sender_name}, I'm away this weekend." },
+//!     { type = "remind", after = "2d" },
+//! ]
+//! ```
+//!
+//! Available actions are `reply`, `remind`, `archive`, and `forward`. The Desktop API has
+//! no endpoints for muting chats or marking them read, so those cannot be rule actions.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::BeeperClient;
+//! use beeper_desktop_api::rules::{RuleEngine, RuleSet};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = BeeperClient::new("your-token-here", "http://localhost:23373");
+//!     let engine = RuleEngine::new(RuleSet::load("away.toml")?)?;
+//!
+//!     engine.attach(client.bot()).run().await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use regex::Regex;
+use serde::Deserialize;
+use crate::bot::{Bot, Filter};
+use crate::broadcast::fill_placeholders;
+use crate::client::BeeperClient;
+use crate::error::{BeeperError, Result};
+use crate::models::{Chat, ChatType, Message, SendMessageInput};
+use crate::runtime::Runtime;
+use crate::transcript::Timezone;
+
+/// Template used by `forward` actions that do not set one
+pub const DEFAULT_FORWARD_TEMPLATE: &str = "{sender_name} in {chat}: {text}";
+
+/// Rules as written in a TOML or YAML file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    /// Rules, evaluated in order
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Parses rules from TOML
+    pub fn from_toml(source: &str) -> Result<Self> {
+        toml::from_str(source).map_err(|e| BeeperError::InvalidConfig(format!("rules: {}", e)))
+    }
+
+    /// Parses rules from YAML
+    pub fn from_yaml(source: &str) -> Result<Self> {
+        serde_yaml::from_str(source).map_err(|e| BeeperError::InvalidConfig(format!("rules: {}", e)))
+    }
+
+    /// Reads a `.toml`, `.yaml`, or `.yml` rules file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&source),
+            Some("yaml") | Some("yml") => Self::from_yaml(&source),
+            _ => Err(BeeperError::InvalidConfig(format!(
+                "rules file {} must end in .toml, .yaml or .yml",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// One rule: conditions on the message and the actions to run when they all hold
+///
+/// Each list matches any of its values; an empty list matches everything.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Name used in logs and cooldown bookkeeping
+    pub name: String,
+    /// Chat IDs the rule applies to
+    #[serde(default)]
+    pub chats: Vec<String>,
+    /// Account IDs the rule applies to
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    /// Networks the rule applies to, compared case-insensitively
+    #[serde(default)]
+    pub networks: Vec<String>,
+    /// Sender IDs the rule applies to
+    #[serde(default)]
+    pub senders: Vec<String>,
+    /// Only direct messages or only groups
+    #[serde(default)]
+    pub chat_type: Option<ChatType>,
+    /// Regex the message text must match
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Times the rule is active; the rule is always active when empty
+    #[serde(default)]
+    pub windows: Vec<TimeWindow>,
+    /// Minimum time between firings for the same sender, e.g. `30m` or `12h`
+    #[serde(default)]
+    pub cooldown: Option<String>,
+    /// Skip the remaining rules when this one fires
+    #[serde(default)]
+    pub stop: bool,
+    /// What to do when the rule fires
+    pub actions: Vec<Action>,
+}
+
+/// Daily time range, optionally limited to some weekdays
+///
+/// A window whose `end` is before its `start` runs past midnight; `days` then names the day
+/// it starts on.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimeWindow {
+    /// Weekdays such as `mon` or `Friday`; every day when empty
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// Start time, `HH:MM`
+    pub start: String,
+    /// End time (exclusive), `HH:MM`
+    pub end: String,
+    /// Time zone the times are in, a name such as `Europe/Berlin` that follows daylight
+    /// saving time, or a fixed offset such as `+02:00`; defaults to UTC
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// What a rule does when it fires
+///
+/// Templates may use `{sender_name}`, `{sender_id}`, `{chat}`, `{network}`, `{text}`, and
+/// `{rule}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Action {
+    /// Reply to the message
+    Reply {
+        /// Reply text
+        template: String,
+    },
+    /// Set a chat reminder this long after the message, e.g. `1h`
+    Remind {
+        /// Delay before the reminder
+        after: String,
+    },
+    /// Archive the chat
+    Archive,
+    /// Send a copy of the message to another chat
+    Forward {
+        /// Chat to send to
+        chat_id: String,
+        /// Text to send; defaults to [`DEFAULT_FORWARD_TEMPLATE`]
+        #[serde(default)]
+        template: Option<String>,
+    },
+}
+
+#[derive(Debug)]
+struct Window {
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+    timezone: Timezone,
+}
+
+impl Window {
+    fn compile(window: &TimeWindow) -> std::result::Result<Self, String> {
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("invalid time '{}', expected HH:MM", value))
+        };
+        let days = window
+            .days
+            .iter()
+            .map(|d| d.parse::<Weekday>().map_err(|_| format!("invalid weekday '{}'", d)))
+            .collect::<std::result::Result<_, _>>()?;
+        let timezone = match &window.timezone {
+            Some(tz) => tz
+                .parse()
+                .map_err(|_| format!("invalid timezone '{}', expected e.g. Europe/Berlin or +02:00", tz))?,
+            None => Timezone::default(),
+        };
+        Ok(Self {
+            days,
+            start: time(&window.start)?,
+            end: time(&window.end)?,
+            timezone,
+        })
+    }
+
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        let local = self.timezone.local(at);
+        let (time, day) = (local.time(), local.weekday());
+        let on = |day: Weekday| self.days.is_empty() || self.days.contains(&day);
+        if self.start <= self.end {
+            on(day) && self.start <= time && time < self.end
+        } else {
+            (on(day) && time >= self.start) || (on(day.pred()) && time < self.end)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    rule: Rule,
+    filter: Filter,
+    text: Option<Regex>,
+    windows: Vec<Window>,
+    cooldown: Option<Duration>,
+    remind_after: Vec<Duration>,
+}
+
+impl CompiledRule {
+    fn compile(rule: Rule) -> Result<Self> {
+        let invalid = |e: String| BeeperError::InvalidConfig(format!("rule '{}': {}", rule.name, e));
+        let text = rule
+            .text
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| invalid(e.to_string()))?;
+        let windows = rule
+            .windows
+            .iter()
+            .map(Window::compile)
+            .collect::<std::result::Result<_, _>>()
+            .map_err(invalid)?;
+        let cooldown = rule.cooldown.as_deref().map(parse_duration).transpose().map_err(invalid)?;
+        let remind_after = rule
+            .actions
+            .iter()
+            .filter_map(|a| match a {
+                Action::Remind { after } => Some(parse_duration(after)),
+                _ => None,
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map_err(invalid)?;
+        if rule.actions.is_empty() {
+            return Err(invalid("no actions".to_string()));
+        }
+        let filter = Filter::new()
+            .chats(rule.chats.clone())
+            .accounts(rule.accounts.clone())
+            .networks(rule.networks.clone())
+            .senders(rule.senders.clone());
+        Ok(Self {
+            rule,
+            filter,
+            text,
+            windows,
+            cooldown,
+            remind_after,
+        })
+    }
+
+    fn matches(&self, message: &Message, chat: Option<&Chat>, at: DateTime<Utc>) -> bool {
+        if !self.filter.matches_message(message, chat, &[]) {
+            return false;
+        }
+        if let Some(chat_type) = self.rule.chat_type {
+            if chat.is_none_or(|c| c.chat_type != chat_type.as_str()) {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            if !message.text.as_deref().is_some_and(|t| text.is_match(t)) {
+                return false;
+            }
+        }
+        self.windows.is_empty() || self.windows.iter().any(|w| w.contains(at))
+    }
+}
+
+/// Parses durations such as `90s`, `15m`, `4h`, or `2d`; a bare number is seconds
+fn parse_duration(value: &str) -> std::result::Result<Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("invalid duration '{}', expected e.g. 30m or 4h", value);
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let seconds = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    number.checked_mul(seconds).map(Duration::from_secs).ok_or_else(invalid)
+}
+
+/// Parses the message timestamp, or returns the current time if it is malformed
+fn message_time(message: &Message) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&message.timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Fills a rule template from the message and chat
+fn render(template: &str, rule: &str, message: &Message, chat: Option<&Chat>) -> String {
+    let sender_name = message.sender_name.clone().unwrap_or_else(|| message.sender_id.clone());
+    let variables = HashMap::from([
+        ("sender_name", sender_name),
+        ("sender_id", message.sender_id.clone()),
+        ("chat", chat.map(|c| c.display_name()).unwrap_or_else(|| message.chat_id.clone())),
+        ("network", chat.map(|c| c.network.clone()).unwrap_or_default()),
+        ("text", message.text.clone().unwrap_or_default()),
+        ("rule", rule.to_string()),
+    ]);
+    fill_placeholders(template, &variables)
+}
+
+/// When each rule last fired, keyed by rule index and sender ID
+type Cooldowns = HashMap<(usize, String), DateTime<Utc>>;
+
+/// A rule that fired, with the cooldown entry it replaced
+struct Firing<'a> {
+    rule: &'a CompiledRule,
+    key: (usize, String),
+    previous: Option<DateTime<Utc>>,
+}
+
+/// Runs a fired rule's actions in order, stopping at the first failure
+async fn run_actions(
+    client: &BeeperClient,
+    compiled: &CompiledRule,
+    message: &Message,
+    chat: Option<&Chat>,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    let rule = &compiled.rule;
+    let mut reminders = compiled.remind_after.iter();
+    for action in &rule.actions {
+        match action {
+            Action::Reply { template } => {
+                client
+                    .send_message(&message.chat_id, SendMessageInput {
+                        text: render(template, &rule.name, message, chat),
+                        reply_to_id: Some(message.id.clone()),
+                        ..Default::default()
+                    })
+                    .await?;
+            }
+            Action::Remind { .. } => {
+                let after = reminders.next().copied().unwrap_or_default();
+                let when = at + chrono::Duration::from_std(after).unwrap_or_default();
+                client.set_chat_reminder(&message.chat_id, &when.to_rfc3339()).await?;
+            }
+            Action::Archive => {
+                client.archive_chat(&message.chat_id, true).await?;
+            }
+            Action::Forward { chat_id, template } => {
+                let template = template.as_deref().unwrap_or(DEFAULT_FORWARD_TEMPLATE);
+                client
+                    .send_message(chat_id, SendMessageInput {
+                        text: render(template, &rule.name, message, chat),
+                        ..Default::default()
+                    })
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compiled rules plus per-sender cooldown state
+///
+/// Clones share cooldowns.
+#[derive(Debug, Clone)]
+pub struct RuleEngine {
+    rules: Arc<Vec<CompiledRule>>,
+    fired: Arc<Mutex<Cooldowns>>,
+}
+
+impl RuleEngine {
+    /// Validates and compiles the rules
+    ///
+    /// Returns [`BeeperError::InvalidConfig`] for bad regexes, times, durations, or weekdays.
+    pub fn new(rule_set: RuleSet) -> Result<Self> {
+        let rules = rule_set.rules.into_iter().map(CompiledRule::compile).collect::<Result<_>>()?;
+        Ok(Self {
+            rules: Arc::new(rules),
+            fired: Arc::default(),
+        })
+    }
+
+    /// Returns the rules that fire for a message and starts their cooldowns
+    ///
+    /// Time windows and cooldowns use the message timestamp, falling back to the current
+    /// time if it cannot be parsed.
+    pub fn evaluate(&self, message: &Message, chat: Option<&Chat>) -> Vec<&Rule> {
+        self.fire(message, chat, message_time(message)).into_iter().map(|f| &f.rule.rule).collect()
+    }
+
+    fn fire(&self, message: &Message, chat: Option<&Chat>, at: DateTime<Utc>) -> Vec<Firing<'_>> {
+        let mut fired = self.fired.lock().expect("rule engine lock poisoned");
+        let mut matched = Vec::new();
+
+        for (index, compiled) in self.rules.iter().enumerate() {
+            if !compiled.matches(message, chat, at) {
+                continue;
+            }
+            let key = (index, message.sender_id.clone());
+            if let (Some(cooldown), Some(last)) = (compiled.cooldown, fired.get(&key)) {
+                if at.signed_duration_since(*last).to_std().is_ok_and(|since| since < cooldown) {
+                    continue;
+                }
+            }
+            let previous = fired.insert(key.clone(), at);
+            matched.push(Firing { rule: compiled, key, previous });
+            if compiled.rule.stop {
+                break;
+            }
+        }
+        matched
+    }
+
+    /// Restores the cooldowns that firings replaced, unless a later firing already moved them
+    fn roll_back(&self, firings: &[Firing<'_>], at: DateTime<Utc>) {
+        let mut fired = self.fired.lock().expect("rule engine lock poisoned");
+        for firing in firings {
+            if fired.get(&firing.key) != Some(&at) {
+                continue;
+            }
+            match firing.previous {
+                Some(previous) => fired.insert(firing.key.clone(), previous),
+                None => fired.remove(&firing.key),
+            };
+        }
+    }
+
+    /// Evaluates a message and runs the actions of every rule that fires
+    ///
+    /// Returns the names of the rules that fired. Stops at the first failed action; the
+    /// cooldowns of that rule and of the rules after it are rolled back, so the next
+    /// matching message tries them again.
+    pub async fn apply(&self, client: &BeeperClient, message: &Message, chat: Option<&Chat>) -> Result<Vec<String>> {
+        let at = message_time(message);
+        let firings = self.fire(message, chat, at);
+        let mut names = Vec::new();
+
+        for (done, firing) in firings.iter().enumerate() {
+            if let Err(e) = run_actions(client, firing.rule, message, chat, at).await {
+                self.roll_back(&firings[done..], at);
+                return Err(e);
+            }
+            names.push(firing.rule.rule.name.clone());
+        }
+        Ok(names)
+    }
+
+    /// Registers the rules as a message handler on a bot
+    ///
+    /// The bot already skips your own messages, so rules only see incoming ones.
+    pub fn attach<R: Runtime>(self, bot: Bot<R>) -> Bot<R> {
+        bot.on_message(Filter::new(), move |ctx| {
+            let engine = self.clone();
+            async move {
+                if let Some(message) = &ctx.message {
+                    engine.apply(ctx.client(), message, ctx.chat.as_ref()).await?;
+                }
+                Ok(())
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn message(sender: &str, text: &str, timestamp: &str) -> Message {
+        Message {
+            chat_id: "c1".to_string(),
+            sender_name: Some("Alice".to_string()),
+            ..test_support::message("m1", sender, "1", timestamp, text)
+        }
+    }
+
+    fn chat(network: &str, chat_type: &str) -> Chat {
+        Chat {
+            id: "c1".to_string(),
+            network: network.to_string(),
+            title: "Alice".to_string(),
+            chat_type: chat_type.to_string(),
+            ..test_support::chat(vec![])
+        }
+    }
+
+    const AWAY_TOML: &str = r#"
+        [[rules]]
+        name = "away"
+        networks = ["WhatsApp", "Signal"]
+        chat_type = "single"
+        cooldown = "1h"
+        windows = [{ days = ["sat", "sun"], start = "00:00", end = "23:59" }]
+        actions = [{ type = "reply", template = "Hi {sender_name}, back Monday" }]
+
+        [[rules]]
+        name = "urgent"
+        text = "(?i)\\burgent\\b"
+        actions = [{ type = "forward", chat_id = "me" }, { type = "remind", after = "15m" }]
+    "#;
+
+    #[test]
+    fn test_rules_match_and_cool_down_per_sender() {
+        let engine = RuleEngine::new(RuleSet::from_toml(AWAY_TOML).unwrap()).unwrap();
+        let whatsapp = chat("WhatsApp", "single");
+        let names = |rules: Vec<&Rule>| rules.iter().map(|r| r.name.clone()).collect::<Vec<_>>();
+
+        // Saturday
+        let first = message("u1", "URGENT: call me", "2025-01-04T10:00:00Z");
+        assert_eq!(names(engine.evaluate(&first, Some(&whatsapp))), vec!["away", "urgent"]);
+        let again = message("u1", "hello?", "2025-01-04T10:30:00Z");
+        assert!(engine.evaluate(&again, Some(&whatsapp)).is_empty());
+        let other = message("u2", "hello?", "2025-01-04T10:30:00Z");
+        assert_eq!(names(engine.evaluate(&other, Some(&whatsapp))), vec!["away"]);
+        let later = message("u1", "hello?", "2025-01-04T11:30:00Z");
+        assert_eq!(names(engine.evaluate(&later, Some(&whatsapp))), vec!["away"]);
+
+        // Monday, group chat, wrong network
+        assert!(engine.evaluate(&message("u3", "hi", "2025-01-06T10:00:00Z"), Some(&whatsapp)).is_empty());
+        assert!(engine.evaluate(&message("u3", "hi", "2025-01-04T10:00:00Z"), Some(&chat("WhatsApp", "group"))).is_empty());
+        assert!(engine.evaluate(&message("u4", "hi", "2025-01-04T10:00:00Z"), Some(&chat("Slack", "single"))).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_actions_roll_back_cooldown() {
+        let engine = RuleEngine::new(RuleSet::from_toml(AWAY_TOML).unwrap()).unwrap();
+        let whatsapp = chat("WhatsApp", "single");
+        let client = BeeperClient::new("token", "http://127.0.0.1:9");
+
+        let first = message("u1", "hi", "2025-01-04T10:00:00Z");
+        assert!(engine.apply(&client, &first, Some(&whatsapp)).await.is_err());
+        let retry = message("u1", "hi again", "2025-01-04T10:05:00Z");
+        assert_eq!(engine.evaluate(&retry, Some(&whatsapp)).len(), 1);
+        assert!(engine.evaluate(&retry, Some(&whatsapp)).is_empty());
+    }
+
+    #[test]
+    fn test_overnight_window_with_timezone() {
+        let window = Window::compile(&TimeWindow {
+            days: vec!["fri".to_string()],
+            start: "22:00".to_string(),
+            end: "06:00".to_string(),
+            timezone: Some("+02:00".to_string()),
+        })
+        .unwrap();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+
+        assert!(window.contains(at("2025-01-03T20:30:00Z"))); // Fri 22:30 local
+        assert!(window.contains(at("2025-01-04T03:00:00Z"))); // Sat 05:00 local
+        assert!(!window.contains(at("2025-01-04T04:30:00Z"))); // Sat 06:30 local
+        assert!(!window.contains(at("2025-01-04T20:30:00Z"))); // Sat 22:30 local
+    }
+
+    #[test]
+    fn test_named_window_follows_dst() {
+        let window = Window::compile(&TimeWindow {
+            days: vec!["sat".to_string()],
+            start: "09:00".to_string(),
+            end: "10:00".to_string(),
+            timezone: Some("Europe/Berlin".to_string()),
+        })
+        .unwrap();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+
+        assert!(window.contains(at("2025-01-04T08:30:00Z"))); // 09:30 CET
+        assert!(!window.contains(at("2025-07-05T08:30:00Z"))); // 10:30 CEST
+        assert!(window.contains(at("2025-07-05T07:30:00Z"))); // 09:30 CEST
+
+        let unknown = TimeWindow {
+            days: Vec::new(),
+            start: "09:00".to_string(),
+            end: "10:00".to_string(),
+            timezone: Some("Mars/Olympus".to_string()),
+        };
+        assert!(Window::compile(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_yaml_and_invalid_rules() {
+        let yaml = "rules:\n  - name: archive-bots\n    senders: [bot]\n    actions:\n      - type: archive\n";
+        let engine = RuleEngine::new(RuleSet::from_yaml(yaml).unwrap()).unwrap();
+        assert_eq!(engine.evaluate(&message("bot", "x", "2025-01-01T00:00:00Z"), None).len(), 1);
+
+        assert!(RuleSet::from_toml("[[rules]]\nname = \"x\"\nactions = [{ type = \"mute\" }]").is_err());
+        let bad_regex = RuleSet::from_toml("[[rules]]\nname = \"x\"\ntext = \"(\"\nactions = [{ type = \"archive\" }]").unwrap();
+        assert!(matches!(RuleEngine::new(bad_regex), Err(BeeperError::InvalidConfig(_))));
+        assert_eq!(parse_duration("4h"), Ok(Duration::from_secs(4 * 3600)));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_render_template() {
+        let msg = message("u1", "ping", "2025-01-01T00:00:00Z");
+        let text = render(DEFAULT_FORWARD_TEMPLATE, "r", &msg, Some(&chat("Signal", "single")));
+        assert_eq!(text, "Alice in Alice: ping");
+
+        let tricky = Message {
+            sender_name: Some("{text}".to_string()),
+            ..message("u1", "{rule}", "2025-01-01T00:00:00Z")
+        };
+        assert_eq!(render("{sender_name}: {text} {x}", "r", &tricky, None), "{text}: {rule} {x}");
+    }
+}
@@ -0,0 +1,91 @@
+//! Async runtime abstraction
+//!
+//! The core client only needs async reqwest and runs on any executor. Utilities that have
+//! to wait or run work in the background (such as the [`crate::events::EventPoller`]) go
+//! through the small [`Runtime`] trait instead of calling tokio directly.
+//!
+//! With the default `tokio` feature, [`TokioRuntime`] is provided. Users of other executors
+//! implement the trait themselves:
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use beeper_desktop_api::runtime::{BoxFuture, Runtime};
+//!
+//! #[derive(Clone)]
+//! struct SmolRuntime;
+//!
+//! impl Runtime for SmolRuntime {
+//!     fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+//!         Box::pin(async move {
+//!             smol::Timer::after(duration).await;
+//!         })
+//!     }
+//!
+//!     fn spawn(&self, future: BoxFuture<'static, ()>) {
+//!         smol::spawn(future).detach();
+//!     }
+//! }
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// A boxed, sendable future
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Minimal set of executor services used by the streaming utilities
+pub trait Runtime: Clone + Send + Sync + 'static {
+    /// Returns a future that completes after `duration` has elapsed
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+
+    /// Runs a future in the background, detached from the caller
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+}
+
+/// [`Runtime`] implementation backed by tokio
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+#[cfg(feature = "tokio")]
+impl Runtime for TokioRuntime {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        tokio::spawn(future);
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_tokio_runtime_sleep() {
+        let start = std::time::Instant::now();
+        TokioRuntime.sleep(Duration::from_millis(10)).await;
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn test_tokio_runtime_spawn() {
+        let flag = Arc::new(AtomicBool::new(false));
+        let task_flag = flag.clone();
+        TokioRuntime.spawn(Box::pin(async move {
+            task_flag.store(true, Ordering::SeqCst);
+        }));
+
+        for _ in 0..50 {
+            if flag.load(Ordering::SeqCst) {
+                break;
+            }
+            TokioRuntime.sleep(Duration::from_millis(5)).await;
+        }
+        assert!(flag.load(Ordering::SeqCst));
+    }
+}
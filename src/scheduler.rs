@@ -0,0 +1,562 @@
+//! Delayed message sending with a persistent queue
+//!
+//! The Desktop API sends immediately, so a [`Scheduler`] keeps jobs in a JSON file and sends
+//! each one with [`BeeperClient::send_message`] once its time has come. Jobs survive restarts,
+//! and while Beeper Desktop is closed ([`BeeperError::ApiNotReachable`]) they stay queued and
+//! are retried. Several processes can share one file: each change rereads it under a lock
+//! on a `.lock` file next to it, so jobs added elsewhere are never overwritten, and a runner
+//! claims each job under that lock before sending it, so no job is sent by two runners. A
+//! claim expires after [`CLAIM_LEASE`], so a job held by a runner that died is picked up
+//! again.
+//!
+//! Times are [`DateTime<Utc>`]; to send at 09:00 in a recipient's time zone, build the time
+//! with that offset and convert it, e.g.
+//! `"2025-12-24T09:00:00+09:00".parse::<DateTime<FixedOffset>>()?.with_timezone(&Utc)`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::{BeeperClient, SendMessageInput};
+//! use beeper_desktop_api::bot::ShutdownHandle;
+//! use beeper_desktop_api::runtime::TokioRuntime;
+//! use beeper_desktop_api::scheduler::Scheduler;
+//! use chrono::{Duration, Utc};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = BeeperClient::new("your-token-here", "http://localhost:23373");
+//!     let scheduler = Scheduler::open("schedule.json")?;
+//!
+//!     let input = SendMessageInput {
+//!         text: "Standup in 10 minutes".to_string(),
+//!         reply_to_id: None,
+//!         idempotency_key: None,
+//!         mentions: Vec::new(),
+//!     };
+//!     scheduler.schedule("chat-id", input, Utc::now() + Duration::minutes(50))?;
+//!
+//!     scheduler.run(&client, &TokioRuntime, &ShutdownHandle::default()).await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::bot::ShutdownHandle;
+use crate::client::BeeperClient;
+use crate::error::{BeeperError, Result};
+use crate::models::SendMessageInput;
+use crate::runtime::Runtime;
+
+/// Delay before retrying a job while Beeper Desktop is unreachable
+pub const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a runner may hold a job it is sending before others take it over
+pub const CLAIM_LEASE: Duration = Duration::from_secs(5 * 60);
+
+/// How often [`Scheduler::run`] checks for due jobs
+const TICK: Duration = Duration::from_secs(1);
+
+type ErrorHandler = Arc<dyn Fn(&BeeperError) + Send + Sync>;
+
+/// State of a scheduled job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting to be sent
+    Pending,
+    /// Claimed by a runner that is sending it, until [`ScheduledJob::claimed_until`]
+    Sending,
+    /// Accepted by Beeper Desktop
+    Sent,
+    /// Rejected by the API; see [`ScheduledJob::last_error`]
+    Failed,
+}
+
+/// A message waiting to be sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    /// Job ID, unique within the queue
+    pub id: String,
+    /// Chat to send to
+    pub chat_id: String,
+    /// Message to send
+    pub input: SendMessageInput,
+    /// Idempotency key of the send, kept apart since [`SendMessageInput`] does not store it
+    ///
+    /// Without one, a key derived from the job ID is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// When to send
+    pub send_at: DateTime<Utc>,
+    /// When the job was queued
+    pub created_at: DateTime<Utc>,
+    /// Current state
+    pub status: JobStatus,
+    /// Number of send attempts so far
+    pub attempts: u32,
+    /// Earliest time of the next attempt after a retryable failure
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Error from the last failed attempt
+    pub last_error: Option<String>,
+    /// Pending message ID returned once sent
+    pub pending_message_id: Option<String>,
+    /// End of the claim of the runner sending the job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_until: Option<DateTime<Utc>>,
+}
+
+impl ScheduledJob {
+    /// True if the job is pending, or its claim has expired, and its send (or retry) time
+    /// has passed
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        let ready = match self.status {
+            JobStatus::Pending => true,
+            JobStatus::Sending => self.claimed_until.is_none_or(|at| at <= now),
+            JobStatus::Sent | JobStatus::Failed => false,
+        };
+        ready && self.send_at <= now && self.retry_at.is_none_or(|at| at <= now)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    jobs: Vec<ScheduledJob>,
+    next_id: u64,
+}
+
+impl Queue {
+    fn new(jobs: Vec<ScheduledJob>) -> Self {
+        let next_id = jobs
+            .iter()
+            .filter_map(|j| j.id.strip_prefix("job-")?.parse::<u64>().ok())
+            .max()
+            .map_or(1, |id| id + 1);
+        Self { jobs, next_id }
+    }
+}
+
+/// Queue of scheduled messages, optionally backed by a JSON file
+///
+/// Every change is written to the file immediately. Clones share the same queue, so jobs can
+/// be added or cancelled while [`Scheduler::run`] is running.
+#[derive(Clone)]
+pub struct Scheduler {
+    queue: Arc<Mutex<Queue>>,
+    path: Option<PathBuf>,
+    retry_interval: Duration,
+    on_error: Option<ErrorHandler>,
+}
+
+impl std::fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("path", &self.path)
+            .field("retry_interval", &self.retry_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Scheduler {
+    /// Opens the queue stored at `path`, starting empty if the file does not exist
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Ok(Self {
+            queue: Arc::new(Mutex::new(Queue::new(read_jobs(&path)?))),
+            path: Some(path),
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            on_error: None,
+        })
+    }
+
+    /// Creates a queue that is lost when the process exits
+    pub fn in_memory() -> Self {
+        Self {
+            queue: Arc::new(Mutex::new(Queue::new(Vec::new()))),
+            path: None,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            on_error: None,
+        }
+    }
+
+    /// Sets the delay before retrying while Beeper Desktop is unreachable
+    pub fn with_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Called with errors that [`Scheduler::run`] recovers from, e.g. an expired token or a
+    /// queue file that cannot be written
+    pub fn on_error<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&BeeperError) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(handler));
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().expect("scheduler lock poisoned")
+    }
+
+    /// Applies a change to the latest queue and saves it
+    ///
+    /// With a file, the change runs under an exclusive lock on the `.lock` file next to it,
+    /// on jobs reread from disk, so changes made by other processes in the meantime are kept.
+    fn update<T>(&self, change: impl FnOnce(&mut Queue) -> Result<T>) -> Result<T> {
+        let mut queue = self.lock();
+        let Some(path) = &self.path else {
+            return change(&mut queue);
+        };
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let lock = File::create(path.with_extension("json.lock"))?;
+        lock.lock()?;
+
+        let next_id = queue.next_id;
+        *queue = Queue::new(read_jobs(path)?);
+        queue.next_id = queue.next_id.max(next_id);
+        let output = change(&mut queue)?;
+
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&queue.jobs)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(output)
+    }
+
+    /// Rereads the queue file, picking up changes made by other processes
+    pub fn reload(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            *self.lock() = Queue::new(read_jobs(path)?);
+        }
+        Ok(())
+    }
+
+    /// Queues a message for `send_at` and returns the new job
+    pub fn schedule(&self, chat_id: &str, input: SendMessageInput, send_at: DateTime<Utc>) -> Result<ScheduledJob> {
+        // Mention spans and the idempotency key are not stored with the input, so the
+        // mentions are written into the text and the key is kept on the job
+        let mut input = input.resolve_mentions()?;
+        let idempotency_key = input.idempotency_key.take();
+        self.update(|queue| {
+            let job = ScheduledJob {
+                id: format!("job-{}", queue.next_id),
+                chat_id: chat_id.to_string(),
+                input,
+                idempotency_key,
+                send_at,
+                created_at: Utc::now(),
+                status: JobStatus::Pending,
+                attempts: 0,
+                retry_at: None,
+                last_error: None,
+                pending_message_id: None,
+                claimed_until: None,
+            };
+            queue.next_id += 1;
+            queue.jobs.push(job.clone());
+            Ok(job)
+        })
+    }
+
+    /// Removes a pending job and returns it
+    ///
+    /// Returns [`BeeperError::NotFound`] if there is no pending job with that ID.
+    pub fn cancel(&self, id: &str) -> Result<ScheduledJob> {
+        self.update(|queue| {
+            let index = queue
+                .jobs
+                .iter()
+                .position(|j| j.id == id && j.status == JobStatus::Pending)
+                .ok_or_else(|| BeeperError::NotFound(format!("Pending job {}", id)))?;
+            Ok(queue.jobs.remove(index))
+        })
+    }
+
+    /// All jobs, soonest first
+    pub fn list(&self) -> Vec<ScheduledJob> {
+        let mut jobs = self.lock().jobs.clone();
+        jobs.sort_by_key(|j| j.send_at);
+        jobs
+    }
+
+    /// Removes sent and failed jobs and returns how many were removed
+    pub fn remove_finished(&self) -> Result<usize> {
+        self.update(|queue| {
+            let before = queue.jobs.len();
+            queue.jobs.retain(|j| matches!(j.status, JobStatus::Pending | JobStatus::Sending));
+            Ok(before - queue.jobs.len())
+        })
+    }
+
+    /// Sends every job due at `now` and returns the jobs attempted, with their new state
+    ///
+    /// Each job is claimed ([`JobStatus::Sending`]) under the file lock before it is sent,
+    /// and jobs another runner claimed first are skipped. If Beeper Desktop is unreachable
+    /// the job goes back to pending with a retry time and the rest of the round is skipped.
+    /// Other API errors mark the job failed. Returns [`BeeperError::Unauthorized`] with the
+    /// job released unchanged, since no job can succeed.
+    pub async fn run_due(&self, client: &BeeperClient, now: DateTime<Utc>) -> Result<Vec<ScheduledJob>> {
+        let due: Vec<ScheduledJob> = {
+            let mut jobs: Vec<_> = self.lock().jobs.iter().filter(|j| j.is_due(now)).cloned().collect();
+            jobs.sort_by_key(|j| j.send_at);
+            jobs
+        };
+
+        let lease = chrono::Duration::from_std(CLAIM_LEASE).unwrap_or_default();
+        let mut attempted = Vec::new();
+        for job in due {
+            let claimed = self.update(|queue| {
+                let Some(stored) = queue.jobs.iter_mut().find(|j| j.id == job.id && j.is_due(now)) else {
+                    // Cancelled, or claimed by another runner
+                    return Ok(None);
+                };
+                stored.status = JobStatus::Sending;
+                stored.claimed_until = Some(now + lease);
+                Ok(Some(stored.clone()))
+            })?;
+            let Some(job) = claimed else {
+                continue;
+            };
+
+            // A retry after an unanswered attempt must not send twice
+            let mut input = job.input.clone();
+            input.idempotency_key = Some(
+                job.idempotency_key
+                    .clone()
+                    .unwrap_or_else(|| format!("scheduler/{}/{}", job.id, job.created_at.timestamp_millis())),
+            );
+            let result = client.send_message(&job.chat_id, input).await;
+            if let Err(BeeperError::Unauthorized) = result {
+                self.update(|queue| {
+                    if let Some(stored) = queue.jobs.iter_mut().find(|j| j.id == job.id) {
+                        stored.status = JobStatus::Pending;
+                        stored.claimed_until = None;
+                    }
+                    Ok(())
+                })?;
+                return Err(BeeperError::Unauthorized);
+            }
+            let unreachable = matches!(result, Err(BeeperError::ApiNotReachable { .. } | BeeperError::RequestError(_)));
+
+            let stored = self.update(|queue| {
+                let Some(stored) = queue.jobs.iter_mut().find(|j| j.id == job.id) else {
+                    // Removed from the file while sending
+                    return Ok(None);
+                };
+                stored.attempts += 1;
+                stored.claimed_until = None;
+                match result {
+                    Ok(output) => {
+                        stored.status = JobStatus::Sent;
+                        stored.retry_at = None;
+                        stored.last_error = None;
+                        stored.pending_message_id = Some(output.pending_message_id).filter(|id| !id.is_empty());
+                    }
+                    Err(e) if unreachable => {
+                        stored.status = JobStatus::Pending;
+                        stored.retry_at = Some(now + chrono::Duration::from_std(self.retry_interval).unwrap_or_default());
+                        stored.last_error = Some(e.to_string());
+                    }
+                    Err(e) => {
+                        stored.status = JobStatus::Failed;
+                        stored.last_error = Some(e.to_string());
+                    }
+                }
+                Ok(Some(stored.clone()))
+            })?;
+            attempted.extend(stored);
+            if unreachable {
+                break;
+            }
+        }
+        Ok(attempted)
+    }
+
+    /// Sends jobs as they become due until shut down
+    ///
+    /// Rereads the queue file every second so jobs added by other processes are picked up.
+    /// Errors, including [`BeeperError::Unauthorized`] and failures to read or write the
+    /// file, go to [`Scheduler::on_error`] and the round is retried after the retry
+    /// interval, so a token can be renewed without restarting.
+    pub async fn run<R: Runtime>(&self, client: &BeeperClient, runtime: &R, shutdown: &ShutdownHandle) -> Result<()> {
+        while !shutdown.is_shutdown() {
+            let round = match self.reload() {
+                Ok(()) => self.run_due(client, Utc::now()).await.map(drop),
+                Err(e) => Err(e),
+            };
+            match round {
+                Ok(()) => runtime.sleep(TICK).await,
+                Err(e) => {
+                    if let Some(on_error) = &self.on_error {
+                        on_error(&e);
+                    }
+                    runtime.sleep(self.retry_interval).await;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_jobs(path: &Path) -> Result<Vec<ScheduledJob>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(text: &str) -> SendMessageInput {
+        SendMessageInput {
+            text: text.to_string(),
+            reply_to_id: None,
+            idempotency_key: None,
+            mentions: Vec::new(),
+        }
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_schedule_persists_and_cancels() {
+        let dir = std::env::temp_dir().join(format!("beeper-scheduler-{}", std::process::id()));
+        let path = dir.join("schedule.json");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let scheduler = Scheduler::open(&path).unwrap();
+        let later = scheduler.schedule("c1", input("later"), at("2025-06-01T09:00:00Z")).unwrap();
+        let sooner = scheduler.schedule("c2", input("sooner"), at("2025-05-01T09:00:00Z")).unwrap();
+        assert_eq!((later.id.as_str(), sooner.id.as_str()), ("job-1", "job-2"));
+
+        let reopened = Scheduler::open(&path).unwrap();
+        let ids: Vec<_> = reopened.list().into_iter().map(|j| j.id).collect();
+        assert_eq!(ids, vec!["job-2", "job-1"]);
+        assert_eq!(reopened.schedule("c1", input("x"), at("2025-07-01T00:00:00Z")).unwrap().id, "job-3");
+
+        reopened.cancel("job-1").unwrap();
+        assert!(matches!(reopened.cancel("job-1"), Err(BeeperError::NotFound(_))));
+        scheduler.reload().unwrap();
+        assert_eq!(scheduler.list().len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_schedulers_share_the_file() {
+        let dir = std::env::temp_dir().join(format!("beeper-scheduler-shared-{}", std::process::id()));
+        let path = dir.join("schedule.json");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let first = Scheduler::open(&path).unwrap();
+        let second = Scheduler::open(&path).unwrap();
+        first.schedule("c1", input("one"), at("2025-05-01T09:00:00Z")).unwrap();
+        let job = second.schedule("c2", input("two"), at("2025-05-01T09:00:00Z")).unwrap();
+        assert_eq!(job.id, "job-2");
+        first.cancel("job-2").unwrap();
+
+        let ids: Vec<_> = Scheduler::open(&path).unwrap().list().into_iter().map(|j| j.id).collect();
+        assert_eq!(ids, vec!["job-1"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_idempotency_key_survives_reload() {
+        let dir = std::env::temp_dir().join(format!("beeper-scheduler-key-{}", std::process::id()));
+        let path = dir.join("schedule.json");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let scheduler = Scheduler::open(&path).unwrap();
+        let keyed = SendMessageInput {
+            idempotency_key: Some("standup-reminder".to_string()),
+            ..input("Standup in 10 minutes")
+        };
+        scheduler.schedule("c1", keyed, at("2025-05-01T09:00:00Z")).unwrap();
+        scheduler.reload().unwrap();
+
+        let job = &scheduler.list()[0];
+        assert_eq!(job.idempotency_key.as_deref(), Some("standup-reminder"));
+        assert_eq!(job.input.idempotency_key, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_due_jobs() {
+        let scheduler = Scheduler::in_memory();
+        let mut job = scheduler.schedule("c1", input("hi"), at("2025-05-01T09:00:00Z")).unwrap();
+
+        assert!(!job.is_due(at("2025-05-01T08:59:59Z")));
+        assert!(job.is_due(at("2025-05-01T09:00:00Z")));
+        job.retry_at = Some(at("2025-05-01T09:00:30Z"));
+        assert!(!job.is_due(at("2025-05-01T09:00:10Z")));
+        job.status = JobStatus::Sent;
+        assert!(!job.is_due(at("2025-05-02T00:00:00Z")));
+
+        job.status = JobStatus::Sending;
+        job.claimed_until = Some(at("2025-05-01T09:05:00Z"));
+        assert!(!job.is_due(at("2025-05-01T09:04:00Z")));
+        assert!(job.is_due(at("2025-05-01T09:05:00Z")));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_unreachable_desktop_keeps_job_queued() {
+        let client = BeeperClient::new("token", "http://127.0.0.1:9");
+        let scheduler = Scheduler::in_memory().with_retry_interval(Duration::from_secs(60));
+        scheduler.schedule("c1", input("one"), at("2025-05-01T09:00:00Z")).unwrap();
+        scheduler.schedule("c1", input("two"), at("2025-05-01T09:00:00Z")).unwrap();
+
+        let now = at("2025-05-01T10:00:00Z");
+        let attempted = scheduler.run_due(&client, now).await.unwrap();
+        assert_eq!(attempted.len(), 1);
+        assert_eq!(attempted[0].status, JobStatus::Pending);
+        assert_eq!(attempted[0].attempts, 1);
+        assert_eq!(attempted[0].retry_at, Some(at("2025-05-01T10:01:00Z")));
+        let retried = scheduler.run_due(&client, at("2025-05-01T10:00:30Z")).await.unwrap();
+        assert_eq!(retried.iter().map(|j| j.id.as_str()).collect::<Vec<_>>(), vec!["job-2"]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_claimed_jobs_are_not_sent_twice() {
+        let dir = std::env::temp_dir().join(format!("beeper-scheduler-claim-{}", std::process::id()));
+        let path = dir.join("schedule.json");
+        let _ = std::fs::remove_dir_all(&dir);
+        let client = BeeperClient::new("token", "http://127.0.0.1:9");
+
+        let first = Scheduler::open(&path).unwrap();
+        let second = Scheduler::open(&path).unwrap();
+        first.schedule("c1", input("one"), at("2025-05-01T09:00:00Z")).unwrap();
+        // The first runner is still sending job-1
+        first
+            .update(|queue| {
+                queue.jobs[0].status = JobStatus::Sending;
+                queue.jobs[0].claimed_until = Some(at("2025-05-01T10:05:00Z"));
+                Ok(())
+            })
+            .unwrap();
+
+        second.reload().unwrap();
+        assert!(second.run_due(&client, at("2025-05-01T10:00:00Z")).await.unwrap().is_empty());
+        assert!(matches!(second.cancel("job-1"), Err(BeeperError::NotFound(_))));
+        assert_eq!(second.remove_finished().unwrap(), 0);
+
+        // Its claim ran out, so the job is taken over
+        let attempted = second.run_due(&client, at("2025-05-01T10:05:00Z")).await.unwrap();
+        assert_eq!(attempted.len(), 1);
+        assert_eq!(attempted[0].status, JobStatus::Pending);
+        assert_eq!(attempted[0].claimed_until, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
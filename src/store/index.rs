@@ -0,0 +1,496 @@
+//! Offline full-text search over synced messages
+//!
+//! [`SearchIndex`] is a tantivy index fed from message history you already have, such as
+//! [`BeeperClient::list_messages`](crate::BeeperClient::list_messages) pages, an export, or
+//! the SQLite mirror. It works while Beeper Desktop is closed and filters on fields the
+//! server search cannot: sender, chat, network, date range, and attachment type.
+//!
+//! The query text uses tantivy's syntax, so `"exact phrase"`, `deploy AND rollback`, and
+//! `-draft` all work. Invalid syntax is parsed leniently rather than rejected.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::MediaType;
+//! use beeper_desktop_api::store::index::{IndexQuery, SearchIndex};
+//!
+//! # fn run(chat: &beeper_desktop_api::Chat, messages: &[beeper_desktop_api::Message]) -> beeper_desktop_api::Result<()> {
+//! let mut index = SearchIndex::open("./beeper-index")?;
+//! index.index_messages(chat, messages)?;
+//!
+//! let query = IndexQuery::new("\"quarterly report\"")
+//!     .on_networks(["Slack"])
+//!     .with_media([MediaType::File]);
+//! for hit in index.search(&query)? {
+//!     println!("{}: {}", hit.message.chat_id, hit.highlighted("**", "**"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::{Bound, Range};
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, RangeQuery, TermSetQuery};
+use tantivy::schema::{Field, Schema, Value, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{doc, Index, IndexReader, IndexWriter, Order, ReloadPolicy, TantivyDocument, Term};
+use crate::error::Result;
+use crate::models::{Chat, MediaType, Message};
+
+/// Memory budget for the index writer
+const WRITER_MEMORY_BYTES: usize = 50_000_000;
+
+/// Number of results returned when [`IndexQuery::limit`] is not set
+pub const DEFAULT_LIMIT: usize = 50;
+
+/// Messages loaded from the SQLite mirror and committed at a time by `index_store`
+pub const INDEX_BATCH_SIZE: usize = 1000;
+
+/// Maximum length of a highlight fragment
+const FRAGMENT_CHARS: usize = 160;
+
+#[derive(Clone, Copy)]
+struct Fields {
+    key: Field,
+    chat_id: Field,
+    sender_id: Field,
+    account_id: Field,
+    network: Field,
+    media: Field,
+    timestamp: Field,
+    text: Field,
+    message: Field,
+}
+
+impl Fields {
+    fn schema() -> (Schema, Fields) {
+        let mut builder = Schema::builder();
+        let fields = Fields {
+            key: builder.add_text_field("key", STRING),
+            chat_id: builder.add_text_field("chat_id", STRING),
+            sender_id: builder.add_text_field("sender_id", STRING),
+            account_id: builder.add_text_field("account_id", STRING),
+            network: builder.add_text_field("network", STRING),
+            media: builder.add_text_field("media", STRING),
+            timestamp: builder.add_date_field("timestamp", INDEXED | FAST),
+            text: builder.add_text_field("text", TEXT | STORED),
+            message: builder.add_text_field("message", STORED),
+        };
+        (builder.build(), fields)
+    }
+}
+
+/// Filters for [`SearchIndex::search`]
+///
+/// Each list filter matches any of its values; different filters must all match.
+#[derive(Debug, Clone, Default)]
+pub struct IndexQuery {
+    /// Full-text query; empty matches every message, newest first
+    pub text: String,
+    /// Only messages in these chats
+    pub chat_ids: Vec<String>,
+    /// Only messages from these senders
+    pub sender_ids: Vec<String>,
+    /// Only messages on these networks (case-insensitive, e.g. `WhatsApp`)
+    pub networks: Vec<String>,
+    /// Only messages in these accounts
+    pub account_ids: Vec<String>,
+    /// Only messages at or after this time
+    pub after: Option<DateTime<Utc>>,
+    /// Only messages before this time
+    pub before: Option<DateTime<Utc>>,
+    /// Only messages carrying one of these media types
+    pub media_types: Vec<MediaType>,
+    /// Maximum number of results, [`DEFAULT_LIMIT`] if unset
+    pub limit: Option<usize>,
+}
+
+impl IndexQuery {
+    /// Creates a query for the given text
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Restricts results to these chats
+    pub fn in_chats<I: IntoIterator<Item = S>, S: Into<String>>(mut self, chat_ids: I) -> Self {
+        self.chat_ids = chat_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Restricts results to these senders
+    pub fn from_senders<I: IntoIterator<Item = S>, S: Into<String>>(mut self, sender_ids: I) -> Self {
+        self.sender_ids = sender_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Restricts results to these networks
+    pub fn on_networks<I: IntoIterator<Item = S>, S: Into<String>>(mut self, networks: I) -> Self {
+        self.networks = networks.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Restricts results to these accounts
+    pub fn in_accounts<I: IntoIterator<Item = S>, S: Into<String>>(mut self, account_ids: I) -> Self {
+        self.account_ids = account_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Restricts results to messages at or after `time`
+    pub fn after(mut self, time: DateTime<Utc>) -> Self {
+        self.after = Some(time);
+        self
+    }
+
+    /// Restricts results to messages before `time`
+    pub fn before(mut self, time: DateTime<Utc>) -> Self {
+        self.before = Some(time);
+        self
+    }
+
+    /// Restricts results to messages carrying any of these media types
+    pub fn with_media<I: IntoIterator<Item = MediaType>>(mut self, media_types: I) -> Self {
+        self.media_types = media_types.into_iter().collect();
+        self
+    }
+
+    /// Sets the maximum number of results
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// A search result
+#[derive(Debug, Clone)]
+pub struct IndexHit {
+    /// The indexed message
+    pub message: Message,
+    /// Relevance score, 0 for queries without text
+    pub score: f32,
+    /// Excerpt of the message text around the best match
+    pub fragment: String,
+    /// Byte ranges of matched terms within `fragment`
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl IndexHit {
+    /// Returns `fragment` with every highlight wrapped in `open` and `close`
+    pub fn highlighted(&self, open: &str, close: &str) -> String {
+        let mut out = String::with_capacity(self.fragment.len());
+        let mut last = 0;
+        for range in &self.highlights {
+            out.push_str(&self.fragment[last..range.start]);
+            out.push_str(open);
+            out.push_str(&self.fragment[range.clone()]);
+            out.push_str(close);
+            last = range.end;
+        }
+        out.push_str(&self.fragment[last..]);
+        out
+    }
+}
+
+/// A local full-text index of messages
+pub struct SearchIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: IndexWriter,
+    fields: Fields,
+}
+
+impl SearchIndex {
+    /// Opens (or creates) an index stored in `dir`
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        let (schema, fields) = Fields::schema();
+        let directory = MmapDirectory::open(dir.as_ref()).map_err(tantivy::TantivyError::from)?;
+        Self::from_index(Index::open_or_create(directory, schema)?, fields)
+    }
+
+    /// Creates a temporary in-memory index
+    pub fn open_in_ram() -> Result<Self> {
+        let (schema, fields) = Fields::schema();
+        Self::from_index(Index::create_in_ram(schema), fields)
+    }
+
+    fn from_index(index: Index, fields: Fields) -> Result<Self> {
+        let reader = index.reader_builder().reload_policy(ReloadPolicy::Manual).try_into()?;
+        let writer = index.writer(WRITER_MEMORY_BYTES)?;
+        Ok(Self { index, reader, writer, fields })
+    }
+
+    /// Number of indexed messages
+    pub fn len(&self) -> u64 {
+        self.reader.searcher().num_docs()
+    }
+
+    /// True if nothing has been indexed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds or replaces messages from one chat and commits
+    ///
+    /// The chat supplies the network name. Messages already in the index are replaced, so
+    /// re-indexing an edited message or overlapping pages is safe.
+    pub fn index_messages(&mut self, chat: &Chat, messages: &[Message]) -> Result<usize> {
+        let f = self.fields;
+        for message in messages {
+            let key = format!("{}\u{1f}{}", message.chat_id, message.id);
+            self.writer.delete_term(Term::from_field_text(f.key, &key));
+
+            let mut document = doc!(
+                f.key => key,
+                f.chat_id => message.chat_id.as_str(),
+                f.sender_id => message.sender_id.as_str(),
+                f.network => chat.network.to_lowercase(),
+                f.text => message.text.as_deref().unwrap_or(""),
+                f.message => serde_json::to_string(message)?,
+                f.account_id => message.account_id.as_deref().unwrap_or(&chat.account_id),
+            );
+            for media in message.media_types() {
+                document.add_text(f.media, media.as_str());
+            }
+            if let Ok(time) = DateTime::parse_from_rfc3339(&message.timestamp) {
+                document.add_date(f.timestamp, tantivy::DateTime::from_timestamp_secs(time.timestamp()));
+            }
+            self.writer.add_document(document)?;
+        }
+
+        self.writer.commit()?;
+        self.reader.reload()?;
+        Ok(messages.len())
+    }
+
+    /// Indexes every message in a SQLite mirror, [`INDEX_BATCH_SIZE`] messages at a time
+    ///
+    /// Whole chats are never loaded into memory at once.
+    #[cfg(feature = "sqlite")]
+    pub fn index_store(&mut self, store: &super::sqlite::SqliteStore) -> Result<usize> {
+        let chat_ids: Vec<String> = {
+            let mut stmt = store.connection().prepare("SELECT id FROM chats")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let mut indexed = 0;
+        for chat_id in chat_ids {
+            let Some(chat) = store.load_chat(&chat_id)? else { continue };
+            store.for_each_message_batch(&chat_id, INDEX_BATCH_SIZE, |messages| {
+                indexed += self.index_messages(&chat, &messages)?;
+                Ok(true)
+            })?;
+        }
+        Ok(indexed)
+    }
+
+    /// Removes every message and commits
+    pub fn clear(&mut self) -> Result<()> {
+        self.writer.delete_all_documents()?;
+        self.writer.commit()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Runs a query, returning the best matches with highlights
+    pub fn search(&self, query: &IndexQuery) -> Result<Vec<IndexHit>> {
+        let f = self.fields;
+        let searcher = self.reader.searcher();
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).max(1);
+
+        let text_query: Option<Box<dyn Query>> = if query.text.trim().is_empty() {
+            None
+        } else {
+            let mut parser = QueryParser::for_index(&self.index, vec![f.text]);
+            parser.set_conjunction_by_default();
+            Some(parser.parse_query_lenient(&query.text).0)
+        };
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        if let Some(text_query) = &text_query {
+            clauses.push((Occur::Must, text_query.box_clone()));
+        }
+        let term_filters = [
+            (f.chat_id, query.chat_ids.clone()),
+            (f.sender_id, query.sender_ids.clone()),
+            (f.network, query.networks.iter().map(|n| n.to_lowercase()).collect()),
+            (f.account_id, query.account_ids.clone()),
+            (f.media, query.media_types.iter().map(|m| m.as_str().to_string()).collect()),
+        ];
+        for (field, values) in term_filters {
+            if !values.is_empty() {
+                let terms = values.iter().map(|v| Term::from_field_text(field, v));
+                clauses.push((Occur::Must, Box::new(TermSetQuery::new(terms))));
+            }
+        }
+        if query.after.is_some() || query.before.is_some() {
+            let bound = |time: Option<DateTime<Utc>>| {
+                time.map(|t| Term::from_field_date_for_search(f.timestamp, tantivy::DateTime::from_timestamp_secs(t.timestamp())))
+            };
+            let lower = bound(query.after).map_or(Bound::Unbounded, Bound::Included);
+            let upper = bound(query.before).map_or(Bound::Unbounded, Bound::Excluded);
+            clauses.push((Occur::Must, Box::new(RangeQuery::new(lower, upper))));
+        }
+
+        let combined: Box<dyn Query> = if clauses.is_empty() {
+            Box::new(AllQuery)
+        } else {
+            Box::new(BooleanQuery::new(clauses))
+        };
+
+        let addresses: Vec<(f32, tantivy::DocAddress)> = if text_query.is_some() {
+            searcher.search(&combined, &TopDocs::with_limit(limit))?
+        } else {
+            searcher
+                .search(
+                    &combined,
+                    &TopDocs::with_limit(limit).order_by_fast_field::<tantivy::DateTime>("timestamp", Order::Desc),
+                )?
+                .into_iter()
+                .map(|(_, address)| (0.0, address))
+                .collect()
+        };
+
+        let snippets = match &text_query {
+            Some(text_query) => {
+                let mut generator = SnippetGenerator::create(&searcher, text_query.as_ref(), f.text)?;
+                generator.set_max_num_chars(FRAGMENT_CHARS);
+                Some(generator)
+            }
+            None => None,
+        };
+
+        let mut hits = Vec::with_capacity(addresses.len());
+        for (score, address) in addresses {
+            let document: TantivyDocument = searcher.doc(address)?;
+            let json = document.get_first(f.message).and_then(|v| v.as_str()).unwrap_or("{}");
+            let message: Message = serde_json::from_str(json)?;
+
+            let (fragment, highlights) = match &snippets {
+                Some(generator) => {
+                    let snippet = generator.snippet_from_doc(&document);
+                    (snippet.fragment().to_string(), snippet.highlighted().to_vec())
+                }
+                None => (message.text.as_deref().unwrap_or("").chars().take(FRAGMENT_CHARS).collect(), Vec::new()),
+            };
+            hits.push(IndexHit { message, score, fragment, highlights });
+        }
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Attachment;
+    use crate::test_support;
+
+    fn chat(id: &str, network: &str) -> Chat {
+        Chat {
+            id: id.to_string(),
+            account_id: network.to_lowercase(),
+            network: network.to_string(),
+            title: id.to_string(),
+            ..test_support::chat(vec![])
+        }
+    }
+
+    fn message(chat_id: &str, id: &str, sender: &str, timestamp: &str, text: &str) -> Message {
+        Message {
+            chat_id: chat_id.to_string(),
+            ..test_support::message(id, sender, id.trim_start_matches('m'), timestamp, text)
+        }
+    }
+
+    fn populated() -> SearchIndex {
+        let mut index = SearchIndex::open_in_ram().unwrap();
+        let mut report = message("c1", "m2", "bob", "2025-02-10T09:00:00Z", "Attached the quarterly report");
+        report.attachments = Some(vec![Attachment {
+            typ: "unknown".to_string(),
+            src_url: None,
+            mime_type: Some("application/pdf".to_string()),
+            file_name: Some("q1.pdf".to_string()),
+            file_size: None,
+            is_gif: None,
+            is_sticker: None,
+            is_voice_note: None,
+            duration: None,
+            poster_img: None,
+        }]);
+        index
+            .index_messages(&chat("c1", "Slack"), &[
+                message("c1", "m1", "alice", "2025-01-05T09:00:00Z", "The report is quarterly now"),
+                report,
+            ])
+            .unwrap();
+        index
+            .index_messages(&chat("c2", "WhatsApp"), &[
+                message("c2", "m3", "alice", "2025-03-01T09:00:00Z", "quarterly report draft"),
+            ])
+            .unwrap();
+        index
+    }
+
+    #[test]
+    fn test_phrase_search_with_highlights() {
+        let index = populated();
+        let hits = index.search(&IndexQuery::new("\"quarterly report\"")).unwrap();
+        let ids: Vec<&str> = hits.iter().map(|h| h.message.id.as_str()).collect();
+        assert_eq!(hits.len(), 2);
+        assert!(ids.contains(&"m2") && ids.contains(&"m3"));
+
+        let hit = hits.iter().find(|h| h.message.id == "m2").unwrap();
+        assert_eq!(hit.highlighted("[", "]"), "Attached the [quarterly] [report]");
+        assert_eq!(hit.message.attachments.as_ref().unwrap()[0].file_name.as_deref(), Some("q1.pdf"));
+    }
+
+    #[test]
+    fn test_filters() {
+        let index = populated();
+
+        let slack = index.search(&IndexQuery::new("report").on_networks(["slack"])).unwrap();
+        assert_eq!(slack.len(), 2);
+
+        let whatsapp = index.search(&IndexQuery::new("report").in_accounts(["whatsapp"])).unwrap();
+        assert_eq!(whatsapp.len(), 1);
+        assert_eq!(whatsapp[0].message.id, "m3");
+
+        let files = index.search(&IndexQuery::new("report").with_media([MediaType::File])).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].message.id, "m2");
+
+        let alice_feb = IndexQuery::new("report")
+            .from_senders(["alice"])
+            .after("2025-02-01T00:00:00Z".parse().unwrap());
+        let hits = index.search(&alice_feb).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message.id, "m3");
+
+        let january = IndexQuery::new("").before("2025-02-01T00:00:00Z".parse().unwrap());
+        assert_eq!(index.search(&january).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reindexing_replaces_and_empty_query_is_newest_first() {
+        let mut index = populated();
+        assert_eq!(index.len(), 3);
+
+        let edited = message("c2", "m3", "alice", "2025-03-01T09:00:00Z", "final numbers");
+        index.index_messages(&chat("c2", "WhatsApp"), &[edited]).unwrap();
+        assert_eq!(index.len(), 3);
+        assert!(index.search(&IndexQuery::new("draft")).unwrap().is_empty());
+
+        let all = index.search(&IndexQuery::default().limit(2)).unwrap();
+        let ids: Vec<&str> = all.iter().map(|h| h.message.id.as_str()).collect();
+        assert_eq!(ids, vec!["m3", "m2"]);
+
+        index.clear().unwrap();
+        assert!(index.is_empty());
+    }
+}
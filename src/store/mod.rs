@@ -0,0 +1,12 @@
+//! Local persistence for chat data
+//!
+//! Backends are behind feature flags:
+//!
+//! - `sqlite`: [`sqlite::SqliteStore`], a normalized SQLite mirror of accounts, chats,
+//!   participants, and messages for offline queries
+//! - `search-index`: [`index::SearchIndex`], a tantivy full-text index over synced messages
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "search-index")]
+pub mod index;
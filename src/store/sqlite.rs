@@ -0,0 +1,837 @@
+//! SQLite mirror of chats, messages, and participants
+//!
+//! [`SqliteStore`] keeps a normalized copy of the Desktop API data so analytics and reporting
+//! queries can run locally with plain SQL instead of paging through the API. Pages from
+//! [`BeeperClient::list_messages`] are upserted as they arrive, keyed on each message's
+//! `sort_key`, and [`SqliteStore::sync`] fetches only what is newer than the mirror.
+//!
+//! # Schema
+//!
+//! | Table               | Key                              |
+//! |---------------------|----------------------------------|
+//! | `accounts`          | `account_id`                     |
+//! | `users`             | `id`                             |
+//! | `chats`             | `id`                             |
+//! | `chat_participants` | `chat_id`, `user_id`             |
+//! | `messages`          | `chat_id`, `id`                  |
+//! | `attachments`       | `chat_id`, `message_id`, `position` |
+//! | `reactions`         | `chat_id`, `message_id`, `id`    |
+//!
+//! `messages.sort_num` holds the numeric value of `sort_key` when it is numeric, so
+//! `ORDER BY sort_num` matches the API's ordering. Migrations are tracked with SQLite's
+//! `user_version` pragma and applied on open.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::{BeeperClient, store::sqlite::SqliteStore};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = BeeperClient::new("your-token-here", "http://localhost:23373");
+//!     let mut store = SqliteStore::open("beeper.db")?;
+//!     let summary = store.sync(&client).await?;
+//!     println!("Stored {} messages from {} chats", summary.messages, summary.chats);
+//!
+//!     let busiest: String = store.connection().query_row(
+//!         "SELECT chat_id FROM messages GROUP BY chat_id ORDER BY COUNT(*) DESC LIMIT 1",
+//!         [],
+//!         |row| row.get(0),
+//!     )?;
+//!     println!("Busiest chat: {}", busiest);
+//!     Ok(())
+//! }
+//! ```
+
+use std::cmp::Ordering;
+use std::path::Path;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use crate::client::BeeperClient;
+use crate::error::Result;
+use crate::models::{compare_sort_keys, Account, Attachment, Chat, Message, Participants, Reaction, User};
+
+/// Schema migrations, applied in order; the index + 1 is stored as `user_version`
+const MIGRATIONS: &[&str] = &[r#"
+CREATE TABLE accounts (
+    account_id TEXT PRIMARY KEY,
+    network TEXT NOT NULL,
+    user_id TEXT NOT NULL
+);
+
+CREATE TABLE users (
+    id TEXT PRIMARY KEY,
+    username TEXT,
+    phone_number TEXT,
+    email TEXT,
+    full_name TEXT,
+    img_url TEXT,
+    cannot_message INTEGER,
+    is_self INTEGER
+);
+
+CREATE TABLE chats (
+    id TEXT PRIMARY KEY,
+    local_chat_id TEXT,
+    account_id TEXT NOT NULL,
+    network TEXT NOT NULL,
+    title TEXT NOT NULL,
+    chat_type TEXT NOT NULL,
+    last_activity TEXT,
+    unread_count INTEGER NOT NULL,
+    last_read_message_sort_key INTEGER,
+    is_archived INTEGER NOT NULL,
+    is_muted INTEGER NOT NULL,
+    is_pinned INTEGER NOT NULL,
+    participant_total INTEGER NOT NULL
+);
+
+CREATE TABLE chat_participants (
+    chat_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    PRIMARY KEY (chat_id, user_id)
+);
+
+CREATE TABLE messages (
+    chat_id TEXT NOT NULL,
+    id TEXT NOT NULL,
+    account_id TEXT,
+    sender_id TEXT NOT NULL,
+    sender_name TEXT,
+    text TEXT,
+    timestamp TEXT NOT NULL,
+    sort_key TEXT NOT NULL,
+    sort_num INTEGER,
+    is_edited INTEGER,
+    is_unread INTEGER,
+    reply_to_id TEXT,
+    is_sender INTEGER,
+    PRIMARY KEY (chat_id, id)
+);
+
+CREATE INDEX messages_by_sort_key ON messages (chat_id, sort_num, sort_key);
+CREATE INDEX messages_by_sender ON messages (sender_id);
+
+CREATE TABLE attachments (
+    chat_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    src_url TEXT,
+    mime_type TEXT,
+    file_name TEXT,
+    file_size INTEGER,
+    is_gif INTEGER,
+    is_sticker INTEGER,
+    is_voice_note INTEGER,
+    duration REAL,
+    poster_img TEXT,
+    PRIMARY KEY (chat_id, message_id, position)
+);
+
+CREATE TABLE reactions (
+    chat_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    id TEXT NOT NULL,
+    reaction_key TEXT NOT NULL,
+    img_url TEXT,
+    participant_id TEXT NOT NULL,
+    emoji INTEGER,
+    PRIMARY KEY (chat_id, message_id, id)
+);
+
+CREATE TABLE sync_state (
+    chat_id TEXT PRIMARY KEY,
+    backfill_complete INTEGER NOT NULL DEFAULT 0
+);
+"#, r#"
+ALTER TABLE chats ADD COLUMN is_low_priority INTEGER;
+"#];
+
+/// Totals for a [`SqliteStore::sync`] run
+#[derive(Debug, Clone, Default)]
+pub struct SyncSummary {
+    /// Number of chats seen
+    pub chats: usize,
+    /// Number of messages written
+    pub messages: usize,
+}
+
+/// A normalized SQLite mirror of Beeper data
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    /// Opens (or creates) a database file and applies pending migrations
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Opens a temporary in-memory database
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self> {
+        migrate(&mut conn)?;
+        Ok(Self { conn })
+    }
+
+    /// Underlying connection, for running queries against the mirror
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Current schema version
+    pub fn schema_version(&self) -> Result<u32> {
+        Ok(self.conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    /// Stores accounts and their users
+    pub fn upsert_accounts(&mut self, accounts: &[Account]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for account in accounts {
+            upsert_user(&tx, &account.user)?;
+            tx.execute(
+                "INSERT INTO accounts (account_id, network, user_id) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (account_id) DO UPDATE SET network = excluded.network, user_id = excluded.user_id",
+                params![account.account_id, account.network, account.user.id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Stores a chat and its participants
+    ///
+    /// The preview message is not stored: messages only enter the mirror from contiguous
+    /// [`BeeperClient::list_messages`] pages, so the latest stored sort key never skips a gap.
+    pub fn upsert_chat(&mut self, chat: &Chat) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        upsert_chat(&tx, chat)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Stores a page of messages, replacing their attachments and reactions
+    ///
+    /// Returns the number of messages written.
+    pub fn upsert_messages(&mut self, messages: &[Message]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        for message in messages {
+            upsert_message(&tx, message)?;
+        }
+        tx.commit()?;
+        Ok(messages.len())
+    }
+
+    /// Sort key of the newest stored message in a chat
+    pub fn latest_sort_key(&self, chat_id: &str) -> Result<Option<String>> {
+        self.edge_sort_key(chat_id, "DESC")
+    }
+
+    /// Sort key of the oldest stored message in a chat
+    pub fn oldest_sort_key(&self, chat_id: &str) -> Result<Option<String>> {
+        self.edge_sort_key(chat_id, "ASC")
+    }
+
+    fn edge_sort_key(&self, chat_id: &str, order: &str) -> Result<Option<String>> {
+        let sql = format!(
+            "SELECT sort_key FROM messages WHERE chat_id = ?1 ORDER BY sort_num {0}, sort_key {0} LIMIT 1",
+            order
+        );
+        Ok(self.conn.query_row(&sql, [chat_id], |row| row.get(0)).optional()?)
+    }
+
+    /// Number of stored messages, in one chat or overall
+    pub fn message_count(&self, chat_id: Option<&str>) -> Result<u64> {
+        let count: i64 = match chat_id {
+            Some(id) => self.conn.query_row("SELECT COUNT(*) FROM messages WHERE chat_id = ?1", [id], |row| row.get(0))?,
+            None => self.conn.query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))?,
+        };
+        Ok(count as u64)
+    }
+
+    /// Loads a stored chat with its participants
+    pub fn load_chat(&self, chat_id: &str) -> Result<Option<Chat>> {
+        let chat = self
+            .conn
+            .query_row(
+                "SELECT id, local_chat_id, account_id, network, title, chat_type, last_activity, unread_count,
+                        last_read_message_sort_key, is_archived, is_muted, is_pinned, participant_total,
+                        is_low_priority
+                 FROM chats WHERE id = ?1",
+                [chat_id],
+                |row| {
+                    Ok(Chat {
+                        id: row.get(0)?,
+                        local_chat_id: row.get(1)?,
+                        account_id: row.get(2)?,
+                        network: row.get(3)?,
+                        title: row.get(4)?,
+                        chat_type: row.get(5)?,
+                        participants: Participants {
+                            items: Vec::new(),
+                            has_more: false,
+                            total: row.get(12)?,
+                        },
+                        last_activity: row.get(6)?,
+                        unread_count: row.get(7)?,
+                        last_read_message_sort_key: row.get::<_, Option<i64>>(8)?.map(|k| k as u64),
+                        is_archived: row.get(9)?,
+                        is_muted: row.get(10)?,
+                        is_pinned: row.get(11)?,
+                        is_low_priority: row.get(13)?,
+                        preview: None,
+                    })
+                },
+            )
+            .optional()?;
+        let Some(mut chat) = chat else { return Ok(None) };
+
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT u.id, u.username, u.phone_number, u.email, u.full_name, u.img_url, u.cannot_message, u.is_self
+             FROM chat_participants p JOIN users u ON u.id = p.user_id
+             WHERE p.chat_id = ?1 ORDER BY p.rowid",
+        )?;
+        let users = stmt.query_map([chat_id], |row| {
+            Ok(User {
+                id: row.get(0)?,
+                username: row.get(1)?,
+                phone_number: row.get(2)?,
+                email: row.get(3)?,
+                full_name: row.get(4)?,
+                img_url: row.get(5)?,
+                cannot_message: row.get(6)?,
+                is_self: row.get(7)?,
+            })
+        })?;
+        chat.participants.items = users.collect::<rusqlite::Result<Vec<_>>>()?;
+        chat.participants.has_more = (chat.participants.items.len() as u32) < chat.participants.total;
+        Ok(Some(chat))
+    }
+
+    /// Loads stored messages for a chat, newest first like [`BeeperClient::list_messages`]
+    pub fn load_messages(&self, chat_id: &str, limit: usize) -> Result<Vec<Message>> {
+        let mut messages = Vec::new();
+        self.for_each_message_batch(chat_id, limit, |batch| {
+            messages = batch;
+            Ok(false)
+        })?;
+        Ok(messages)
+    }
+
+    /// Calls `f` with a chat's stored messages, newest first, up to `batch_size` at a time
+    ///
+    /// Only one batch is held in memory, so chats of any size can be processed. Stops early
+    /// when `f` returns `false`.
+    pub fn for_each_message_batch<F>(&self, chat_id: &str, batch_size: usize, mut f: F) -> Result<()>
+    where
+        F: FnMut(Vec<Message>) -> Result<bool>,
+    {
+        let mut stmt = self.conn.prepare(
+            "SELECT chat_id, id, account_id, sender_id, sender_name, text, timestamp, sort_key,
+                    is_edited, is_unread, reply_to_id, is_sender
+             FROM messages WHERE chat_id = ?1
+             ORDER BY sort_num DESC, sort_key DESC",
+        )?;
+        let mut rows = stmt.query_map([chat_id], message_from_row)?;
+        loop {
+            let mut batch = Vec::new();
+            for row in rows.by_ref().take(batch_size) {
+                let mut message = row?;
+                let attachments = self.load_attachments(chat_id, &message.id)?;
+                let reactions = self.load_reactions(chat_id, &message.id)?;
+                message.attachments = (!attachments.is_empty()).then_some(attachments);
+                message.reactions = (!reactions.is_empty()).then_some(reactions);
+                batch.push(message);
+            }
+            if batch.is_empty() || !f(batch)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn load_attachments(&self, chat_id: &str, message_id: &str) -> Result<Vec<Attachment>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT type, src_url, mime_type, file_name, file_size, is_gif, is_sticker, is_voice_note,
+                    duration, poster_img
+             FROM attachments WHERE chat_id = ?1 AND message_id = ?2 ORDER BY position",
+        )?;
+        let rows = stmt.query_map(params![chat_id, message_id], |row| {
+            Ok(Attachment {
+                typ: row.get(0)?,
+                src_url: row.get(1)?,
+                mime_type: row.get(2)?,
+                file_name: row.get(3)?,
+                file_size: row.get::<_, Option<i64>>(4)?.map(|s| s as u64),
+                is_gif: row.get(5)?,
+                is_sticker: row.get(6)?,
+                is_voice_note: row.get(7)?,
+                duration: row.get(8)?,
+                poster_img: row.get(9)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    fn load_reactions(&self, chat_id: &str, message_id: &str) -> Result<Vec<Reaction>> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, reaction_key, img_url, participant_id, emoji
+             FROM reactions WHERE chat_id = ?1 AND message_id = ?2 ORDER BY rowid",
+        )?;
+        let rows = stmt.query_map(params![chat_id, message_id], |row| {
+            Ok(Reaction {
+                id: row.get(0)?,
+                reaction_key: row.get(1)?,
+                img_url: row.get(2)?,
+                participant_id: row.get(3)?,
+                emoji: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    fn backfill_complete(&self, chat_id: &str) -> Result<bool> {
+        let complete: Option<bool> = self
+            .conn
+            .query_row("SELECT backfill_complete FROM sync_state WHERE chat_id = ?1", [chat_id], |row| row.get(0))
+            .optional()?;
+        Ok(complete.unwrap_or(false))
+    }
+
+    fn set_backfill_complete(&self, chat_id: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO sync_state (chat_id, backfill_complete) VALUES (?1, 1)
+             ON CONFLICT (chat_id) DO UPDATE SET backfill_complete = 1",
+            [chat_id],
+        )?;
+        Ok(())
+    }
+
+    /// Brings one chat's messages up to date
+    ///
+    /// New messages since the latest stored sort key are fetched first and written in one
+    /// transaction, so an interrupted run never leaves a gap. History older than the mirror
+    /// is then backfilled page by page until the start of the chat; an interrupted backfill
+    /// resumes from the oldest stored message. Returns the number of messages written.
+    pub async fn sync_chat(&mut self, client: &BeeperClient, chat_id: &str) -> Result<usize> {
+        let mut written = 0;
+
+        if let Some(latest) = self.latest_sort_key(chat_id)? {
+            let mut pending = Vec::new();
+            let mut cursor: Option<String> = None;
+            loop {
+                let direction = cursor.as_ref().map(|_| "before");
+                let page = client.list_messages(chat_id, cursor.as_deref(), direction).await?;
+                let reached_latest = page
+                    .items
+                    .iter()
+                    .any(|m| compare_sort_keys(&m.sort_key, &latest) != Ordering::Greater);
+                cursor = oldest_in(&page.items);
+                pending.extend(page.items);
+                if reached_latest || !page.has_more || cursor.is_none() {
+                    break;
+                }
+            }
+            written += self.upsert_messages(&pending)?;
+        }
+
+        if !self.backfill_complete(chat_id)? {
+            let mut cursor = self.oldest_sort_key(chat_id)?;
+            loop {
+                let direction = cursor.as_ref().map(|_| "before");
+                let page = client.list_messages(chat_id, cursor.as_deref(), direction).await?;
+                written += self.upsert_messages(&page.items)?;
+                cursor = oldest_in(&page.items);
+                if !page.has_more || cursor.is_none() {
+                    self.set_backfill_complete(chat_id)?;
+                    break;
+                }
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// Mirrors every account and chat, then syncs each chat with new activity
+    ///
+    /// Chats whose preview message is already stored and whose history is fully backfilled
+    /// are skipped without requesting their messages.
+    pub async fn sync(&mut self, client: &BeeperClient) -> Result<SyncSummary> {
+        let accounts = client.get_accounts().await?;
+        self.upsert_accounts(&accounts)?;
+
+        let mut chats = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let direction = cursor.as_ref().map(|_| "before");
+            let page = client.list_chats(cursor.as_deref(), direction).await?;
+            let done = !page.has_more || page.oldest_cursor.is_none() || page.items.is_empty();
+            cursor = page.oldest_cursor;
+            chats.extend(page.items);
+            if done {
+                break;
+            }
+        }
+
+        let mut summary = SyncSummary {
+            chats: chats.len(),
+            ..Default::default()
+        };
+        for chat in &chats {
+            let up_to_date = match (&chat.preview, self.latest_sort_key(&chat.id)?) {
+                (Some(preview), Some(latest)) => compare_sort_keys(&preview.sort_key, &latest) != Ordering::Greater,
+                _ => false,
+            };
+            self.upsert_chat(chat)?;
+            if !(up_to_date && self.backfill_complete(&chat.id)?) {
+                summary.messages += self.sync_chat(client, &chat.id).await?;
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get::<_, u32>(0))? as usize;
+    if version >= MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction()?;
+    for migration in &MIGRATIONS[version..] {
+        tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len() as u32)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn oldest_in(messages: &[Message]) -> Option<String> {
+    messages
+        .iter()
+        .map(|m| &m.sort_key)
+        .min_by(|a, b| compare_sort_keys(a, b))
+        .cloned()
+}
+
+fn sort_num(sort_key: &str) -> Option<i64> {
+    sort_key.parse().ok()
+}
+
+fn upsert_user(conn: &Connection, user: &User) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO users (id, username, phone_number, email, full_name, img_url, cannot_message, is_self)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT (id) DO UPDATE SET
+            username = coalesce(excluded.username, username),
+            phone_number = coalesce(excluded.phone_number, phone_number),
+            email = coalesce(excluded.email, email),
+            full_name = coalesce(excluded.full_name, full_name),
+            img_url = coalesce(excluded.img_url, img_url),
+            cannot_message = coalesce(excluded.cannot_message, cannot_message),
+            is_self = coalesce(excluded.is_self, is_self)",
+        params![
+            user.id,
+            user.username,
+            user.phone_number,
+            user.email,
+            user.full_name,
+            user.img_url,
+            user.cannot_message,
+            user.is_self,
+        ],
+    )?;
+    Ok(())
+}
+
+fn upsert_chat(conn: &Connection, chat: &Chat) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO chats (id, local_chat_id, account_id, network, title, chat_type, last_activity,
+                            unread_count, last_read_message_sort_key, is_archived, is_muted, is_pinned,
+                            participant_total, is_low_priority)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+         ON CONFLICT (id) DO UPDATE SET
+            local_chat_id = excluded.local_chat_id,
+            account_id = excluded.account_id,
+            network = excluded.network,
+            title = excluded.title,
+            chat_type = excluded.chat_type,
+            last_activity = excluded.last_activity,
+            unread_count = excluded.unread_count,
+            last_read_message_sort_key = excluded.last_read_message_sort_key,
+            is_archived = excluded.is_archived,
+            is_muted = excluded.is_muted,
+            is_pinned = excluded.is_pinned,
+            participant_total = excluded.participant_total,
+            is_low_priority = excluded.is_low_priority",
+        params![
+            chat.id,
+            chat.local_chat_id,
+            chat.account_id,
+            chat.network,
+            chat.title,
+            chat.chat_type,
+            chat.last_activity,
+            chat.unread_count,
+            chat.last_read_message_sort_key.map(|k| k as i64),
+            chat.is_archived,
+            chat.is_muted,
+            chat.is_pinned,
+            chat.participants.total,
+            chat.is_low_priority,
+        ],
+    )?;
+
+    // A complete list replaces the stored one so participants who left are removed; a
+    // truncated one can only add participants
+    if !chat.participants.has_more {
+        conn.execute("DELETE FROM chat_participants WHERE chat_id = ?1", [&chat.id])?;
+    }
+    for user in &chat.participants.items {
+        upsert_user(conn, user)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO chat_participants (chat_id, user_id) VALUES (?1, ?2)",
+            params![chat.id, user.id],
+        )?;
+    }
+    Ok(())
+}
+
+fn upsert_message(conn: &Connection, message: &Message) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO messages (chat_id, id, account_id, sender_id, sender_name, text, timestamp, sort_key,
+                               sort_num, is_edited, is_unread, reply_to_id, is_sender)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+         ON CONFLICT (chat_id, id) DO UPDATE SET
+            account_id = excluded.account_id,
+            sender_id = excluded.sender_id,
+            sender_name = excluded.sender_name,
+            text = excluded.text,
+            timestamp = excluded.timestamp,
+            sort_key = excluded.sort_key,
+            sort_num = excluded.sort_num,
+            is_edited = excluded.is_edited,
+            is_unread = excluded.is_unread,
+            reply_to_id = excluded.reply_to_id,
+            is_sender = excluded.is_sender",
+        params![
+            message.chat_id,
+            message.id,
+            message.account_id,
+            message.sender_id,
+            message.sender_name,
+            message.text,
+            message.timestamp,
+            message.sort_key,
+            sort_num(&message.sort_key),
+            message.is_edited,
+            message.is_unread,
+            message.reply_to_id,
+            message.is_sender,
+        ],
+    )?;
+
+    conn.execute(
+        "DELETE FROM attachments WHERE chat_id = ?1 AND message_id = ?2",
+        params![message.chat_id, message.id],
+    )?;
+    for (position, attachment) in message.attachments.iter().flatten().enumerate() {
+        conn.execute(
+            "INSERT INTO attachments (chat_id, message_id, position, type, src_url, mime_type, file_name,
+                                      file_size, is_gif, is_sticker, is_voice_note, duration, poster_img)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                message.chat_id,
+                message.id,
+                position as i64,
+                attachment.typ,
+                attachment.src_url,
+                attachment.mime_type,
+                attachment.file_name,
+                attachment.file_size.map(|s| s as i64),
+                attachment.is_gif,
+                attachment.is_sticker,
+                attachment.is_voice_note,
+                attachment.duration,
+                attachment.poster_img,
+            ],
+        )?;
+    }
+
+    conn.execute(
+        "DELETE FROM reactions WHERE chat_id = ?1 AND message_id = ?2",
+        params![message.chat_id, message.id],
+    )?;
+    for reaction in message.reactions.iter().flatten() {
+        conn.execute(
+            "INSERT OR REPLACE INTO reactions (chat_id, message_id, id, reaction_key, img_url, participant_id, emoji)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                message.chat_id,
+                message.id,
+                reaction.id,
+                reaction.reaction_key,
+                reaction.img_url,
+                reaction.participant_id,
+                reaction.emoji,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn message_from_row(row: &Row<'_>) -> rusqlite::Result<Message> {
+    Ok(Message {
+        chat_id: row.get(0)?,
+        id: row.get(1)?,
+        account_id: row.get(2)?,
+        sender_id: row.get(3)?,
+        sender_name: row.get(4)?,
+        text: row.get(5)?,
+        timestamp: row.get(6)?,
+        sort_key: row.get(7)?,
+        is_edited: row.get(8)?,
+        attachments: None,
+        is_unread: row.get(9)?,
+        reactions: None,
+        reply_to_id: row.get(10)?,
+        is_sender: row.get(11)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, reaction, user};
+
+    fn message(id: &str, sort_key: &str, text: &str) -> Message {
+        Message {
+            account_id: Some("whatsapp".to_string()),
+            sender_name: Some("Alice".to_string()),
+            is_unread: Some(false),
+            ..test_support::message(id, "u1", sort_key, "2025-01-01T10:00:00Z", text)
+        }
+    }
+
+    #[test]
+    fn test_migrations_are_applied_once() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len() as u32);
+        migrate(&mut store.conn).unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn test_sort_keys_compare_numerically() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store
+            .upsert_messages(&[message("m1", "9", "a"), message("m2", "10", "b"), message("m3", "100", "c")])
+            .unwrap();
+
+        assert_eq!(store.latest_sort_key("chat-1").unwrap().as_deref(), Some("100"));
+        assert_eq!(store.oldest_sort_key("chat-1").unwrap().as_deref(), Some("9"));
+        assert_eq!(store.latest_sort_key("other").unwrap(), None);
+        assert_eq!(store.message_count(Some("chat-1")).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_upsert_replaces_reactions_and_attachments() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let mut msg = message("m1", "1", "hello");
+        msg.reactions = Some(vec![reaction("r1", "👍", "u2"), reaction("r2", "❤️", "u2")]);
+        msg.attachments = Some(vec![Attachment {
+            typ: "img".to_string(),
+            src_url: Some("mxc://example.com/a".to_string()),
+            mime_type: Some("image/png".to_string()),
+            file_name: Some("a.png".to_string()),
+            file_size: Some(2048),
+            is_gif: None,
+            is_sticker: None,
+            is_voice_note: None,
+            duration: None,
+            poster_img: None,
+        }]);
+        store.upsert_messages(std::slice::from_ref(&msg)).unwrap();
+
+        msg.text = Some("hello (edited)".to_string());
+        msg.is_edited = Some(true);
+        msg.reactions = Some(vec![reaction("r2", "❤️", "u2")]);
+        store.upsert_messages(&[msg]).unwrap();
+
+        let loaded = store.load_messages("chat-1", 10).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].text.as_deref(), Some("hello (edited)"));
+        assert_eq!(loaded[0].is_edited, Some(true));
+        assert_eq!(loaded[0].reactions.as_ref().unwrap().len(), 1);
+        assert_eq!(loaded[0].attachments.as_ref().unwrap()[0].file_size, Some(2048));
+    }
+
+    #[test]
+    fn test_message_batches() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        let messages: Vec<Message> = (1..=5).map(|i| message(&format!("m{}", i), &i.to_string(), "hi")).collect();
+        store.upsert_messages(&messages).unwrap();
+
+        let mut batches = Vec::new();
+        store
+            .for_each_message_batch("chat-1", 2, |batch| {
+                batches.push(batch.iter().map(|m| m.id.clone()).collect::<Vec<_>>());
+                Ok(true)
+            })
+            .unwrap();
+        assert_eq!(batches, vec![vec!["m5", "m4"], vec!["m3", "m2"], vec!["m1"]]);
+        assert_eq!(store.load_messages("chat-1", 3).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_upsert_chat_and_accounts() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store
+            .upsert_accounts(&[Account {
+                account_id: "whatsapp".to_string(),
+                network: "WhatsApp".to_string(),
+                user: user("me", "Me", None),
+            }])
+            .unwrap();
+
+        let chat = Chat {
+            title: "Team".to_string(),
+            unread_count: 3,
+            last_read_message_sort_key: Some(5),
+            is_pinned: true,
+            is_low_priority: Some(true),
+            preview: Some(Box::new(message("m1", "7", "latest"))),
+            ..test_support::chat(vec![user("me", "Me", None), user("u1", "Alice", None)])
+        };
+        store.upsert_chat(&chat).unwrap();
+        store.upsert_chat(&chat).unwrap();
+
+        let conn = store.connection();
+        let participants: i64 = conn
+            .query_row("SELECT COUNT(*) FROM chat_participants WHERE chat_id = 'chat-1'", [], |r| r.get(0))
+            .unwrap();
+        let users: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |r| r.get(0)).unwrap();
+        let pinned: bool = conn.query_row("SELECT is_pinned FROM chats WHERE id = 'chat-1'", [], |r| r.get(0)).unwrap();
+        assert_eq!(participants, 2);
+        assert_eq!(users, 2);
+        assert!(pinned);
+        assert_eq!(store.latest_sort_key("chat-1").unwrap(), None);
+
+        let loaded = store.load_chat("chat-1").unwrap().unwrap();
+        assert_eq!(loaded.unread_count, 3);
+        assert_eq!(loaded.is_low_priority, Some(true));
+        assert_eq!(loaded.participants.items[1].full_name.as_deref(), Some("Alice"));
+        assert!(store.load_chat("missing").unwrap().is_none());
+
+        let mut left = chat.clone();
+        left.participants.items.truncate(1);
+        left.participants.total = 1;
+        store.upsert_chat(&left).unwrap();
+        let loaded = store.load_chat("chat-1").unwrap().unwrap();
+        assert_eq!(loaded.participants.items.len(), 1);
+        assert_eq!(loaded.participants.items[0].id, "me");
+    }
+}
@@ -0,0 +1,145 @@
+//! Per-network formatting dialects
+
+use crate::models::rich_text::{write_markdown, MarkdownSyntax};
+use crate::models::{escape_markdown, Chat, RichText};
+
+/// How formatting is written on a network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// No formatting; links are written as `text (url)`
+    Plain,
+    /// `*bold*`, `_italic_`, `~strike~`, `` `code` ``
+    WhatsApp,
+    /// `**bold**`, `__italic__`, `~~strike~~`, `` `code` ``, `[text](url)`
+    Telegram,
+    /// Markdown as [`crate::SendMessageInput::text`] takes it: `**bold**`, `_italic_`,
+    /// `~~strike~~`, `` `code` ``, `[text](url)`; Beeper turns it into Matrix's HTML
+    Matrix,
+}
+
+impl Dialect {
+    /// Picks the dialect for a network name such as `WhatsApp`, falling back to
+    /// [`Dialect::Plain`] for networks without known formatting
+    pub fn for_network(network: &str) -> Self {
+        let network = network.to_lowercase();
+        if network.contains("whatsapp") {
+            Dialect::WhatsApp
+        } else if network.contains("telegram") {
+            Dialect::Telegram
+        } else if network.contains("matrix") || network.contains("beeper") {
+            Dialect::Matrix
+        } else {
+            Dialect::Plain
+        }
+    }
+
+    /// Picks the dialect for a chat from its [`Chat::network`], or its
+    /// [`Chat::account_id`] if the network name is not recognized
+    pub fn for_chat(chat: &Chat) -> Self {
+        match Self::for_network(&chat.network) {
+            Dialect::Plain => Self::for_network(&chat.account_id),
+            dialect => dialect,
+        }
+    }
+}
+
+const WHATSAPP: MarkdownSyntax = MarkdownSyntax {
+    bold: "*",
+    italic: "_",
+    strikethrough: "~",
+    links: false,
+    mention_links: false,
+    escape: false,
+};
+
+const TELEGRAM: MarkdownSyntax = MarkdownSyntax {
+    bold: "**",
+    italic: "__",
+    strikethrough: "~~",
+    links: true,
+    mention_links: false,
+    escape: false,
+};
+
+impl Dialect {
+    /// Writes `text` the way this dialect formats it
+    pub fn render(self, text: &RichText) -> String {
+        match self {
+            Dialect::Plain => text.to_plain_text(),
+            Dialect::Matrix => text.to_markdown(),
+            Dialect::WhatsApp | Dialect::Telegram => {
+                let syntax = if self == Dialect::WhatsApp { WHATSAPP } else { TELEGRAM };
+                let mut out = String::new();
+                write_markdown(&mut out, &text.nodes, &syntax);
+                out
+            }
+        }
+    }
+}
+
+/// Escapes `text` so it appears literally when used as markup
+///
+/// Alias of [`escape_markdown`].
+pub fn escape_markup(text: &str) -> String {
+    escape_markdown(text)
+}
+
+/// Converts Markdown into `dialect`
+///
+/// See [`RichText::parse_markdown`] for the accepted syntax.
+pub fn render_markup(markup: &str, dialect: Dialect) -> String {
+    dialect.render(&RichText::parse_markdown(markup))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALERT: &str = "**Disk full** on _db-1_: ~~ok~~ `df -h` see [runbook](https://x.io/r)";
+
+    #[test]
+    fn test_dialects() {
+        assert_eq!(
+            render_markup(ALERT, Dialect::WhatsApp),
+            "*Disk full* on _db-1_: ~ok~ `df -h` see runbook (https://x.io/r)"
+        );
+        assert_eq!(
+            render_markup(ALERT, Dialect::Telegram),
+            "**Disk full** on __db-1__: ~~ok~~ `df -h` see [runbook](https://x.io/r)"
+        );
+        assert_eq!(
+            render_markup(ALERT, Dialect::Matrix),
+            "**Disk full** on _db-1_: ~~ok~~ `df -h` see [runbook](https://x.io/r)"
+        );
+        assert_eq!(
+            render_markup(ALERT, Dialect::Plain),
+            "Disk full on db-1: ok df -h see runbook (https://x.io/r)"
+        );
+    }
+
+    #[test]
+    fn test_literal_markers() {
+        assert_eq!(render_markup("snake_case_name", Dialect::Telegram), "snake_case_name");
+        assert_eq!(render_markup("5 * 3 **unclosed", Dialect::Plain), "5 * 3 **unclosed");
+        // Overlapping styles keep the inner one so the output stays well-formed
+        assert_eq!(render_markup("**a _b** c_", Dialect::WhatsApp), "**a _b** c_");
+        assert_eq!(render_markup(&escape_markup("*[x](y)*"), Dialect::Telegram), "*[x](y)*");
+        assert_eq!(render_markup("<b>&\nx", Dialect::Matrix), "<b\\>&\nx");
+    }
+
+    #[test]
+    fn test_code_block() {
+        let markup = "```\nfn main() {}\n```";
+        assert_eq!(render_markup(markup, Dialect::WhatsApp), markup);
+        assert_eq!(render_markup(markup, Dialect::Plain), "fn main() {}");
+        assert_eq!(render_markup(markup, Dialect::Matrix), markup);
+    }
+
+    #[test]
+    fn test_dialect_for_network() {
+        assert_eq!(Dialect::for_network("WhatsApp"), Dialect::WhatsApp);
+        assert_eq!(Dialect::for_network("Telegram"), Dialect::Telegram);
+        assert_eq!(Dialect::for_network("Beeper (Matrix)"), Dialect::Matrix);
+        assert_eq!(Dialect::for_network("Signal"), Dialect::Plain);
+    }
+}
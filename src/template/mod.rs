@@ -0,0 +1,434 @@
+//! Message templates with per-network formatting
+//!
+//! A [`Template`] is message text with `{{...}}` tags, filled in from any value that
+//! implements [`Serialize`]:
+//!
+//! | Tag                                        | Meaning |
+//! |--------------------------------------------|---------|
+//! | `{{name}}`, `{{host.name}}`                | Inserts a variable; unknown variables are an error |
+//! | `{{#if name}}...{{else}}...{{/if}}`        | Keeps the first part if the variable is set and not `false`, `0`, `""`, `[]` or `{}` |
+//! | `{{#each items}}...{{else}}...{{/each}}`   | Repeats for every list item; the `else` part is used for an empty list |
+//! | `{{this}}`, `{{@index}}`, `{{@first}}`, `{{@last}}` | The current item, its 0-based position, and whether it is the first or last |
+//! | `{{! comment }}`                           | Left out of the output |
+//!
+//! Inside `{{#each}}`, variables are looked up on the current item first. Block tags that
+//! are alone on a line take the whole line with them.
+//!
+//! Formatting is written once in Markdown (`**bold**`, `_italic_`, `~~strike~~`, `` `code` ``,
+//! ```` ```block``` ````, `[text](url)`, `> quote`; see [`RichText::parse_markdown`]) and
+//! converted into the [`Dialect`] of the chat's network: WhatsApp's `*bold*`, Telegram's
+//! Markdown, the API's Markdown for Matrix, and plain text for networks without formatting.
+//! Inserted variables are escaped, so a `*` in a hostname never opens or closes the
+//! template's own formatting. WhatsApp has no escape syntax, though, so a value that itself
+//! looks like `*bold*` is still shown bold there.
+//!
+//! [`RichText::parse_markdown`]: crate::RichText::parse_markdown
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::{BeeperClient, Chat};
+//! use beeper_desktop_api::template::Templates;
+//! use serde_json::json;
+//!
+//! # async fn run(client: BeeperClient, chat: Chat) -> beeper_desktop_api::Result<()> {
+//! let templates = Templates::new().with_template(
+//!     "alert",
+//!     "**{{severity}}**: {{summary}}
+//! {{#each hosts}}
+//! - `{{this}}`
+//! {{/each}}
+//! {{#if runbook}}[Runbook]({{runbook}}){{else}}_No runbook_{{/if}}",
+//! )?;
+//!
+//! let input = templates.message("alert", &json!({
+//!     "severity": "CRITICAL",
+//!     "summary": "Disk full",
+//!     "hosts": ["db-1", "db-2"],
+//!     "runbook": "https://wiki.example.com/disk",
+//! }), &chat)?;
+//! client.send_message(&chat.id, input).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod format;
+
+use std::collections::HashMap;
+use serde::Serialize;
+use serde_json::Value;
+use crate::error::{BeeperError, Result};
+use crate::models::{escape_markdown, Chat, SendMessageInput};
+
+pub use format::{escape_markup, render_markup, Dialect};
+
+/// Parsed template text
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Variable(String),
+    If { name: String, then: Vec<Node>, otherwise: Vec<Node> },
+    Each { name: String, body: Vec<Node>, otherwise: Vec<Node> },
+}
+
+/// A `{{...}}` tag
+#[derive(Debug, Clone, PartialEq)]
+enum Tag {
+    Variable(String),
+    If(String),
+    Each(String),
+    Else,
+    EndIf,
+    EndEach,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Text(String),
+    Tag(Tag),
+}
+
+/// A message template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+/// True if `line` holds nothing but one block tag or comment
+fn is_standalone_tag(line: &str) -> bool {
+    let line = line.trim();
+    let single_tag = line.starts_with("{{") && line.ends_with("}}") && line.matches("{{").count() == 1;
+    single_tag && (matches!(line[2..].trim_start().chars().next(), Some('#' | '/' | '!')) || line == "{{else}}")
+}
+
+fn tokenize(source: &str) -> std::result::Result<Vec<Token>, String> {
+    // Standalone block tags drop their indentation and line break
+    let source: String = source
+        .split_inclusive('\n')
+        .map(|line| if is_standalone_tag(line) { line.trim() } else { line })
+        .collect();
+
+    let mut tokens = Vec::new();
+    let mut rest = source.as_str();
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| format!("unclosed tag `{}`", &rest[start..].lines().next().unwrap_or_default()))?;
+        let tag = rest[start + 2..start + end].trim();
+        rest = &rest[start + end + 2..];
+
+        let name = |keyword: &str| {
+            let name = tag[keyword.len()..].trim();
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                Err(format!("`{{{{{}}}}}` needs one variable name", tag))
+            } else {
+                Ok(name.to_string())
+            }
+        };
+        let tag = match tag {
+            _ if tag.starts_with('!') => continue,
+            "else" => Tag::Else,
+            "/if" => Tag::EndIf,
+            "/each" => Tag::EndEach,
+            _ if tag.starts_with("#if ") => Tag::If(name("#if")?),
+            _ if tag.starts_with("#each ") => Tag::Each(name("#each")?),
+            _ if tag.starts_with(['#', '/']) => return Err(format!("unknown tag `{{{{{}}}}}`", tag)),
+            _ => Tag::Variable(name("")?),
+        };
+        tokens.push(Token::Tag(tag));
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Parses nodes up to a closing tag, which is returned along with them
+fn parse_nodes(
+    tokens: &mut std::vec::IntoIter<Token>,
+) -> std::result::Result<(Vec<Node>, Option<Tag>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+        match tag {
+            Tag::Variable(name) => nodes.push(Node::Variable(name)),
+            Tag::If(name) => {
+                let (then, otherwise) = parse_block(tokens, "if", Tag::EndIf)?;
+                nodes.push(Node::If { name, then, otherwise });
+            }
+            Tag::Each(name) => {
+                let (body, otherwise) = parse_block(tokens, "each", Tag::EndEach)?;
+                nodes.push(Node::Each { name, body, otherwise });
+            }
+            closing => return Ok((nodes, Some(closing))),
+        }
+    }
+    Ok((nodes, None))
+}
+
+/// Parses the body of a block and its optional `{{else}}` part
+fn parse_block(
+    tokens: &mut std::vec::IntoIter<Token>,
+    keyword: &str,
+    end: Tag,
+) -> std::result::Result<(Vec<Node>, Vec<Node>), String> {
+    let (body, closing) = parse_nodes(tokens)?;
+    let otherwise = match closing {
+        Some(Tag::Else) => {
+            let (otherwise, closing) = parse_nodes(tokens)?;
+            if closing.as_ref() != Some(&end) {
+                return Err(format!("`{{{{#{}}}}}` is not closed", keyword));
+            }
+            otherwise
+        }
+        Some(closing) if closing == end => Vec::new(),
+        _ => return Err(format!("`{{{{#{}}}}}` is not closed", keyword)),
+    };
+    Ok((body, otherwise))
+}
+
+/// Values visible while rendering, innermost scope last
+struct Scope<'a> {
+    values: Vec<&'a Value>,
+    loops: Vec<(usize, usize)>,
+}
+
+impl<'a> Scope<'a> {
+    fn lookup(&self, name: &str) -> Option<Value> {
+        if let Some(&(index, len)) = self.loops.last() {
+            match name {
+                "@index" => return Some(Value::from(index)),
+                "@first" => return Some(Value::Bool(index == 0)),
+                "@last" => return Some(Value::Bool(index + 1 == len)),
+                _ => {}
+            }
+        }
+        if name == "this" || name == "." {
+            return self.values.last().map(|v| (*v).clone());
+        }
+
+        let mut parts = name.split('.');
+        let first = parts.next()?;
+        let (first, parts) = if first == "this" {
+            (parts.next()?, parts)
+        } else {
+            (first, parts)
+        };
+        let mut value = self.values.iter().rev().find_map(|v| v.get(first))?;
+        for part in parts {
+            value = match value {
+                Value::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+                value => value.get(part)?,
+            };
+        }
+        Some(value.clone())
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+fn render_nodes(out: &mut String, nodes: &[Node], scope: &Scope) -> std::result::Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Variable(name) => match scope.lookup(name) {
+                Some(Value::String(s)) => out.push_str(&escape_markdown(&s)),
+                Some(Value::Null) => {}
+                Some(value @ (Value::Number(_) | Value::Bool(_))) => out.push_str(&escape_markdown(&value.to_string())),
+                Some(_) => return Err(format!("`{}` is a list or object, not text", name)),
+                None => return Err(format!("unknown variable `{}`", name)),
+            },
+            Node::If { name, then, otherwise } => {
+                let branch = if scope.lookup(name).is_some_and(|v| is_truthy(&v)) { then } else { otherwise };
+                render_nodes(out, branch, scope)?;
+            }
+            Node::Each { name, body, otherwise } => {
+                let items = match scope.lookup(name) {
+                    Some(Value::Array(items)) => items,
+                    None | Some(Value::Null) => Vec::new(),
+                    Some(_) => return Err(format!("`{}` is not a list", name)),
+                };
+                if items.is_empty() {
+                    render_nodes(out, otherwise, scope)?;
+                }
+                for (index, item) in items.iter().enumerate() {
+                    let inner = Scope {
+                        values: scope.values.iter().copied().chain(std::iter::once(item)).collect(),
+                        loops: scope.loops.iter().copied().chain(std::iter::once((index, items.len()))).collect(),
+                    };
+                    render_nodes(out, body, &inner)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Template {
+    /// Parses a template, failing with [`BeeperError::InvalidConfig`] on unbalanced or
+    /// unknown tags
+    pub fn parse(source: &str) -> Result<Self> {
+        let parsed = tokenize(source).and_then(|tokens| match parse_nodes(&mut tokens.into_iter())? {
+            (nodes, None) => Ok(nodes),
+            (_, Some(Tag::Else)) => Err("`{{else}}` outside a block".to_string()),
+            (_, Some(_)) => Err("closing tag without a matching block".to_string()),
+        });
+        parsed
+            .map(|nodes| Self { nodes })
+            .map_err(|e| BeeperError::InvalidConfig(format!("template: {}", e)))
+    }
+
+    /// Fills in `variables` and converts the formatting into `dialect`
+    pub fn render<T: Serialize + ?Sized>(&self, variables: &T, dialect: Dialect) -> Result<String> {
+        let values = serde_json::to_value(variables)?;
+        let scope = Scope {
+            values: vec![&values],
+            loops: Vec::new(),
+        };
+        let mut markup = String::new();
+        render_nodes(&mut markup, &self.nodes, &scope)
+            .map_err(|e| BeeperError::InvalidConfig(format!("template: {}", e)))?;
+        Ok(render_markup(&markup, dialect))
+    }
+
+    /// Renders the message text for `chat`, formatted for its network
+    pub fn message<T: Serialize + ?Sized>(&self, variables: &T, chat: &Chat) -> Result<SendMessageInput> {
+        Ok(SendMessageInput {
+            text: self.render(variables, Dialect::for_chat(chat))?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Named templates
+#[derive(Debug, Clone, Default)]
+pub struct Templates {
+    templates: HashMap<String, Template>,
+}
+
+impl Templates {
+    /// Creates an empty set of templates
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses `source` and adds it under `name`, replacing any template of that name
+    pub fn with_template(mut self, name: &str, source: &str) -> Result<Self> {
+        let template = Template::parse(source).map_err(|e| match e {
+            BeeperError::InvalidConfig(message) => {
+                BeeperError::InvalidConfig(message.replacen("template", &format!("template `{}`", name), 1))
+            }
+            e => e,
+        })?;
+        self.insert(name, template);
+        Ok(self)
+    }
+
+    /// Adds a parsed template under `name`
+    pub fn insert(&mut self, name: &str, template: Template) {
+        self.templates.insert(name.to_string(), template);
+    }
+
+    /// Returns the template called `name`
+    pub fn get(&self, name: &str) -> Option<&Template> {
+        self.templates.get(name)
+    }
+
+    fn template(&self, name: &str) -> Result<&Template> {
+        self.get(name)
+            .ok_or_else(|| BeeperError::NotFound(format!("template `{}`", name)))
+    }
+
+    /// Renders the template called `name`, see [`Template::render`]
+    pub fn render<T: Serialize + ?Sized>(&self, name: &str, variables: &T, dialect: Dialect) -> Result<String> {
+        self.template(name)?.render(variables, dialect)
+    }
+
+    /// Renders the template called `name` for `chat`, see [`Template::message`]
+    pub fn message<T: Serialize + ?Sized>(&self, name: &str, variables: &T, chat: &Chat) -> Result<SendMessageInput> {
+        self.template(name)?.message(variables, chat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ALERT: &str = "**{{severity}}**: {{summary}}
+{{#each hosts}}
+- `{{name}}`{{#if @last}}.{{else}},{{/if}}
+{{else}}
+No hosts affected
+{{/each}}
+{{#if runbook}}[Runbook]({{runbook}}){{else}}_No runbook_{{/if}}";
+
+    #[test]
+    fn test_render_dialects() {
+        let template = Template::parse(ALERT).unwrap();
+        let variables = json!({
+            "severity": "CRITICAL",
+            "summary": "Disk *full*",
+            "hosts": [{"name": "db-1"}, {"name": "db-2"}],
+            "runbook": "https://wiki.example.com/disk_full",
+        });
+
+        assert_eq!(
+            template.render(&variables, Dialect::WhatsApp).unwrap(),
+            "*CRITICAL*: Disk *full*\n- `db-1`,\n- `db-2`.\nRunbook (https://wiki.example.com/disk_full)"
+        );
+        // Markdown dialects escape the variable; WhatsApp cannot
+        assert_eq!(
+            template.render(&variables, Dialect::Matrix).unwrap(),
+            "**CRITICAL**: Disk \\*full\\*\n- `db-1`,\n- `db-2`.\n[Runbook](https://wiki.example.com/disk_full)"
+        );
+    }
+
+    #[test]
+    fn test_render_else_branches() {
+        let template = Template::parse(ALERT).unwrap();
+        let variables = json!({"severity": "OK", "summary": "Recovered", "hosts": []});
+        assert_eq!(
+            template.render(&variables, Dialect::Plain).unwrap(),
+            "OK: Recovered\nNo hosts affected\nNo runbook"
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let template = Template::parse("{{#each users}}{{@index}}:{{this.name}}@{{team}} {{/each}}{{! done }}").unwrap();
+        let variables = json!({"team": "ops", "users": [{"name": "a"}, {"name": "b", "team": "dev"}]});
+        assert_eq!(template.render(&variables, Dialect::Plain).unwrap(), "0:a@ops 1:b@dev ");
+    }
+
+    #[test]
+    fn test_errors() {
+        for source in ["{{#if a}}x", "{{/each}}", "{{#each a}}{{/if}}", "{{else}}", "{{a", "{{#unless a}}{{/unless}}"] {
+            assert!(matches!(Template::parse(source), Err(BeeperError::InvalidConfig(_))), "{}", source);
+        }
+        let template = Template::parse("{{missing}}").unwrap();
+        assert!(matches!(template.render(&json!({}), Dialect::Plain), Err(BeeperError::InvalidConfig(_))));
+        assert!(matches!(
+            Templates::new().render("nope", &json!({}), Dialect::Plain),
+            Err(BeeperError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+//! Fixtures shared by the unit tests
+//!
+//! Tests adjust what they need with struct update syntax, e.g.
+//! `Message { chat_id: "c1".to_string(), ..message(...) }`.
+
+use crate::models::*;
+
+/// A participant with only a display name and avatar set
+pub fn user(id: &str, name: &str, img: Option<&str>) -> User {
+    User {
+        id: id.to_string(),
+        username: None,
+        phone_number: None,
+        email: None,
+        full_name: Some(name.to_string()),
+        img_url: img.map(str::to_string),
+        cannot_message: None,
+        is_self: None,
+    }
+}
+
+/// The account's own user, `me`
+pub fn me() -> User {
+    User { is_self: Some(true), ..user("me", "Me", None) }
+}
+
+/// WhatsApp group `chat-1` titled `Team <Ops>`
+pub fn chat(participants: Vec<User>) -> Chat {
+    Chat {
+        id: "chat-1".to_string(),
+        local_chat_id: None,
+        account_id: "whatsapp".to_string(),
+        network: "WhatsApp".to_string(),
+        title: "Team <Ops>".to_string(),
+        chat_type: "group".to_string(),
+        participants: Participants {
+            total: participants.len() as u32,
+            items: participants,
+            has_more: false,
+        },
+        last_activity: None,
+        unread_count: 0,
+        last_read_message_sort_key: None,
+        is_archived: false,
+        is_muted: false,
+        is_pinned: false,
+        is_low_priority: None,
+        preview: None,
+    }
+}
+
+/// Text message in `chat-1` with every optional field unset
+pub fn message(id: &str, sender: &str, sort_key: &str, timestamp: &str, text: &str) -> Message {
+    Message {
+        id: id.to_string(),
+        chat_id: "chat-1".to_string(),
+        account_id: None,
+        sender_id: sender.to_string(),
+        sender_name: None,
+        text: Some(text.to_string()),
+        timestamp: timestamp.to_string(),
+        sort_key: sort_key.to_string(),
+        is_edited: None,
+        attachments: None,
+        is_unread: None,
+        reactions: None,
+        reply_to_id: None,
+        is_sender: None,
+    }
+}
+
+/// Emoji reaction by `participant`
+pub fn reaction(id: &str, key: &str, participant: &str) -> Reaction {
+    Reaction {
+        id: id.to_string(),
+        reaction_key: key.to_string(),
+        img_url: None,
+        participant_id: participant.to_string(),
+        emoji: Some(true),
+    }
+}
+
+/// PNG attachment named `photo.png`
+pub fn image(src: &str) -> Attachment {
+    Attachment {
+        typ: "img".to_string(),
+        src_url: Some(src.to_string()),
+        mime_type: Some("image/png".to_string()),
+        file_name: Some("photo.png".to_string()),
+        file_size: None,
+        is_gif: None,
+        is_sticker: None,
+        is_voice_note: None,
+        duration: None,
+        poster_img: None,
+    }
+}
+
+/// Answers every request with `body` as JSON on a local port, returning its base URL
+pub fn serve_json(body: String) -> String {
+    serve("200 OK", body)
+}
+
+/// Answers every request with `status` and `body` as JSON on a local port, returning its
+/// base URL
+pub fn serve(status: &'static str, body: String) -> String {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line != "\r\n" {
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+                line.clear();
+            }
+            let _ = reader.read_exact(&mut vec![0; length]);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+        }
+    });
+    base_url
+}
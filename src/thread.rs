@@ -0,0 +1,373 @@
+//! Reply trees built from [`Message::reply_to_id`]
+//!
+//! A [`ThreadTree`] links the messages of a chat by what they reply to. Messages can be
+//! added as they arrive, e.g. from [`crate::events`], and [`BeeperClient::fetch_missing_parents`]
+//! fills in parents that have not been seen yet. The Desktop API cannot look up a message by
+//! ID, so parents are found by paging back through the chat.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use beeper_desktop_api::BeeperClient;
+//!
+//! # async fn run(client: BeeperClient) -> beeper_desktop_api::Result<()> {
+//! // The alert reply, the messages it answers, and everything said in reply to it
+//! let tree = client.load_thread("ops-chat", "alert-reply-id").await?;
+//! for message in tree.branch("alert-reply-id") {
+//!     println!("{}: {}", message.sender_id, message.text.as_deref().unwrap_or(""));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use crate::client::BeeperClient;
+use crate::error::{BeeperError, Result};
+use crate::models::{compare_sort_keys, ListMessagesOutput, Message};
+
+/// Messages of a chat linked by their replies
+#[derive(Debug, Clone, Default)]
+pub struct ThreadTree {
+    messages: HashMap<String, Message>,
+    children: HashMap<String, Vec<String>>,
+}
+
+impl ThreadTree {
+    /// Creates an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a tree from messages in any order
+    pub fn from_messages<I: IntoIterator<Item = Message>>(messages: I) -> Self {
+        let mut tree = Self::new();
+        for message in messages {
+            tree.insert(message);
+        }
+        tree
+    }
+
+    /// Adds a message, replacing an earlier copy with the same ID
+    pub fn insert(&mut self, message: Message) {
+        if let Some(parent_id) = &message.reply_to_id {
+            let siblings = self.children.entry(parent_id.clone()).or_default();
+            if !siblings.contains(&message.id) {
+                siblings.push(message.id.clone());
+            }
+        }
+        self.messages.insert(message.id.clone(), message);
+    }
+
+    /// Number of messages in the tree
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// True if the tree has no messages
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Returns the message with `message_id`
+    pub fn get(&self, message_id: &str) -> Option<&Message> {
+        self.messages.get(message_id)
+    }
+
+    /// True if the message with `message_id` is in the tree
+    pub fn contains(&self, message_id: &str) -> bool {
+        self.messages.contains_key(message_id)
+    }
+
+    /// Returns the message that `message_id` replies to, if it is in the tree
+    pub fn parent(&self, message_id: &str) -> Option<&Message> {
+        self.get(message_id)?.reply_to_id.as_deref().and_then(|id| self.get(id))
+    }
+
+    /// Replies to `message_id`, oldest first
+    pub fn children(&self, message_id: &str) -> Vec<&Message> {
+        let mut children: Vec<&Message> = self
+            .children
+            .get(message_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.get(id))
+            .collect();
+        children.sort_by(|a, b| compare_sort_keys(&a.sort_key, &b.sort_key));
+        children
+    }
+
+    /// The messages `message_id` replies to, nearest first
+    ///
+    /// Stops at the first parent that is not in the tree.
+    pub fn ancestors(&self, message_id: &str) -> Ancestors<'_> {
+        Ancestors {
+            tree: self,
+            next: self.parent(message_id),
+            seen: HashSet::from([message_id.to_string()]),
+        }
+    }
+
+    /// The oldest known ancestor of `message_id`, or the message itself if it is not a reply
+    pub fn root(&self, message_id: &str) -> Option<&Message> {
+        self.ancestors(message_id).last().or_else(|| self.get(message_id))
+    }
+
+    /// All replies below `message_id`, depth first with siblings oldest first
+    pub fn descendants(&self, message_id: &str) -> Descendants<'_> {
+        let mut stack = self.children(message_id);
+        stack.reverse();
+        Descendants {
+            tree: self,
+            stack,
+            seen: HashSet::from([message_id.to_string()]),
+        }
+    }
+
+    /// The conversation around `message_id`: its ancestors from the root down, the message
+    /// itself, then its descendants
+    pub fn branch(&self, message_id: &str) -> Vec<&Message> {
+        let Some(message) = self.get(message_id) else {
+            return Vec::new();
+        };
+        let mut branch: Vec<&Message> = self.ancestors(message_id).collect();
+        branch.reverse();
+        branch.push(message);
+        branch.extend(self.descendants(message_id));
+        branch
+    }
+
+    /// Messages that do not reply to anything, oldest first
+    pub fn roots(&self) -> Vec<&Message> {
+        let mut roots: Vec<&Message> = self.messages.values().filter(|m| m.reply_to_id.is_none()).collect();
+        roots.sort_by(|a, b| compare_sort_keys(&a.sort_key, &b.sort_key));
+        roots
+    }
+
+    /// IDs of messages that are replied to but not in the tree
+    pub fn missing_parents(&self) -> Vec<&str> {
+        let mut missing: Vec<&str> = self
+            .children
+            .keys()
+            .map(String::as_str)
+            .filter(|id| !self.contains(id))
+            .collect();
+        missing.sort_unstable();
+        missing
+    }
+
+    /// True if `message_id` and all of its ancestors are in the tree
+    pub(crate) fn has_ancestry(&self, message_id: &str) -> bool {
+        let Some(message) = self.get(message_id) else {
+            return false;
+        };
+        let top = self.ancestors(message_id).last().unwrap_or(message);
+        top.reply_to_id.as_deref().is_none_or(|id| self.contains(id) || id == message_id)
+    }
+}
+
+/// Iterator over the ancestors of a message, see [`ThreadTree::ancestors`]
+#[derive(Debug)]
+pub struct Ancestors<'a> {
+    tree: &'a ThreadTree,
+    next: Option<&'a Message>,
+    seen: HashSet<String>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = &'a Message;
+
+    fn next(&mut self) -> Option<&'a Message> {
+        let current = self.next.take()?;
+        // Reply cycles only come from broken data, but must not loop forever
+        if !self.seen.insert(current.id.clone()) {
+            return None;
+        }
+        self.next = self.tree.parent(&current.id);
+        Some(current)
+    }
+}
+
+/// Iterator over the replies below a message, see [`ThreadTree::descendants`]
+#[derive(Debug)]
+pub struct Descendants<'a> {
+    tree: &'a ThreadTree,
+    stack: Vec<&'a Message>,
+    seen: HashSet<String>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = &'a Message;
+
+    fn next(&mut self) -> Option<&'a Message> {
+        loop {
+            let current = self.stack.pop()?;
+            if !self.seen.insert(current.id.clone()) {
+                continue;
+            }
+            self.stack.extend(self.tree.children(&current.id).into_iter().rev());
+            return Some(current);
+        }
+    }
+}
+
+/// Cursor for the page before `page`, or `None` at the start of the chat
+pub(crate) fn older_cursor(page: &ListMessagesOutput) -> Option<String> {
+    page.items
+        .iter()
+        .map(|m| m.sort_key.as_str())
+        .min_by(|a, b| compare_sort_keys(a, b))
+        .filter(|_| page.has_more)
+        .map(str::to_string)
+}
+
+/// Pages [`BeeperClient::load_thread`], [`BeeperClient::fetch_missing_parents`], and
+/// [`BeeperClient::get_message`] read before giving up
+pub const DEFAULT_MAX_PAGES: usize = 100;
+
+/// Error for a search that read `max_pages` pages without finding what it looked for
+pub(crate) fn pages_exhausted(what: &str, chat_id: &str, max_pages: usize) -> BeeperError {
+    BeeperError::NotFound(format!("{} in chat {} within {} pages", what, chat_id, max_pages))
+}
+
+impl BeeperClient {
+    /// Pages back from the newest message, adding every message to `tree`, until `done`
+    /// or the start of the chat
+    ///
+    /// Returns `false` if `max_pages` pages were read without reaching either.
+    async fn page_into(
+        &self,
+        chat_id: &str,
+        tree: &mut ThreadTree,
+        max_pages: usize,
+        done: impl Fn(&ThreadTree) -> bool,
+    ) -> Result<bool> {
+        let mut cursor: Option<String> = None;
+        for _ in 0..max_pages {
+            let page = self.list_messages(chat_id, cursor.as_deref(), cursor.as_ref().map(|_| "before")).await?;
+            cursor = older_cursor(&page);
+            for message in page.items {
+                tree.insert(message);
+            }
+            if done(tree) || cursor.is_none() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Builds the reply tree around a message
+    ///
+    /// Pages back through the chat until the message and all of its ancestors are loaded.
+    /// Every newer message is loaded on the way, so the tree holds all of its replies too.
+    /// Fails with [`BeeperError::NotFound`] if the message is not in the chat, or not
+    /// within [`DEFAULT_MAX_PAGES`] pages; see [`BeeperClient::load_thread_with`].
+    pub async fn load_thread(&self, chat_id: &str, message_id: &str) -> Result<ThreadTree> {
+        self.load_thread_with(chat_id, message_id, DEFAULT_MAX_PAGES).await
+    }
+
+    /// Builds the reply tree around a message, reading at most `max_pages` pages
+    pub async fn load_thread_with(&self, chat_id: &str, message_id: &str, max_pages: usize) -> Result<ThreadTree> {
+        let mut tree = ThreadTree::new();
+        if !self.page_into(chat_id, &mut tree, max_pages, |tree| tree.has_ancestry(message_id)).await? {
+            return Err(pages_exhausted(&format!("Thread of message {}", message_id), chat_id, max_pages));
+        }
+        if !tree.contains(message_id) {
+            return Err(BeeperError::NotFound(format!("Message {} in chat {}", message_id, chat_id)));
+        }
+        Ok(tree)
+    }
+
+    /// Loads the parents missing from `tree` by paging back through the chat
+    ///
+    /// Parents that are no longer in the chat stay missing. Fails with
+    /// [`BeeperError::NotFound`] if parents are still missing after [`DEFAULT_MAX_PAGES`]
+    /// pages; the tree keeps what was loaded.
+    pub async fn fetch_missing_parents(&self, chat_id: &str, tree: &mut ThreadTree) -> Result<()> {
+        self.fetch_missing_parents_with(chat_id, tree, DEFAULT_MAX_PAGES).await
+    }
+
+    /// Loads the parents missing from `tree`, reading at most `max_pages` pages
+    pub async fn fetch_missing_parents_with(&self, chat_id: &str, tree: &mut ThreadTree, max_pages: usize) -> Result<()> {
+        if tree.missing_parents().is_empty() {
+            return Ok(());
+        }
+        if !self.page_into(chat_id, tree, max_pages, |tree| tree.missing_parents().is_empty()).await? {
+            return Err(pages_exhausted("Missing parents", chat_id, max_pages));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn message(id: &str, sort_key: &str, reply_to: Option<&str>) -> Message {
+        Message {
+            reply_to_id: reply_to.map(str::to_string),
+            ..test_support::message(id, "u1", sort_key, "2025-01-01T10:00:00Z", id)
+        }
+    }
+
+    fn ids<'a>(messages: impl IntoIterator<Item = &'a Message>) -> Vec<&'a str> {
+        messages.into_iter().map(|m| m.id.as_str()).collect()
+    }
+
+    // alert ─┬─ ack ── fixed
+    //        └─ cause
+    // chatter
+    fn tree() -> ThreadTree {
+        ThreadTree::from_messages([
+            message("fixed", "5", Some("ack")),
+            message("cause", "4", Some("alert")),
+            message("chatter", "2", None),
+            message("ack", "3", Some("alert")),
+            message("alert", "1", None),
+        ])
+    }
+
+    #[test]
+    fn test_navigation() {
+        let tree = tree();
+        assert_eq!(ids(tree.ancestors("fixed")), vec!["ack", "alert"]);
+        assert_eq!(tree.root("fixed").unwrap().id, "alert");
+        assert_eq!(tree.root("alert").unwrap().id, "alert");
+        assert_eq!(ids(tree.children("alert")), vec!["ack", "cause"]);
+        assert_eq!(ids(tree.descendants("alert")), vec!["ack", "fixed", "cause"]);
+        assert_eq!(ids(tree.branch("ack")), vec!["alert", "ack", "fixed"]);
+        assert_eq!(ids(tree.roots()), vec!["alert", "chatter"]);
+        assert!(tree.missing_parents().is_empty());
+    }
+
+    #[test]
+    fn test_missing_parents() {
+        let mut tree = ThreadTree::from_messages([message("b", "2", Some("a")), message("c", "3", Some("b"))]);
+        assert_eq!(tree.missing_parents(), vec!["a"]);
+        assert!(!tree.has_ancestry("c"));
+        assert_eq!(tree.root("c").unwrap().id, "b");
+
+        tree.insert(message("a", "1", None));
+        assert!(tree.missing_parents().is_empty());
+        assert!(tree.has_ancestry("c"));
+    }
+
+    #[test]
+    fn test_reply_cycle_terminates() {
+        let tree = ThreadTree::from_messages([message("a", "1", Some("b")), message("b", "2", Some("a"))]);
+        assert_eq!(ids(tree.ancestors("a")), vec!["b"]);
+        assert_eq!(ids(tree.descendants("a")), vec!["b"]);
+        assert!(tree.has_ancestry("a"));
+    }
+
+    #[tokio::test]
+    async fn test_page_limit_gives_not_found() {
+        let client = BeeperClient::new("token", "http://127.0.0.1:9");
+        let err = client.load_thread_with("c1", "a", 0).await.unwrap_err();
+        assert!(matches!(err, BeeperError::NotFound(m) if m.contains("within 0 pages")));
+
+        let mut tree = ThreadTree::from_messages([message("b", "2", Some("a"))]);
+        let err = client.fetch_missing_parents_with("c1", &mut tree, 0).await.unwrap_err();
+        assert!(matches!(err, BeeperError::NotFound(_)));
+    }
+}
@@ -0,0 +1,99 @@
+//! Local copies of the images and files a transcript references
+
+use std::path::{Path, PathBuf};
+use base64::Engine;
+use crate::client::BeeperClient;
+use crate::error::{BeeperError, Result};
+use crate::export::file_url_to_path;
+use crate::models::{Chat, Message};
+
+/// Assets fetched by [`download_assets`]
+#[derive(Debug, Default)]
+pub struct AssetDownloads {
+    /// `src_url` to local file, ready for [`super::TranscriptOptions::with_asset_paths`] or
+    /// [`super::TranscriptOptions::with_embedded_assets`]
+    pub files: Vec<(String, PathBuf)>,
+    /// Assets that could not be downloaded; transcripts keep their original URL
+    pub failed: Vec<(String, BeeperError)>,
+}
+
+/// Downloads the attachments, video posters, and avatars of a transcript through Beeper
+/// Desktop
+///
+/// `mxc://` assets are fetched with [`BeeperClient::download_asset`] and `file://` URLs are
+/// used directly. Remote `http(s)` URLs are left alone. A failed download is recorded in
+/// [`AssetDownloads::failed`] and does not stop the others.
+pub async fn download_assets(client: &BeeperClient, chat: &Chat, messages: &[Message]) -> AssetDownloads {
+    let mut urls: Vec<&str> = Vec::new();
+    let attachments = messages.iter().flat_map(|m| m.attachments.iter().flatten());
+    for url in attachments
+        .flat_map(|a| [a.src_url.as_deref(), a.poster_img.as_deref()])
+        .chain(chat.participants.items.iter().map(|u| u.img_url.as_deref()))
+        .flatten()
+    {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    let mut downloads = AssetDownloads::default();
+    for url in urls {
+        let local = if url.starts_with("file://") {
+            Ok(url.to_string())
+        } else if url.starts_with("mxc://") || url.starts_with("localmxc://") {
+            client.download_asset(url).await.map(|output| output.local_url)
+        } else {
+            continue;
+        };
+        let path = local.and_then(|local| {
+            file_url_to_path(&local).ok_or_else(|| BeeperError::InvalidInput(format!("not a local file: {}", local)))
+        });
+        match path {
+            Ok(path) => downloads.files.push((url.to_string(), path)),
+            Err(e) => downloads.failed.push((url.to_string(), e)),
+        }
+    }
+    downloads
+}
+
+/// Reads a file into a `data:` URI
+pub(crate) fn data_uri(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)?;
+    let mime = mime_type(path);
+    Ok(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes)))
+}
+
+/// MIME type guessed from a file extension
+fn mime_type(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "mp3" => "audio/mpeg",
+        "ogg" | "oga" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_uri() {
+        let path = std::env::temp_dir().join(format!("beeper-asset-{}.png", std::process::id()));
+        std::fs::write(&path, b"hi").unwrap();
+        assert_eq!(data_uri(&path).unwrap(), "data:image/png;base64,aGk=");
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(mime_type(Path::new("a/B.JPG")), "image/jpeg");
+        assert_eq!(mime_type(Path::new("notes")), "application/octet-stream");
+    }
+}
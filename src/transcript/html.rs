@@ -0,0 +1,280 @@
+//! Self-contained HTML transcripts
+
+use std::fmt::Write;
+use crate::models::{Attachment, Chat, Message};
+use super::{
+    chronological, continues_group, find_sender, is_image, is_video, reaction_counts,
+    sender_display_name, snippet, TranscriptOptions,
+};
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Roboto, sans-serif; background: #f5f6f8; color: #1c1e21; margin: 0; }
+header { background: #fff; border-bottom: 1px solid #dde1e6; padding: 16px 24px; }
+header h1 { font-size: 20px; margin: 0; }
+header p { color: #65676b; margin: 4px 0 0; font-size: 13px; }
+main { max-width: 820px; margin: 0 auto; padding: 16px 24px; }
+.group { display: flex; gap: 12px; margin: 14px 0; }
+.avatar { width: 36px; height: 36px; border-radius: 50%; flex: none; object-fit: cover; }
+.initials { display: flex; align-items: center; justify-content: center; background: #c5cae9; color: #283593; font-weight: 600; font-size: 14px; }
+.body { min-width: 0; flex: 1; }
+.sender { font-weight: 600; font-size: 14px; }
+.sender time { color: #8a8d91; font-weight: 400; font-size: 12px; margin-left: 8px; }
+.message { margin: 3px 0; }
+.text { white-space: pre-wrap; word-wrap: break-word; }
+.edited { color: #8a8d91; font-size: 11px; margin-left: 4px; }
+.reply { border-left: 3px solid #b0b3b8; padding: 2px 8px; margin: 2px 0; color: #65676b; font-size: 13px; }
+.attachment img, .attachment video { max-width: 360px; max-height: 360px; border-radius: 8px; display: block; margin: 4px 0; }
+.reactions { margin-top: 2px; }
+.chip { display: inline-block; background: #fff; border: 1px solid #dde1e6; border-radius: 12px; padding: 0 8px; font-size: 12px; margin-right: 4px; }
+"#;
+
+/// Renders a chat transcript as a single self-contained HTML document
+///
+/// Styles are inlined, but attachment and avatar URLs are used as-is, so `mxc://` images
+/// will not load. For a file that can be opened or archived on its own, fetch the assets
+/// with [`super::download_assets`] and embed them with
+/// [`TranscriptOptions::with_embedded_assets`] before calling [`render_html_with_options`].
+pub fn render_html(chat: &Chat, messages: &[Message]) -> String {
+    render_html_with_options(chat, messages, &TranscriptOptions::default())
+}
+
+/// Renders a chat transcript as HTML with custom options
+pub fn render_html_with_options(chat: &Chat, messages: &[Message], options: &TranscriptOptions) -> String {
+    let sorted = chronological(messages);
+    let mut out = String::new();
+
+    let title = escape(&chat.display_name());
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        title, STYLE
+    );
+    let _ = write!(
+        out,
+        "<header><h1>{}</h1><p>{} · {} messages</p></header>\n<main>\n",
+        title,
+        escape(&chat.network),
+        sorted.len()
+    );
+
+    let mut previous: Option<&Message> = None;
+    for message in &sorted {
+        if !continues_group(previous, message) {
+            if previous.is_some() {
+                out.push_str("</div></div>\n");
+            }
+            open_group(&mut out, chat, message, options);
+        }
+        render_message(&mut out, message, &sorted, chat, options);
+        previous = Some(message);
+    }
+    if previous.is_some() {
+        out.push_str("</div></div>\n");
+    }
+
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+fn open_group(out: &mut String, chat: &Chat, message: &Message, options: &TranscriptOptions) {
+    let name = sender_display_name(chat, message);
+    let avatar = match find_sender(chat, message).and_then(|u| u.img_url.as_deref()) {
+        Some(url) => format!("<img class=\"avatar\" src=\"{}\" alt=\"\">", escape(options.resolve_url(url))),
+        None => format!("<div class=\"avatar initials\">{}</div>", escape(&initials(&name))),
+    };
+    let time = options.format_timestamp(&message.timestamp);
+
+    let _ = writeln!(
+        out,
+        "<div class=\"group\">{}<div class=\"body\"><div class=\"sender\">{}<time datetime=\"{}\">{}</time></div>",
+        avatar,
+        escape(&name),
+        escape(&message.timestamp),
+        escape(&time)
+    );
+}
+
+fn render_message(out: &mut String, message: &Message, all: &[&Message], chat: &Chat, options: &TranscriptOptions) {
+    let _ = write!(out, "<div class=\"message\" id=\"msg-{}\">", escape(&message.id));
+
+    if let Some(reply_id) = &message.reply_to_id {
+        match all.iter().find(|m| &m.id == reply_id) {
+            Some(parent) => {
+                let _ = write!(
+                    out,
+                    "<a href=\"#msg-{}\"><div class=\"reply\"><strong>{}</strong> {}</div></a>",
+                    escape(&parent.id),
+                    escape(&sender_display_name(chat, parent)),
+                    escape(&snippet(parent.text.as_deref().unwrap_or("[attachment]"), 120))
+                );
+            }
+            None => out.push_str("<div class=\"reply\">Reply to an earlier message</div>"),
+        }
+    }
+
+    if let Some(text) = message.text.as_deref().filter(|t| !t.is_empty()) {
+        let _ = write!(out, "<span class=\"text\">{}</span>", escape(text));
+    }
+    if message.is_edited.unwrap_or(false) {
+        out.push_str("<span class=\"edited\">(edited)</span>");
+    }
+
+    for attachment in message.attachments.iter().flatten() {
+        render_attachment(out, attachment, options);
+    }
+
+    let reactions = reaction_counts(message);
+    if !reactions.is_empty() {
+        out.push_str("<div class=\"reactions\">");
+        for (key, count) in reactions {
+            let _ = write!(out, "<span class=\"chip\">{} {}</span>", escape(key), count);
+        }
+        out.push_str("</div>");
+    }
+
+    out.push_str("</div>\n");
+}
+
+fn render_attachment(out: &mut String, attachment: &Attachment, options: &TranscriptOptions) {
+    let name = attachment.file_name.as_deref().unwrap_or(&attachment.typ);
+    let Some(location) = options.asset_location(attachment) else {
+        let _ = write!(out, "<div class=\"attachment\">[{}]</div>", escape(name));
+        return;
+    };
+    let location = escape(location);
+
+    if is_image(attachment) {
+        let _ = write!(
+            out,
+            "<div class=\"attachment\"><a href=\"{0}\"><img src=\"{0}\" alt=\"{1}\" loading=\"lazy\"></a></div>",
+            location,
+            escape(name)
+        );
+    } else if is_video(attachment) {
+        let poster = attachment
+            .poster_img
+            .as_deref()
+            .map(|p| format!(" poster=\"{}\"", escape(options.resolve_url(p))))
+            .unwrap_or_default();
+        let _ = write!(
+            out,
+            "<div class=\"attachment\"><video controls src=\"{}\"{}></video></div>",
+            location, poster
+        );
+    } else {
+        let _ = write!(
+            out,
+            "<div class=\"attachment\"><a href=\"{}\">📎 {}</a></div>",
+            location,
+            escape(name)
+        );
+    }
+}
+
+fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .filter_map(|w| w.chars().find(|c| c.is_alphanumeric()))
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Escapes text for use in HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{chat, image, message, reaction, user};
+
+    #[test]
+    fn test_escapes_content() {
+        let chat = chat(vec![user("u1", "Alice", None)]);
+        let html = render_html(&chat, &[message("m1", "u1", "1", "2025-01-01T10:00:00Z", "<script>x</script>")]);
+        assert!(html.contains("&lt;script&gt;x&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<title>Team &lt;Ops&gt;</title>"));
+    }
+
+    #[test]
+    fn test_groups_consecutive_messages() {
+        let chat = chat(vec![
+            user("u1", "Alice Smith", Some("https://example.com/a.png")),
+            user("u2", "Bob", None),
+        ]);
+        let html = render_html(&chat, &[
+            message("m3", "u2", "3", "2025-01-01T10:02:00Z", "third"),
+            message("m1", "u1", "1", "2025-01-01T10:00:00Z", "first"),
+            message("m2", "u1", "2", "2025-01-01T10:01:00Z", "second"),
+        ]);
+
+        assert_eq!(html.matches("class=\"group\"").count(), 2);
+        assert!(html.contains("src=\"https://example.com/a.png\""));
+        assert!(html.contains("<div class=\"avatar initials\">B</div>"));
+        assert!(html.find("first").unwrap() < html.find("second").unwrap());
+        assert!(html.find("second").unwrap() < html.find("third").unwrap());
+    }
+
+    #[test]
+    fn test_reply_reactions_edits_and_images() {
+        let chat = chat(vec![user("u1", "Alice", None), user("u2", "Bob", None)]);
+        let parent = message("m1", "u1", "1", "2025-01-01T10:00:00Z", "Is prod down?");
+        let mut reply = message("m2", "u2", "2", "2025-01-01T10:10:00Z", "Yes");
+        reply.reply_to_id = Some("m1".to_string());
+        reply.is_edited = Some(true);
+        reply.reactions = Some(vec![reaction("r1", "👍", "u1"), reaction("r2", "👍", "u2")]);
+        reply.attachments = Some(vec![image("mxc://example.com/graph")]);
+
+        let options = TranscriptOptions::new().with_asset_path("mxc://example.com/graph", "attachments/graph.png");
+        let html = render_html_with_options(&chat, &[parent, reply], &options);
+
+        assert!(html.contains("<strong>Alice</strong> Is prod down?"));
+        assert!(html.contains("(edited)"));
+        assert!(html.contains("<span class=\"chip\">👍 2</span>"));
+        assert!(html.contains("<img src=\"attachments/graph.png\""));
+    }
+
+    #[test]
+    fn test_embedded_assets() {
+        let dir = std::env::temp_dir().join(format!("beeper-html-assets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.png"), b"hi").unwrap();
+
+        let chat = chat(vec![user("u1", "Alice", Some("mxc://example.com/avatar"))]);
+        let mut msg = message("m1", "u1", "1", "2025-01-01T10:00:00Z", "look");
+        msg.attachments = Some(vec![image("mxc://example.com/graph")]);
+        let (options, unreadable) = TranscriptOptions::new().with_embedded_assets([
+            ("mxc://example.com/avatar", dir.join("a.png")),
+            ("mxc://example.com/graph", dir.join("a.png")),
+            ("mxc://example.com/gone", dir.join("gone.png")),
+        ]);
+        let html = render_html_with_options(&chat, &[msg], &options);
+
+        assert!(!html.contains("mxc://"));
+        assert_eq!(html.matches("src=\"data:image/png;base64,aGk=\"").count(), 2);
+        assert_eq!(unreadable.len(), 1);
+        assert_eq!(unreadable[0].0, "mxc://example.com/gone");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_reply_parent() {
+        let chat = chat(vec![]);
+        let mut msg = message("m2", "u2", "2", "2025-01-01T10:10:00Z", "Yes");
+        msg.reply_to_id = Some("gone".to_string());
+        assert!(render_html(&chat, &[msg]).contains("Reply to an earlier message"));
+    }
+}
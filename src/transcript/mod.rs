@@ -0,0 +1,308 @@
+//! Human-readable chat transcripts
+//!
+//! Renders a [`Chat`] and its [`Message`]s into formats meant for people rather than
+//! machines. Messages may be passed in any order; they are sorted by sort key before
+//! rendering.
+
+mod assets;
+mod html;
+mod text;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use chrono::{DateTime, FixedOffset};
+use crate::error::{BeeperError, Result};
+use crate::models::{compare_sort_keys, Attachment, Chat, MediaType, Message, User};
+
+pub use assets::{download_assets, AssetDownloads};
+#[cfg(feature = "tz")]
+pub use chrono_tz::Tz;
+pub use html::{render_html, render_html_with_options};
+pub use text::{render_markdown, render_markdown_with_options, render_plaintext, render_plaintext_with_options};
+
+/// Default `chrono` format string for message timestamps
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Consecutive messages from the same sender closer than this are grouped together
+const GROUP_WINDOW_SECONDS: i64 = 5 * 60;
+
+/// Time zone transcript timestamps are shown in, also used by rule time windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timezone {
+    /// A fixed UTC offset such as `+02:00`
+    Offset(FixedOffset),
+    /// A named zone such as `Europe/Berlin`, following its daylight saving rules (`tz`
+    /// feature)
+    #[cfg(feature = "tz")]
+    Named(Tz),
+}
+
+impl Default for Timezone {
+    fn default() -> Self {
+        Timezone::Offset(FixedOffset::east_opt(0).expect("UTC offset is valid"))
+    }
+}
+
+impl From<FixedOffset> for Timezone {
+    fn from(offset: FixedOffset) -> Self {
+        Timezone::Offset(offset)
+    }
+}
+
+#[cfg(feature = "tz")]
+impl From<Tz> for Timezone {
+    fn from(tz: Tz) -> Self {
+        Timezone::Named(tz)
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = BeeperError;
+
+    /// Parses an offset like `+02:00`, or with the `tz` feature a zone name like
+    /// `Europe/Berlin` or `UTC`
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(offset) = FixedOffset::from_str(s) {
+            return Ok(Timezone::Offset(offset));
+        }
+        #[cfg(feature = "tz")]
+        if let Ok(tz) = Tz::from_str(s) {
+            return Ok(Timezone::Named(tz));
+        }
+        Err(BeeperError::InvalidInput(format!("unknown time zone `{}`", s)))
+    }
+}
+
+impl Timezone {
+    /// Local date and time of `at` in this zone
+    #[cfg(feature = "rules")]
+    pub(crate) fn local(&self, at: DateTime<chrono::Utc>) -> chrono::NaiveDateTime {
+        match self {
+            Timezone::Offset(offset) => at.with_timezone(offset).naive_local(),
+            #[cfg(feature = "tz")]
+            Timezone::Named(tz) => at.with_timezone(tz).naive_local(),
+        }
+    }
+}
+
+/// Options shared by all transcript renderers
+#[derive(Debug, Clone)]
+pub struct TranscriptOptions {
+    /// Maps an attachment's `src_url` to a local path or URL to use instead
+    pub asset_paths: HashMap<String, String>,
+    /// `chrono` format string used for timestamps
+    pub timestamp_format: String,
+    /// Time zone timestamps are shown in
+    pub timezone: Timezone,
+}
+
+impl Default for TranscriptOptions {
+    fn default() -> Self {
+        Self {
+            asset_paths: HashMap::new(),
+            timestamp_format: DEFAULT_TIMESTAMP_FORMAT.to_string(),
+            timezone: Timezone::default(),
+        }
+    }
+}
+
+impl TranscriptOptions {
+    /// Creates default options: UTC timestamps formatted as `2025-01-31 14:05`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses `path` wherever an attachment with `src_url` is referenced
+    pub fn with_asset_path(mut self, src_url: impl Into<String>, path: impl Into<String>) -> Self {
+        self.asset_paths.insert(src_url.into(), path.into());
+        self
+    }
+
+    /// Uses several `src_url` to path mappings at once, e.g. from
+    /// [`crate::export::read_attachment_index`]
+    pub fn with_asset_paths<I, K, V>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.asset_paths.extend(paths.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Embeds local files as `data:` URIs in place of their `src_url`, e.g. the files from
+    /// [`download_assets`], so an HTML transcript shows them without any other files
+    ///
+    /// A file that cannot be read keeps its original URL and is returned with the error, so
+    /// one missing file does not stop the rest.
+    pub fn with_embedded_assets<I, K>(mut self, files: I) -> (Self, Vec<(String, BeeperError)>)
+    where
+        I: IntoIterator<Item = (K, PathBuf)>,
+        K: Into<String>,
+    {
+        let mut unreadable = Vec::new();
+        for (src_url, path) in files {
+            match assets::data_uri(&path) {
+                Ok(uri) => {
+                    self.asset_paths.insert(src_url.into(), uri);
+                }
+                Err(e) => unreadable.push((src_url.into(), e)),
+            }
+        }
+        (self, unreadable)
+    }
+
+    /// Sets the `chrono` format string used for timestamps
+    pub fn with_timestamp_format(mut self, format: impl Into<String>) -> Self {
+        self.timestamp_format = format.into();
+        self
+    }
+
+    /// Shows timestamps in the given time zone instead of UTC
+    pub fn with_timezone(mut self, timezone: impl Into<Timezone>) -> Self {
+        self.timezone = timezone.into();
+        self
+    }
+
+    /// Formats an ISO 8601 timestamp, falling back to the raw string if it cannot be parsed
+    pub(crate) fn format_timestamp(&self, timestamp: &str) -> String {
+        let Some(ts) = parse_timestamp(timestamp) else {
+            return timestamp.to_string();
+        };
+        match self.timezone {
+            Timezone::Offset(offset) => ts.with_timezone(&offset).format(&self.timestamp_format).to_string(),
+            #[cfg(feature = "tz")]
+            Timezone::Named(tz) => ts.with_timezone(&tz).format(&self.timestamp_format).to_string(),
+        }
+    }
+
+    /// Resolves the location to use for an attachment, if it has one
+    pub(crate) fn asset_location<'a>(&'a self, attachment: &'a Attachment) -> Option<&'a str> {
+        attachment.src_url.as_deref().map(|src| self.resolve_url(src))
+    }
+
+    /// The local copy of `url` if there is one, otherwise `url` itself
+    pub(crate) fn resolve_url<'a>(&'a self, url: &'a str) -> &'a str {
+        self.asset_paths.get(url).map(String::as_str).unwrap_or(url)
+    }
+}
+
+pub(crate) fn parse_timestamp(timestamp: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(timestamp).ok()
+}
+
+/// True if `message` should be shown under the same sender heading as `previous`
+pub(crate) fn continues_group(previous: Option<&Message>, message: &Message) -> bool {
+    let Some(previous) = previous else {
+        return false;
+    };
+    if previous.sender_id != message.sender_id {
+        return false;
+    }
+    match (parse_timestamp(&previous.timestamp), parse_timestamp(&message.timestamp)) {
+        (Some(a), Some(b)) => (b - a).num_seconds().abs() < GROUP_WINDOW_SECONDS,
+        _ => false,
+    }
+}
+
+/// Shortens text to at most `max` characters on a single line
+pub(crate) fn snippet(text: &str, max: usize) -> String {
+    let text = text.replace('\n', " ");
+    if text.chars().count() <= max {
+        text
+    } else {
+        format!("{}…", text.chars().take(max).collect::<String>())
+    }
+}
+
+/// Returns messages ordered oldest first
+pub(crate) fn chronological(messages: &[Message]) -> Vec<&Message> {
+    let mut sorted: Vec<&Message> = messages.iter().collect();
+    sorted.sort_by(|a, b| compare_sort_keys(&a.sort_key, &b.sort_key));
+    sorted
+}
+
+/// Finds the chat participant who sent a message
+pub(crate) fn find_sender<'a>(chat: &'a Chat, message: &Message) -> Option<&'a User> {
+    chat.participants.items.iter().find(|u| u.id == message.sender_id)
+}
+
+/// Best display name for a message's sender
+pub(crate) fn sender_display_name(chat: &Chat, message: &Message) -> String {
+    if let Some(name) = &message.sender_name {
+        return name.clone();
+    }
+    find_sender(chat, message)
+        .and_then(|u| u.full_name.clone().or_else(|| u.username.clone()))
+        .unwrap_or_else(|| message.sender_id.clone())
+}
+
+/// Counts reactions by key, in first-seen order
+pub(crate) fn reaction_counts(message: &Message) -> Vec<(&str, usize)> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for reaction in message.reactions.iter().flatten() {
+        match counts.iter_mut().find(|(key, _)| *key == reaction.reaction_key) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&reaction.reaction_key, 1)),
+        }
+    }
+    counts
+}
+
+/// True if the attachment should be shown inline as an image
+pub(crate) fn is_image(attachment: &Attachment) -> bool {
+    MediaType::of(attachment) == MediaType::Image
+}
+
+/// True if the attachment is a video
+pub(crate) fn is_video(attachment: &Attachment) -> bool {
+    MediaType::of(attachment) == MediaType::Video
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{image, message, reaction};
+
+    #[test]
+    fn test_reaction_counts() {
+        let mut msg = message("m1", "u1", "1", "2025-01-01T00:00:00Z", "hi");
+        msg.reactions = Some(vec![
+            reaction("r1", "👍", "u1"),
+            reaction("r2", "❤️", "u2"),
+            reaction("r3", "👍", "u3"),
+        ]);
+        assert_eq!(super::reaction_counts(&msg), vec![("👍", 2), ("❤️", 1)]);
+    }
+
+    #[test]
+    fn test_format_timestamp_with_timezone() {
+        let options = super::TranscriptOptions::new()
+            .with_timezone(chrono::FixedOffset::east_opt(3 * 3600).unwrap())
+            .with_timestamp_format("%d.%m.%Y %H:%M");
+        assert_eq!(options.format_timestamp("2025-01-31T22:30:00Z"), "01.02.2025 01:30");
+        assert_eq!(options.format_timestamp("not a date"), "not a date");
+    }
+
+    #[cfg(feature = "tz")]
+    #[test]
+    fn test_named_timezone_follows_dst() {
+        let berlin: super::Timezone = "Europe/Berlin".parse().unwrap();
+        let options = super::TranscriptOptions::new().with_timezone(berlin).with_timestamp_format("%H:%M %Z");
+        assert_eq!(options.format_timestamp("2025-01-15T12:00:00Z"), "13:00 CET");
+        assert_eq!(options.format_timestamp("2025-07-15T12:00:00Z"), "14:00 CEST");
+
+        assert_eq!(
+            "+02:00".parse::<super::Timezone>().unwrap(),
+            super::Timezone::Offset(chrono::FixedOffset::east_opt(7200).unwrap())
+        );
+        assert!("Mars/Olympus".parse::<super::Timezone>().is_err());
+    }
+
+    #[test]
+    fn test_asset_location_prefers_local_path() {
+        let options = super::TranscriptOptions::new().with_asset_path("mxc://a/b", "attachments/b.png");
+        assert_eq!(options.asset_location(&image("mxc://a/b")), Some("attachments/b.png"));
+        assert_eq!(options.asset_location(&image("https://x/y.png")), Some("https://x/y.png"));
+    }
+}
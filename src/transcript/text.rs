@@ -0,0 +1,249 @@
+//! Markdown and plaintext transcripts
+
+use std::fmt::Write;
+use crate::models::{escape_markdown, Attachment, Chat, Message};
+use super::{
+    chronological, continues_group, is_image, reaction_counts, sender_display_name, snippet,
+    TranscriptOptions,
+};
+
+/// Renders a chat transcript as Markdown
+///
+/// Suitable for pasting into tickets and postmortems. Message text is escaped so it shows
+/// exactly as written, attachments become links, and reactions are summarized under each
+/// message.
+pub fn render_markdown(chat: &Chat, messages: &[Message]) -> String {
+    render_markdown_with_options(chat, messages, &TranscriptOptions::default())
+}
+
+/// Renders a chat transcript as Markdown with custom options
+pub fn render_markdown_with_options(chat: &Chat, messages: &[Message], options: &TranscriptOptions) -> String {
+    let sorted = chronological(messages);
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# {}\n", escape_line(&chat.display_name()));
+    let _ = writeln!(out, "_{} · {} messages_", escape_line(&chat.network), sorted.len());
+
+    let mut previous: Option<&Message> = None;
+    for message in &sorted {
+        if !continues_group(previous, message) {
+            let _ = writeln!(
+                out,
+                "\n**{}** · {}",
+                escape_line(&sender_display_name(chat, message)),
+                options.format_timestamp(&message.timestamp)
+            );
+        }
+        out.push('\n');
+
+        if let Some(parent) = reply_parent(message, &sorted) {
+            let _ = writeln!(
+                out,
+                "> **{}**: {}",
+                escape_line(&sender_display_name(chat, parent)),
+                escape_line(&snippet(parent.text.as_deref().unwrap_or("[attachment]"), 120))
+            );
+            out.push('\n');
+        } else if message.reply_to_id.is_some() {
+            out.push_str("> _Reply to an earlier message_\n\n");
+        }
+
+        let mut lines: Vec<String> = message
+            .text
+            .as_deref()
+            .filter(|t| !t.is_empty())
+            .map(|t| t.lines().map(escape_line).collect())
+            .unwrap_or_default();
+        if message.is_edited.unwrap_or(false) {
+            match lines.last_mut() {
+                Some(last) => last.push_str(" _(edited)_"),
+                None => lines.push("_(edited)_".to_string()),
+            }
+        }
+        for attachment in message.attachments.iter().flatten() {
+            lines.push(markdown_attachment(attachment, options));
+        }
+        if let Some(summary) = reaction_summary(message) {
+            lines.push(format!("_Reactions: {}_", summary));
+        }
+
+        let _ = writeln!(out, "{}", lines.join("  \n"));
+        previous = Some(message);
+    }
+
+    out
+}
+
+/// Renders a chat transcript as plain text, one message per line
+pub fn render_plaintext(chat: &Chat, messages: &[Message]) -> String {
+    render_plaintext_with_options(chat, messages, &TranscriptOptions::default())
+}
+
+/// Renders a chat transcript as plain text with custom options
+pub fn render_plaintext_with_options(chat: &Chat, messages: &[Message], options: &TranscriptOptions) -> String {
+    let sorted = chronological(messages);
+    let mut out = String::new();
+
+    let heading = format!("{} ({})", chat.display_name(), chat.network);
+    let _ = writeln!(out, "{}", heading);
+    let _ = writeln!(out, "{}\n", "=".repeat(heading.chars().count()));
+
+    for message in &sorted {
+        let text = message.text.as_deref().unwrap_or("");
+        let edited = if message.is_edited.unwrap_or(false) { " (edited)" } else { "" };
+        let _ = writeln!(
+            out,
+            "[{}] {}: {}{}",
+            options.format_timestamp(&message.timestamp),
+            sender_display_name(chat, message),
+            text.replace('\n', "\n    "),
+            edited
+        );
+
+        if let Some(parent) = reply_parent(message, &sorted) {
+            let _ = writeln!(
+                out,
+                "    ↳ in reply to {}: {}",
+                sender_display_name(chat, parent),
+                snippet(parent.text.as_deref().unwrap_or("[attachment]"), 80)
+            );
+        } else if message.reply_to_id.is_some() {
+            out.push_str("    ↳ in reply to an earlier message\n");
+        }
+
+        for attachment in message.attachments.iter().flatten() {
+            let name = attachment_name(attachment);
+            match options.asset_location(attachment) {
+                Some(location) => {
+                    let _ = writeln!(out, "    [attachment: {} → {}]", name, location);
+                }
+                None => {
+                    let _ = writeln!(out, "    [attachment: {}]", name);
+                }
+            }
+        }
+        if let Some(summary) = reaction_summary(message) {
+            let _ = writeln!(out, "    [reactions: {}]", summary);
+        }
+    }
+
+    out
+}
+
+fn reply_parent<'a>(message: &Message, all: &[&'a Message]) -> Option<&'a Message> {
+    let reply_id = message.reply_to_id.as_ref()?;
+    all.iter().find(|m| &m.id == reply_id).copied()
+}
+
+fn attachment_name(attachment: &Attachment) -> &str {
+    attachment.file_name.as_deref().unwrap_or(&attachment.typ)
+}
+
+fn markdown_attachment(attachment: &Attachment, options: &TranscriptOptions) -> String {
+    let name = escape_line(attachment_name(attachment));
+    let icon = if is_image(attachment) { "🖼" } else { "📎" };
+    match options.asset_location(attachment) {
+        Some(location) => format!("{} [{}](<{}>)", icon, name, location),
+        None => format!("{} {}", icon, name),
+    }
+}
+
+/// Escapes a single line so Markdown shows it literally, including HTML tags and markers
+/// that only apply at the start of a line
+fn escape_line(line: &str) -> String {
+    let mut escaped = escape_markdown(line).replace('<', "\\<");
+    if escaped.starts_with(['#', '-', '+', '|']) {
+        escaped.insert(0, '\\');
+    } else if let Some((number, _)) = escaped.split_once('.') {
+        if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+            escaped.insert(number.len(), '\\');
+        }
+    }
+    escaped
+}
+
+fn reaction_summary(message: &Message) -> Option<String> {
+    let counts = reaction_counts(message);
+    if counts.is_empty() {
+        return None;
+    }
+    Some(
+        counts
+            .iter()
+            .map(|(key, count)| format!("{} {}", key, count))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use crate::test_support::{chat, image, message, reaction, user};
+
+    fn conversation() -> Vec<Message> {
+        let parent = message("m1", "u1", "1", "2025-01-01T10:00:00Z", "Is prod down?");
+        let mut reply = message("m2", "u2", "2", "2025-01-01T10:10:00Z", "Yes\nrolling back");
+        reply.reply_to_id = Some("m1".to_string());
+        reply.is_edited = Some(true);
+        reply.reactions = Some(vec![reaction("r1", "👍", "u1"), reaction("r2", "👍", "u3")]);
+        reply.attachments = Some(vec![image("mxc://example.com/graph")]);
+        vec![reply, parent]
+    }
+
+    #[test]
+    fn test_markdown_transcript() {
+        let chat = chat(vec![user("u1", "Alice", None), user("u2", "Bob", None)]);
+        let options = TranscriptOptions::new().with_asset_path("mxc://example.com/graph", "attachments/graph.png");
+        let md = render_markdown_with_options(&chat, &conversation(), &options);
+
+        assert!(md.starts_with("# Team \\<Ops\\>\n"));
+        assert!(md.contains("**Alice** · 2025-01-01 10:00"));
+        assert!(md.contains("> **Alice**: Is prod down?"));
+        assert!(md.contains("Yes  \nrolling back _(edited)_"));
+        assert!(md.contains("🖼 [photo.png](<attachments/graph.png>)"));
+        assert!(md.contains("_Reactions: 👍 2_"));
+        assert!(md.find("Is prod down?").unwrap() < md.find("rolling back").unwrap());
+    }
+
+    #[test]
+    fn test_plaintext_transcript_with_timezone() {
+        let chat = chat(vec![user("u1", "Alice", None), user("u2", "Bob", None)]);
+        let options = TranscriptOptions::new()
+            .with_timezone(FixedOffset::west_opt(5 * 3600).unwrap())
+            .with_timestamp_format("%H:%M")
+            .with_asset_path("mxc://example.com/graph", "/tmp/export/graph.png");
+        let text = render_plaintext_with_options(&chat, &conversation(), &options);
+
+        assert!(text.starts_with("Team <Ops> (WhatsApp)\n=====================\n"));
+        assert!(text.contains("[05:00] Alice: Is prod down?\n"));
+        assert!(text.contains("[05:10] Bob: Yes\n    rolling back (edited)\n"));
+        assert!(text.contains("    ↳ in reply to Alice: Is prod down?\n"));
+        assert!(text.contains("    [attachment: photo.png → /tmp/export/graph.png]\n"));
+        assert!(text.contains("    [reactions: 👍 2]\n"));
+    }
+
+    #[test]
+    fn test_markdown_escapes_text() {
+        let chat = chat(vec![user("u1", "Alice", None)]);
+        let md = render_markdown(&chat, &[message(
+            "m1",
+            "u1",
+            "1",
+            "2025-01-01T10:00:00Z",
+            "Disk *full* on <db>\n# not a heading\n1. not a list",
+        )]);
+        assert!(md.contains("Disk \\*full\\* on \\<db\\>  \n\\# not a heading  \n1\\. not a list"));
+    }
+
+    #[test]
+    fn test_markdown_groups_messages_from_same_sender() {
+        let chat = chat(vec![user("u1", "Alice", None)]);
+        let md = render_markdown(&chat, &[
+            message("m1", "u1", "1", "2025-01-01T10:00:00Z", "one"),
+            message("m2", "u1", "2", "2025-01-01T10:01:00Z", "two"),
+        ]);
+        assert_eq!(md.matches("**Alice**").count(), 1);
+    }
+}
@@ -0,0 +1,51 @@
+//! Integration tests for the blocking client
+
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use beeper_desktop_api::blocking::BeeperClient;
+use common::{get_test_token, get_test_base_url, should_run_integration_tests};
+
+#[test]
+fn test_blocking_get_accounts() {
+    if !should_run_integration_tests() {
+        println!("Skipping test_blocking_get_accounts - set BEEPER_TEST_TOKEN to run");
+        return;
+    }
+
+    let token = get_test_token().expect("BEEPER_TEST_TOKEN not set");
+    let base_url = get_test_base_url();
+    let client = BeeperClient::new(token, base_url);
+
+    match client.get_accounts() {
+        Ok(accounts) => {
+            println!("✓ Successfully retrieved {} accounts", accounts.len());
+            assert!(!accounts.is_empty(), "Expected at least one account");
+        }
+        Err(e) => {
+            panic!("Failed to get accounts: {}", e);
+        }
+    }
+}
+
+#[test]
+fn test_blocking_list_chats() {
+    if !should_run_integration_tests() {
+        println!("Skipping test_blocking_list_chats - set BEEPER_TEST_TOKEN to run");
+        return;
+    }
+
+    let token = get_test_token().expect("BEEPER_TEST_TOKEN not set");
+    let base_url = get_test_base_url();
+    let client = BeeperClient::new(token, base_url);
+
+    match client.list_chats(None, None) {
+        Ok(output) => {
+            println!("✓ Successfully retrieved {} chats", output.items.len());
+        }
+        Err(e) => {
+            panic!("Failed to list chats: {}", e);
+        }
+    }
+}
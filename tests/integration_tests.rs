@@ -51,6 +51,8 @@ fn test_send_message_input_creation() {
     let input = SendMessageInput {
         text: "Hello, world!".to_string(),
         reply_to_id: None,
+        idempotency_key: None,
+        mentions: Vec::new(),
     };
     assert_eq!(input.text, "Hello, world!");
     assert_eq!(input.reply_to_id, None);
@@ -61,6 +63,8 @@ fn test_send_message_input_with_reply() {
     let input = SendMessageInput {
         text: "Reply message".to_string(),
         reply_to_id: Some("msg-123".to_string()),
+        idempotency_key: None,
+        mentions: Vec::new(),
     };
     assert_eq!(input.text, "Reply message");
     assert_eq!(input.reply_to_id, Some("msg-123".to_string()));
@@ -200,12 +204,13 @@ fn test_chat_model_creation() {
         is_archived: false,
         is_muted: false,
         is_pinned: false,
+        is_low_priority: None,
         preview: None,
     };
     assert_eq!(chat.id, "chat-1");
     assert_eq!(chat.title, "Alice");
     assert_eq!(chat.chat_type, "single");
-    assert_eq!(chat.is_archived, false);
+    assert!(!chat.is_archived);
 }
 
 #[test]
@@ -249,6 +254,7 @@ fn test_group_chat_model() {
         is_archived: false,
         is_muted: false,
         is_pinned: false,
+        is_low_priority: None,
         preview: None,
     };
     assert_eq!(chat.chat_type, "group");
@@ -268,7 +274,6 @@ async fn test_get_accounts_integration() {
         Ok(accounts) => {
             // Test passed if we got a valid response
             println!("Got {} accounts", accounts.len());
-            assert!(true);
         }
         Err(e) => {
             println!("Error getting accounts: {}", e);
@@ -288,7 +293,6 @@ async fn test_list_chats_integration() {
     match client.list_chats(None, None).await {
         Ok(output) => {
             println!("Got {} chats", output.items.len());
-            assert!(true);
         }
         Err(e) => {
             println!("Error listing chats: {}", e);
@@ -308,7 +312,6 @@ async fn test_search_messages_integration() {
     match client.search_messages("test", None, None).await {
         Ok(output) => {
             println!("Found {} messages", output.items.len());
-            assert!(true);
         }
         Err(e) => {
             println!("Error searching messages: {}", e);
@@ -328,7 +331,6 @@ async fn test_search_chats_integration() {
     match client.search_chats("alice", None, None).await {
         Ok(output) => {
             println!("Found {} chats", output.items.len());
-            assert!(true);
         }
         Err(e) => {
             println!("Error searching chats: {}", e);